@@ -1,12 +1,19 @@
 use std::{cmp::Ordering, ops::Div};
 
-use ndarray::{Array1, Array2};
-use num_traits::{Float, One, Zero};
+use ndarray::{Array1, Array2, ScalarOperand};
+use num_traits::Float;
+
+use crate::autograd::Var;
 
 pub trait Activation<T> {
+    /// Identifier of the activation function in saved models.
+    fn name(&self) -> &'static str;
+
     fn call(&self, a: &Array2<T>) -> Array2<T>;
 
-    fn call_deriv(&self, a: &Array2<T>) -> Array2<T>;
+    /// Records the activation on the tape of `a`, like [Activation::call]
+    /// but differentiable, see [autograd](crate::autograd).
+    fn forward<'t>(&self, a: Var<'t, T>) -> Var<'t, T>;
 }
 
 /// Rectified Linear Unit (ReLU).
@@ -18,8 +25,12 @@ pub struct Relu;
 
 impl<T> Activation<T> for Relu
 where
-    T: Zero + PartialOrd + Clone + One,
+    T: Float + ScalarOperand,
 {
+    fn name(&self) -> &'static str {
+        "relu"
+    }
+
     fn call(&self, a: &Array2<T>) -> Array2<T> {
         a.mapv(|x| match T::zero().partial_cmp(&x) {
             None => T::zero(),
@@ -30,14 +41,8 @@ where
         })
     }
 
-    fn call_deriv(&self, a: &Array2<T>) -> Array2<T> {
-        a.mapv(|x| match T::zero().partial_cmp(&x) {
-            None => T::zero(),
-            Some(ordering) => match ordering {
-                Ordering::Less | Ordering::Equal => T::one(),
-                Ordering::Greater => T::zero(),
-            },
-        })
+    fn forward<'t>(&self, a: Var<'t, T>) -> Var<'t, T> {
+        a.relu()
     }
 }
 
@@ -0,0 +1,36 @@
+//! Reverse-mode automatic differentiation.
+//!
+//! Operations on [Var]s are recorded on a [Tape]. [Tape::backward] walks the
+//! recorded operations in reverse order and returns the [Gradients] of the
+//! output with respect to every recorded value, so layers and losses only
+//! have to define their forward pass.
+//!
+//! # Examples
+//!
+//! ```
+//! use ndarray::{arr1, arr2};
+//! use robit::autograd::Tape;
+//!
+//! let tape = Tape::new();
+//! let x = tape.constant(arr2(&[[1.0, 2.0]]));
+//! let w = tape.var(arr2(&[[0.5], [-1.0]]));
+//! let b = tape.var(arr1(&[0.25]));
+//!
+//! let loss = (x.matmul(w) + b).square().sum();
+//! let gradients = tape.backward(loss);
+//!
+//! // d/dw (x·w + b)² = 2 (x·w + b) xᵀ
+//! assert_eq!(
+//!     arr2(&[[-2.5], [-5.0]]).into_dyn(),
+//!     gradients[w]
+//! );
+//! assert_eq!(arr1(&[-2.5]).into_dyn(), gradients[b]);
+//! ```
+
+mod ops;
+mod tape;
+
+pub use tape::{Gradients, Tape, Var};
+
+#[cfg(test)]
+pub(crate) use ops::tests::check;
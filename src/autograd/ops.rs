@@ -0,0 +1,712 @@
+use std::ops::{Add, Div, Mul, Neg, Range, Sub};
+
+use ndarray::{arr0, Array2, Array3, ArrayD, ArrayView2, Axis, IxDyn, ScalarOperand, Slice};
+use num_traits::Float;
+
+use super::Var;
+
+impl<'t, T> Var<'t, T>
+where
+    T: Float + ScalarOperand + 'static,
+{
+    /// Records an operation with a single input.
+    fn unary<F>(self, value: ArrayD<T>, backward: F) -> Var<'t, T>
+    where
+        F: Fn(&ArrayD<T>, &ArrayD<T>, &ArrayD<T>) -> ArrayD<T> + 'static,
+    {
+        self.tape.op(&[self], value, move |grad, inputs, output| {
+            vec![backward(grad, inputs[0], output)]
+        })
+    }
+
+    /// Records an elementwise operation with two inputs that are broadcast
+    /// to a common shape. `backward` returns the gradients with respect to
+    /// the broadcast inputs.
+    fn binary<F>(self, other: Var<'t, T>, value: ArrayD<T>, backward: F) -> Var<'t, T>
+    where
+        F: Fn(&ArrayD<T>, &ArrayD<T>, &ArrayD<T>) -> (ArrayD<T>, ArrayD<T>) + 'static,
+    {
+        self.tape.assert_recorded(other);
+
+        self.tape.op(&[self, other], value, move |grad, inputs, _| {
+            let (a, b) = backward(grad, inputs[0], inputs[1]);
+
+            vec![
+                unbroadcast(a, inputs[0].shape()),
+                unbroadcast(b, inputs[1].shape()),
+            ]
+        })
+    }
+
+    fn map(self, f: impl Fn(T) -> T) -> ArrayD<T> {
+        self.with_value(|a| a.mapv(f))
+    }
+
+    /// Multiplies every value with `factor`.
+    pub fn scale(self, factor: T) -> Var<'t, T> {
+        self.unary(self.map(|a| a * factor), move |grad, _, _| grad * factor)
+    }
+
+    /// Adds `value` to every value.
+    pub fn add_scalar(self, value: T) -> Var<'t, T> {
+        self.unary(self.map(|a| a + value), |grad, _, _| grad.clone())
+    }
+
+    pub fn square(self) -> Var<'t, T> {
+        self.unary(self.map(|a| a * a), |grad, a, _| grad * &a.mapv(|a| a + a))
+    }
+
+    pub fn powi(self, n: i32) -> Var<'t, T> {
+        self.unary(self.map(|a| a.powi(n)), move |grad, a, _| {
+            grad * &a.mapv(|a| T::from(n).unwrap() * a.powi(n - 1))
+        })
+    }
+
+    pub fn sqrt(self) -> Var<'t, T> {
+        self.unary(self.map(T::sqrt), |grad, _, output| {
+            grad / &output.mapv(|y| y + y)
+        })
+    }
+
+    pub fn exp(self) -> Var<'t, T> {
+        self.unary(self.map(T::exp), |grad, _, output| grad * output)
+    }
+
+    /// Natural logarithm.
+    pub fn ln(self) -> Var<'t, T> {
+        self.unary(self.map(T::ln), |grad, a, _| grad / a)
+    }
+
+    /// Rectified linear unit, `max(a, 0)`.
+    pub fn relu(self) -> Var<'t, T> {
+        self.unary(self.map(|a| a.max(T::zero())), |grad, a, _| {
+            grad * &a.mapv(|a| if a > T::zero() { T::one() } else { T::zero() })
+        })
+    }
+
+    /// Logistic sigmoid, `1 / (1 + e^-a)`.
+    pub fn sigmoid(self) -> Var<'t, T> {
+        self.unary(
+            self.map(|a| T::one() / (T::one() + (-a).exp())),
+            |grad, _, output| grad * &output.mapv(|y| y * (T::one() - y)),
+        )
+    }
+
+    pub fn tanh(self) -> Var<'t, T> {
+        self.unary(self.map(T::tanh), |grad, _, output| {
+            grad * &output.mapv(|y| T::one() - y * y)
+        })
+    }
+
+    /// Normalizes the values along the axis to probabilities, `e^a / Σ e^a`.
+    pub fn softmax(self, axis: usize) -> Var<'t, T> {
+        let value = self.with_value(|a| softmax(a, axis));
+
+        self.unary(value, move |grad, _, output| {
+            let dot = (grad * output).sum_axis(Axis(axis)).insert_axis(Axis(axis));
+
+            output * &(grad - &dot)
+        })
+    }
+
+    /// Logarithm of [Var::softmax], computed without overflow.
+    pub fn log_softmax(self, axis: usize) -> Var<'t, T> {
+        let value = self.with_value(|a| {
+            let max = max_axis(a, axis);
+            let shifted = a - &max;
+            let log_sum = shifted
+                .mapv(T::exp)
+                .sum_axis(Axis(axis))
+                .mapv(T::ln)
+                .insert_axis(Axis(axis));
+
+            shifted - log_sum
+        });
+
+        self.unary(value, move |grad, _, output| {
+            let sum = grad.sum_axis(Axis(axis)).insert_axis(Axis(axis));
+
+            grad - &(output.mapv(T::exp) * sum)
+        })
+    }
+
+    /// Matrix product over the last two axes.
+    ///
+    /// If `other` is a matrix, it is multiplied with every matrix of `self`,
+    /// e.g. to apply the weights of a dense layer to a `(batch, time,
+    /// features)` sequence. Otherwise both values need the same number of
+    /// axes and the same size of all but the last two axes, and the matrices
+    /// are multiplied pairwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shapes are not compatible or the values are recorded
+    /// on different tapes.
+    pub fn matmul(self, other: Var<'t, T>) -> Var<'t, T> {
+        self.tape.assert_recorded(other);
+        let value = self.with_value(|a| other.with_value(|b| matmul(a, b)));
+
+        self.tape.op(&[self, other], value, |grad, inputs, _| {
+            let (a, b) = (inputs[0], inputs[1]);
+
+            if b.ndim() == 2 {
+                let n_rows = a.len() / a.shape()[a.ndim() - 1];
+
+                vec![
+                    matmul(grad, &transpose(b)),
+                    to_matrix(a, n_rows)
+                        .t()
+                        .dot(&to_matrix(grad, n_rows))
+                        .into_dyn(),
+                ]
+            } else {
+                vec![matmul(grad, &transpose(b)), matmul(&transpose(a), grad)]
+            }
+        })
+    }
+
+    /// Sum of all values.
+    pub fn sum(self) -> Var<'t, T> {
+        let value = self.with_value(|a| arr0(a.sum()).into_dyn());
+
+        self.unary(value, |grad, a, _| ArrayD::from_elem(a.shape(), grad[[]]))
+    }
+
+    /// Mean of all values.
+    pub fn mean(self) -> Var<'t, T> {
+        let n = T::from(self.with_value(|a| a.len())).unwrap();
+
+        self.sum().scale(T::one() / n)
+    }
+
+    /// Sum along the axis, which is removed.
+    pub fn sum_axis(self, axis: usize) -> Var<'t, T> {
+        let value = self.with_value(|a| a.sum_axis(Axis(axis)));
+
+        self.unary(value, move |grad, a, _| {
+            grad.view()
+                .insert_axis(Axis(axis))
+                .broadcast(a.shape())
+                .unwrap()
+                .to_owned()
+        })
+    }
+
+    /// Mean along the axis, which is removed.
+    pub fn mean_axis(self, axis: usize) -> Var<'t, T> {
+        let n = T::from(self.with_value(|a| a.shape()[axis])).unwrap();
+
+        self.sum_axis(axis).scale(T::one() / n)
+    }
+
+    /// Returns the values in row-major order with a new shape.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values differs.
+    pub fn reshape(self, shape: &[usize]) -> Var<'t, T> {
+        let value = self.with_value(|a| reshape(a, shape));
+
+        self.unary(value, |grad, a, _| reshape(grad, a.shape()))
+    }
+
+    /// Reorders the axes, axis `i` of the result is axis `axes[i]` of `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `axes` is not a permutation of the axes.
+    pub fn permute(self, axes: &[usize]) -> Var<'t, T> {
+        let value = self.with_value(|a| permute(a, axes));
+        let mut inverse = vec![0; axes.len()];
+        for (i, &axis) in axes.iter().enumerate() {
+            inverse[axis] = i;
+        }
+
+        self.unary(value, move |grad, _, _| permute(grad, &inverse))
+    }
+
+    /// Inserts an axis of length one.
+    pub fn insert_axis(self, axis: usize) -> Var<'t, T> {
+        let value = self.with_value(|a| a.clone().insert_axis(Axis(axis)));
+
+        self.unary(value, move |grad, _, _| {
+            grad.clone().index_axis_move(Axis(axis), 0)
+        })
+    }
+
+    /// Selects the values at `index` along the axis, which is removed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn index_axis(self, axis: usize, index: usize) -> Var<'t, T> {
+        let value = self.with_value(|a| a.index_axis(Axis(axis), index).to_owned());
+
+        self.unary(value, move |grad, a, _| {
+            let mut a_grad = ArrayD::zeros(a.shape());
+            a_grad.index_axis_mut(Axis(axis), index).assign(grad);
+            a_grad
+        })
+    }
+
+    /// Selects the values in `range` along the axis.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn slice_axis(self, axis: usize, range: Range<usize>) -> Var<'t, T> {
+        let slice = Slice::from(range);
+        let value = self.with_value(|a| a.slice_axis(Axis(axis), slice).to_owned());
+
+        self.unary(value, move |grad, a, _| {
+            let mut a_grad = ArrayD::zeros(a.shape());
+            a_grad.slice_axis_mut(Axis(axis), slice).assign(grad);
+            a_grad
+        })
+    }
+
+    /// Joins the values along an existing axis.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `vars` is empty, the other axes differ in length or the
+    /// values are recorded on different tapes.
+    pub fn concatenate(axis: usize, vars: &[Var<'t, T>]) -> Var<'t, T> {
+        let tape = vars
+            .first()
+            .expect("concatenate expects at least one value")
+            .tape;
+        for &var in vars {
+            tape.assert_recorded(var);
+        }
+
+        let values: Vec<ArrayD<T>> = vars.iter().map(|var| var.value()).collect();
+        let views: Vec<_> = values.iter().map(|a| a.view()).collect();
+        let value = ndarray::concatenate(Axis(axis), &views)
+            .expect("concatenate expects values with the same shape except along the axis");
+        let lengths: Vec<usize> = values.iter().map(|a| a.shape()[axis]).collect();
+
+        tape.op(vars, value, move |grad, _, _| {
+            let mut start = 0;
+
+            lengths
+                .iter()
+                .map(|&n| {
+                    let slice = Slice::from(start..start + n);
+                    start += n;
+                    grad.slice_axis(Axis(axis), slice).to_owned()
+                })
+                .collect()
+        })
+    }
+
+    /// Joins values of the same shape along a new axis.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `vars` is empty or the shapes differ.
+    pub fn stack(axis: usize, vars: &[Var<'t, T>]) -> Var<'t, T> {
+        let vars: Vec<_> = vars.iter().map(|var| var.insert_axis(axis)).collect();
+
+        Self::concatenate(axis, &vars)
+    }
+}
+
+/// Elementwise sum, broadcasting both values to a common shape.
+impl<'t, T> Add for Var<'t, T>
+where
+    T: Float + ScalarOperand + 'static,
+{
+    type Output = Var<'t, T>;
+
+    fn add(self, other: Var<'t, T>) -> Var<'t, T> {
+        let value = self.with_value(|a| other.with_value(|b| a + b));
+
+        self.binary(other, value, |grad, _, _| (grad.clone(), grad.clone()))
+    }
+}
+
+/// Elementwise difference, broadcasting both values to a common shape.
+impl<'t, T> Sub for Var<'t, T>
+where
+    T: Float + ScalarOperand + 'static,
+{
+    type Output = Var<'t, T>;
+
+    fn sub(self, other: Var<'t, T>) -> Var<'t, T> {
+        let value = self.with_value(|a| other.with_value(|b| a - b));
+
+        self.binary(other, value, |grad, _, _| (grad.clone(), grad.mapv(|g| -g)))
+    }
+}
+
+/// Elementwise product, broadcasting both values to a common shape.
+impl<'t, T> Mul for Var<'t, T>
+where
+    T: Float + ScalarOperand + 'static,
+{
+    type Output = Var<'t, T>;
+
+    fn mul(self, other: Var<'t, T>) -> Var<'t, T> {
+        let value = self.with_value(|a| other.with_value(|b| a * b));
+
+        self.binary(other, value, |grad, a, b| (grad * b, grad * a))
+    }
+}
+
+/// Elementwise quotient, broadcasting both values to a common shape.
+impl<'t, T> Div for Var<'t, T>
+where
+    T: Float + ScalarOperand + 'static,
+{
+    type Output = Var<'t, T>;
+
+    fn div(self, other: Var<'t, T>) -> Var<'t, T> {
+        let value = self.with_value(|a| other.with_value(|b| a / b));
+
+        self.binary(other, value, |grad, a, b| {
+            (grad / b, (grad * a).mapv(|g| -g) / &b.mapv(|b| b * b))
+        })
+    }
+}
+
+impl<'t, T> Neg for Var<'t, T>
+where
+    T: Float + ScalarOperand + 'static,
+{
+    type Output = Var<'t, T>;
+
+    fn neg(self) -> Var<'t, T> {
+        self.unary(self.map(|a| -a), |grad, _, _| grad.mapv(|g| -g))
+    }
+}
+
+/// Sums the gradient of a broadcast value over the broadcast axes.
+fn unbroadcast<T: Float>(mut grad: ArrayD<T>, shape: &[usize]) -> ArrayD<T> {
+    while grad.ndim() > shape.len() {
+        grad = grad.sum_axis(Axis(0));
+    }
+
+    for (axis, &n) in shape.iter().enumerate() {
+        if n == 1 && grad.shape()[axis] != 1 {
+            grad = grad.sum_axis(Axis(axis)).insert_axis(Axis(axis));
+        }
+    }
+
+    grad
+}
+
+fn max_axis<T: Float>(a: &ArrayD<T>, axis: usize) -> ArrayD<T> {
+    a.fold_axis(Axis(axis), T::neg_infinity(), |&max, &x| max.max(x))
+        .insert_axis(Axis(axis))
+}
+
+pub(crate) fn softmax<T: Float + ScalarOperand>(a: &ArrayD<T>, axis: usize) -> ArrayD<T> {
+    let exp = (a - &max_axis(a, axis)).mapv(T::exp);
+    let sum = exp.sum_axis(Axis(axis)).insert_axis(Axis(axis));
+
+    exp / sum
+}
+
+fn reshape<T: Clone>(a: &ArrayD<T>, shape: &[usize]) -> ArrayD<T> {
+    a.as_standard_layout()
+        .into_owned()
+        .into_shape(IxDyn(shape))
+        .expect("number of values must not change")
+}
+
+fn permute<T: Clone>(a: &ArrayD<T>, axes: &[usize]) -> ArrayD<T> {
+    a.view()
+        .permuted_axes(IxDyn(axes))
+        .as_standard_layout()
+        .into_owned()
+}
+
+/// Swaps the last two axes.
+fn transpose<T: Clone>(a: &ArrayD<T>) -> ArrayD<T> {
+    let mut axes: Vec<usize> = (0..a.ndim()).collect();
+    axes.swap(a.ndim() - 2, a.ndim() - 1);
+
+    permute(a, &axes)
+}
+
+/// Merges all but the last axis.
+fn to_matrix<T: Clone>(a: &ArrayD<T>, n_rows: usize) -> Array2<T> {
+    let n_cols = a.shape()[a.ndim() - 1];
+
+    a.as_standard_layout()
+        .into_owned()
+        .into_shape((n_rows, n_cols))
+        .unwrap()
+}
+
+pub(crate) fn matmul<T: Float + 'static>(a: &ArrayD<T>, b: &ArrayD<T>) -> ArrayD<T> {
+    assert!(
+        a.ndim() >= 2 && b.ndim() >= 2,
+        "matmul expects at least two axes, found shapes {:?} and {:?}",
+        a.shape(),
+        b.shape()
+    );
+
+    let (m, k) = (a.shape()[a.ndim() - 2], a.shape()[a.ndim() - 1]);
+    let n = b.shape()[b.ndim() - 1];
+    let mut shape = a.shape().to_vec();
+    *shape.last_mut().unwrap() = n;
+
+    assert!(
+        b.shape()[b.ndim() - 2] == k
+            && (b.ndim() == 2 || a.shape()[..a.ndim() - 2] == b.shape()[..b.ndim() - 2]),
+        "matmul of incompatible shapes {:?} and {:?}",
+        a.shape(),
+        b.shape()
+    );
+
+    if b.ndim() == 2 {
+        let b: ArrayView2<T> = b.view().into_dimensionality().unwrap();
+
+        return reshape(&to_matrix(a, a.len() / k).dot(&b).into_dyn(), &shape);
+    }
+
+    let batch = a.len() / (m * k);
+    let a: Array3<T> = reshape(a, &[batch, m, k]).into_dimensionality().unwrap();
+    let b: Array3<T> = reshape(b, &[batch, k, n]).into_dimensionality().unwrap();
+    let mut product = Array3::zeros((batch, m, n));
+
+    for i in 0..batch {
+        product
+            .index_axis_mut(Axis(0), i)
+            .assign(&a.index_axis(Axis(0), i).dot(&b.index_axis(Axis(0), i)));
+    }
+
+    reshape(&product.into_dyn(), &shape)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::slice;
+
+    use ndarray::{arr1, arr2, Array};
+
+    use super::*;
+    use crate::autograd::Tape;
+
+    /// Compares the gradients of `f` with respect to every input with
+    /// central finite differences.
+    pub(crate) fn check<F>(inputs: &[ArrayD<f64>], f: F)
+    where
+        F: for<'t> Fn(&[Var<'t, f64>]) -> Var<'t, f64>,
+    {
+        let evaluate = |inputs: &[ArrayD<f64>]| {
+            let tape = Tape::new();
+            let vars: Vec<_> = inputs.iter().map(|x| tape.var(x.clone())).collect();
+            let value = f(&vars).sum().value()[[]];
+            value
+        };
+
+        let tape = Tape::new();
+        let vars: Vec<_> = inputs.iter().map(|x| tape.var(x.clone())).collect();
+        let gradients = tape.backward(f(&vars).sum());
+
+        for (i, var) in vars.iter().enumerate() {
+            for (index, &analytic) in gradients[*var].indexed_iter() {
+                let mut shifted = inputs.to_vec();
+                shifted[i][&index] += 1e-6;
+                let plus = evaluate(&shifted);
+                shifted[i][&index] -= 2e-6;
+                let minus = evaluate(&shifted);
+                let numeric = (plus - minus) / 2e-6;
+
+                assert!(
+                    (analytic - numeric).abs() <= 1e-6 * analytic.abs().max(1.0),
+                    "input {} at {:?}: analytic {}, numeric {}",
+                    i,
+                    index,
+                    analytic,
+                    numeric
+                );
+            }
+        }
+    }
+
+    fn matrix(rows: usize, cols: usize) -> ArrayD<f64> {
+        Array::linspace(-1.0, 1.3, rows * cols)
+            .into_shape((rows, cols))
+            .unwrap()
+            .into_dyn()
+    }
+
+    #[test]
+    fn test_broadcast() {
+        let a = matrix(3, 4);
+        let b = arr1(&[0.5, -1.5, 2.0, 1.2]).into_dyn();
+        let c = arr2(&[[0.7], [1.1], [-0.4]]).into_dyn();
+
+        check(&[a.clone(), b.clone()], |v| v[0] + v[1]);
+        check(&[a.clone(), c.clone()], |v| (v[0] - v[1]).square());
+        check(&[a.clone(), b.clone()], |v| v[0] * v[1]);
+        check(&[a, c], |v| v[0] / v[1]);
+    }
+
+    #[test]
+    fn test_elementwise() {
+        let a = matrix(2, 3);
+        let positive = a.mapv(|x| x + 2.0);
+
+        check(slice::from_ref(&a), |v| (-v[0]).scale(3.0).add_scalar(1.0));
+        check(slice::from_ref(&a), |v| v[0].powi(3));
+        check(slice::from_ref(&a), |v| v[0].exp());
+        check(slice::from_ref(&a), |v| v[0].sigmoid());
+        check(slice::from_ref(&a), |v| v[0].tanh());
+        check(&[a.mapv(|x| x + 0.05)], |v| v[0].relu().square());
+        check(slice::from_ref(&positive), |v| v[0].sqrt());
+        check(&[positive], |v| v[0].ln());
+    }
+
+    #[test]
+    fn test_relu() {
+        let tape = Tape::new();
+        let x = tape.var(arr1(&[-1.0, 0.0, 2.0]));
+        let y = x.relu();
+        let gradients = tape.backward(y);
+
+        assert_eq!(arr1(&[0.0, 0.0, 2.0]).into_dyn(), y.value());
+        assert_eq!(arr1(&[0.0, 0.0, 1.0]).into_dyn(), gradients[x]);
+    }
+
+    #[test]
+    fn test_softmax() {
+        let a = matrix(2, 3);
+        let weights = arr2(&[[1.0, -2.0, 0.5], [0.3, 0.2, -1.0]]).into_dyn();
+
+        let tape = Tape::new();
+        let y = tape.constant(a.clone()).softmax(1).value();
+        assert!((y.sum_axis(Axis(1)) - 1.0).iter().all(|d| d.abs() < 1e-12));
+
+        check(&[a.clone(), weights.clone()], |v| v[0].softmax(1) * v[1]);
+        check(&[a.clone(), weights.clone()], |v| v[0].softmax(0) * v[1]);
+        check(&[a, weights], |v| v[0].log_softmax(1) * v[1]);
+    }
+
+    #[test]
+    fn test_log_softmax() {
+        let a = arr2(&[[1000.0, 0.0]]).into_dyn();
+
+        let tape = Tape::new();
+        let y = tape.constant(a).log_softmax(1).value();
+
+        assert_eq!(arr2(&[[0.0, -1000.0]]).into_dyn(), y);
+    }
+
+    #[test]
+    fn test_matmul() {
+        let tape = Tape::new();
+        let a = tape.constant(arr2(&[[1.0, 2.0], [3.0, 4.0]]));
+        let b = tape.constant(arr2(&[[5.0], [6.0]]));
+
+        assert_eq!(arr2(&[[17.0], [39.0]]).into_dyn(), a.matmul(b).value());
+
+        check(&[matrix(3, 4), matrix(4, 2)], |v| {
+            v[0].matmul(v[1]).square()
+        });
+    }
+
+    #[test]
+    fn test_matmul_batched() {
+        let a = matrix(6, 4).into_shape(vec![2, 3, 4]).unwrap();
+        let b = matrix(4, 5);
+        let c = matrix(8, 5).into_shape(vec![2, 4, 5]).unwrap();
+
+        check(&[a.clone(), b], |v| v[0].matmul(v[1]).square());
+        check(&[a.clone(), c.clone()], |v| v[0].matmul(v[1]).square());
+
+        let tape = Tape::new();
+        let product = tape
+            .constant(a.clone())
+            .matmul(tape.constant(c.clone()))
+            .value();
+        let a: Array3<f64> = a.into_dimensionality().unwrap();
+        let c: Array3<f64> = c.into_dimensionality().unwrap();
+
+        assert_eq!(
+            a.index_axis(Axis(0), 1)
+                .dot(&c.index_axis(Axis(0), 1))
+                .into_dyn(),
+            product.index_axis(Axis(0), 1)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "incompatible shapes")]
+    fn test_matmul_incompatible() {
+        let tape = Tape::new();
+        tape.constant(matrix(2, 3))
+            .matmul(tape.constant(matrix(2, 3)));
+    }
+
+    #[test]
+    #[should_panic(expected = "different tapes")]
+    fn test_different_tapes() {
+        let (a, b) = (Tape::new(), Tape::new());
+        a.var(matrix(2, 3)).matmul(b.var(matrix(3, 2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "different tapes")]
+    fn test_concatenate_different_tapes() {
+        let (a, b) = (Tape::new(), Tape::new());
+        Var::concatenate(0, &[a.var(matrix(2, 3)), b.var(matrix(2, 3))]);
+    }
+
+    #[test]
+    fn test_reductions() {
+        let a = matrix(3, 4);
+
+        check(slice::from_ref(&a), |v| v[0].sum().square());
+        check(slice::from_ref(&a), |v| v[0].mean().square());
+        check(slice::from_ref(&a), |v| v[0].sum_axis(0).square());
+        check(slice::from_ref(&a), |v| v[0].mean_axis(1).square());
+
+        let tape = Tape::new();
+        assert!((0.15 - tape.constant(a).mean().value()[[]]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_shape_ops() {
+        let a = matrix(6, 4).into_shape(vec![2, 3, 4]).unwrap();
+        let weights = matrix(12, 2).into_shape(vec![4, 3, 2]).unwrap();
+
+        check(&[a.clone(), weights.clone()], |v| {
+            v[0].permute(&[2, 1, 0]) * v[1]
+        });
+        check(slice::from_ref(&a), |v| v[0].reshape(&[6, 4]).square());
+        check(slice::from_ref(&a), |v| v[0].insert_axis(1).square());
+        check(slice::from_ref(&a), |v| v[0].index_axis(1, 2).square());
+        check(slice::from_ref(&a), |v| v[0].slice_axis(2, 1..3).square());
+        check(&[a.clone(), weights], |v| {
+            Var::concatenate(0, &[v[0], v[1].permute(&[2, 1, 0])]).square()
+        });
+        check(&[a.clone(), a], |v| {
+            Var::stack(1, &[v[0], v[1].scale(2.0)]).square()
+        });
+    }
+
+    #[test]
+    fn test_concatenate() {
+        let tape = Tape::new();
+        let a = tape.constant(arr2(&[[1.0, 2.0], [3.0, 4.0]]));
+        let b = tape.constant(arr2(&[[5.0], [6.0]]));
+
+        assert_eq!(
+            arr2(&[[1.0, 2.0, 5.0], [3.0, 4.0, 6.0]]).into_dyn(),
+            Var::concatenate(1, &[a, b]).value()
+        );
+        assert_eq!(
+            arr2(&[[3.0, 4.0], [1.0, 2.0]]).into_dyn(),
+            Var::stack(0, &[a.index_axis(0, 1), a.index_axis(0, 0)]).value()
+        );
+        assert_eq!(
+            arr2(&[[2.0], [4.0]]).into_dyn(),
+            a.slice_axis(1, 1..2).value()
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use std::{
+    cell::RefCell,
+    fmt::{self, Debug},
+    ops::Index,
+};
+
+use ndarray::{Array, ArrayD, Dimension, ScalarOperand};
+use num_traits::Float;
+
+/// Computes the gradients of the inputs of an operation from the gradient of
+/// its output, the values of its inputs and the value of its output.
+pub(crate) type Backward<T> = Box<dyn Fn(&ArrayD<T>, &[&ArrayD<T>], &ArrayD<T>) -> Vec<ArrayD<T>>>;
+
+struct Node<T> {
+    value: ArrayD<T>,
+    parents: Vec<usize>,
+    requires_grad: bool,
+    backward: Option<Backward<T>>,
+}
+
+/// Records the operations on [Var]s for [Tape::backward].
+///
+/// A tape is meant to be used for a single forward and backward pass, e.g.
+/// one training batch, and dropped afterwards.
+pub struct Tape<T = f64> {
+    nodes: RefCell<Vec<Node<T>>>,
+}
+
+impl<T> Tape<T> {
+    pub fn new() -> Self {
+        Self {
+            nodes: RefCell::new(vec![]),
+        }
+    }
+
+    /// Number of recorded values.
+    pub fn len(&self) -> usize {
+        self.nodes.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Panics if `var` is recorded on another tape, whose indices would
+    /// refer to unrelated values of this tape.
+    pub(crate) fn assert_recorded(&self, var: Var<'_, T>) {
+        assert!(
+            std::ptr::eq(self, var.tape),
+            "values recorded on different tapes cannot be combined"
+        );
+    }
+
+    fn push(&self, node: Node<T>) -> Var<'_, T> {
+        let mut nodes = self.nodes.borrow_mut();
+        nodes.push(node);
+
+        Var {
+            tape: self,
+            index: nodes.len() - 1,
+        }
+    }
+
+    /// Records a value whose gradient is computed by [Tape::backward], e.g. a
+    /// parameter.
+    pub fn var<D: Dimension>(&self, value: Array<T, D>) -> Var<'_, T> {
+        self.push(Node {
+            value: value.into_dyn(),
+            parents: vec![],
+            requires_grad: true,
+            backward: None,
+        })
+    }
+
+    /// Records a value without gradient, e.g. the input data or targets.
+    pub fn constant<D: Dimension>(&self, value: Array<T, D>) -> Var<'_, T> {
+        self.push(Node {
+            value: value.into_dyn(),
+            parents: vec![],
+            requires_grad: false,
+            backward: None,
+        })
+    }
+
+    /// Records the result of an operation that is not provided by [Var].
+    ///
+    /// `backward` is called with the gradient of the output, the values of
+    /// the inputs and the value of the output and has to return the gradient
+    /// of every input, in the order and with the shapes of the inputs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ndarray::arr1;
+    /// use robit::autograd::Tape;
+    ///
+    /// let tape = Tape::new();
+    /// let x = tape.var(arr1(&[1.0, 2.0]));
+    ///
+    /// // cube of every value
+    /// let y = tape.op(&[x], x.value().mapv(|v| v * v * v), |grad, inputs, _| {
+    ///     vec![grad * &inputs[0].mapv(|v| 3.0 * v * v)]
+    /// });
+    ///
+    /// let gradients = tape.backward(y.sum());
+    ///
+    /// assert_eq!(arr1(&[3.0, 12.0]).into_dyn(), gradients[x]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if an input is recorded on another tape.
+    pub fn op<F>(&self, inputs: &[Var<'_, T>], value: ArrayD<T>, backward: F) -> Var<'_, T>
+    where
+        F: Fn(&ArrayD<T>, &[&ArrayD<T>], &ArrayD<T>) -> Vec<ArrayD<T>> + 'static,
+    {
+        for &input in inputs {
+            self.assert_recorded(input);
+        }
+
+        let requires_grad = {
+            let nodes = self.nodes.borrow();
+            inputs.iter().any(|input| nodes[input.index].requires_grad)
+        };
+
+        self.push(Node {
+            value,
+            parents: inputs.iter().map(|input| input.index).collect(),
+            requires_grad,
+            backward: requires_grad.then(|| Box::new(backward) as Backward<T>),
+        })
+    }
+}
+
+impl<T> Tape<T>
+where
+    T: Float + ScalarOperand,
+{
+    /// Returns the gradients of the sum of all values of `output` with
+    /// respect to every value recorded before it.
+    ///
+    /// Values recorded with [Tape::var] that `output` does not depend on
+    /// have a gradient of zero.
+    pub fn backward(&self, output: Var<'_, T>) -> Gradients<T> {
+        let nodes = self.nodes.borrow();
+        let mut grads: Vec<Option<ArrayD<T>>> = vec![None; nodes.len()];
+
+        if nodes[output.index].requires_grad {
+            grads[output.index] = Some(ArrayD::ones(nodes[output.index].value.shape()));
+        }
+
+        for index in (0..=output.index).rev() {
+            let node = &nodes[index];
+            let (Some(backward), Some(grad)) = (&node.backward, &grads[index]) else {
+                continue;
+            };
+
+            let inputs: Vec<&ArrayD<T>> = node
+                .parents
+                .iter()
+                .map(|&parent| &nodes[parent].value)
+                .collect();
+            let input_grads = backward(grad, &inputs, &node.value);
+
+            for (&parent, input_grad) in node.parents.iter().zip(input_grads) {
+                if !nodes[parent].requires_grad {
+                    continue;
+                }
+
+                debug_assert_eq!(nodes[parent].value.shape(), input_grad.shape());
+
+                match &mut grads[parent] {
+                    Some(total) => *total = &*total + &input_grad,
+                    None => grads[parent] = Some(input_grad),
+                }
+            }
+        }
+
+        for (node, grad) in nodes.iter().zip(grads.iter_mut()) {
+            if node.requires_grad && grad.is_none() {
+                *grad = Some(ArrayD::zeros(node.value.shape()));
+            }
+        }
+
+        Gradients { grads }
+    }
+}
+
+impl<T> Default for Tape<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Handle of a value recorded on a [Tape].
+///
+/// The operations are provided as operators and methods, e.g. `+` or
+/// [Var::matmul], which record their result on the same tape. They panic if
+/// their inputs are recorded on different tapes.
+pub struct Var<'t, T = f64> {
+    pub(crate) tape: &'t Tape<T>,
+    pub(crate) index: usize,
+}
+
+impl<'t, T> Var<'t, T> {
+    /// Returns the tape the value is recorded on.
+    pub fn tape(&self) -> &'t Tape<T> {
+        self.tape
+    }
+
+    /// Calls `f` with the recorded value without copying it.
+    pub fn with_value<R>(&self, f: impl FnOnce(&ArrayD<T>) -> R) -> R {
+        f(&self.tape.nodes.borrow()[self.index].value)
+    }
+
+    pub fn shape(&self) -> Vec<usize> {
+        self.with_value(|value| value.shape().to_vec())
+    }
+
+    pub fn ndim(&self) -> usize {
+        self.with_value(|value| value.ndim())
+    }
+}
+
+impl<'t, T: Clone> Var<'t, T> {
+    /// Returns a copy of the recorded value.
+    pub fn value(&self) -> ArrayD<T> {
+        self.with_value(|value| value.clone())
+    }
+}
+
+impl<'t, T> Clone for Var<'t, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'t, T> Copy for Var<'t, T> {}
+
+impl<'t, T> Debug for Var<'t, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Var").field("index", &self.index).finish()
+    }
+}
+
+/// Gradients computed by [Tape::backward], indexed by the [Var]s.
+pub struct Gradients<T = f64> {
+    grads: Vec<Option<ArrayD<T>>>,
+}
+
+impl<T> Gradients<T> {
+    /// Returns the gradient of the value, `None` if it was recorded with
+    /// [Tape::constant] or does not belong to the tape.
+    pub fn get(&self, var: Var<'_, T>) -> Option<&ArrayD<T>> {
+        self.grads.get(var.index)?.as_ref()
+    }
+
+    /// Removes the gradient of the value, avoiding a copy.
+    pub fn take(&mut self, var: Var<'_, T>) -> Option<ArrayD<T>> {
+        self.grads.get_mut(var.index)?.take()
+    }
+}
+
+impl<'t, T> Index<Var<'t, T>> for Gradients<T> {
+    type Output = ArrayD<T>;
+
+    /// # Panics
+    ///
+    /// Panics if the value has no gradient, see [Gradients::get].
+    fn index(&self, var: Var<'t, T>) -> &ArrayD<T> {
+        self.get(var).expect("value has no gradient")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr0, arr1};
+
+    use super::*;
+
+    #[test]
+    fn test_constant() {
+        let tape = Tape::new();
+        let x = tape.constant(arr1(&[1.0, 2.0]));
+        let y = tape.var(arr1(&[3.0, 4.0]));
+
+        let gradients = tape.backward((x * y).sum());
+
+        assert_eq!(None, gradients.get(x));
+        assert_eq!(arr1(&[1.0, 2.0]).into_dyn(), gradients[y]);
+    }
+
+    #[test]
+    #[should_panic(expected = "different tapes")]
+    fn test_different_tapes() {
+        let (a, b) = (Tape::new(), Tape::new());
+        let _ = a.var(arr1(&[1.0])) + b.var(arr1(&[2.0]));
+    }
+
+    #[test]
+    fn test_accumulate() {
+        let tape = Tape::new();
+        let x = tape.var(arr0(3.0));
+
+        // x² + x
+        let gradients = tape.backward(x * x + x);
+
+        assert_eq!(arr0(7.0).into_dyn(), gradients[x]);
+    }
+
+    #[test]
+    fn test_unused() {
+        let tape = Tape::new();
+        let x = tape.var(arr1(&[1.0, 2.0]));
+        let y = tape.var(arr1(&[3.0]));
+
+        let mut gradients = tape.backward(x.sum());
+
+        assert_eq!(Some(ArrayD::zeros(vec![1])), gradients.take(y));
+        assert_eq!(None, gradients.take(y));
+    }
+
+    #[test]
+    fn test_len() {
+        let tape = Tape::new();
+        assert!(tape.is_empty());
+
+        let x = tape.var(arr1(&[1.0, 2.0]));
+        x.exp().sum();
+
+        assert_eq!(3, tape.len());
+    }
+}
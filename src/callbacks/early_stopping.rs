@@ -0,0 +1,297 @@
+use std::ops::{Add, Sub};
+
+use ndarray::ArrayD;
+use num_traits::Zero;
+
+use crate::{activations::Activation, losses::Loss, model::Parameters, Error, Model};
+
+use super::{Callback, Logs};
+
+/// Whether the monitored value should decrease or increase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// Lower values are better, e.g. for losses.
+    #[default]
+    Min,
+
+    /// Higher values are better, e.g. for accuracy.
+    Max,
+}
+
+/// Stops the training once the monitored value stopped improving.
+///
+/// The value is considered improved when it is better than the best value so
+/// far by more than `min_delta`. Training stops after `patience` epochs
+/// without improvement.
+///
+/// If the monitored value is missing from the epoch [Logs], e.g. `val_loss`
+/// without validation data, the training is stopped with an
+/// [Error::InvalidHyperparameter] that is available from
+/// [EarlyStopping::error].
+///
+/// # Examples
+///
+/// ```
+/// use robit::callbacks::{EarlyStopping, Mode};
+///
+/// let early_stopping = EarlyStopping::new("val_loss", 5)
+///     .min_delta(0.001)
+///     .mode(Mode::Min)
+///     .restore_best_weights(true);
+/// ```
+pub struct EarlyStopping<T = f64> {
+    monitor: String,
+    patience: usize,
+    min_delta: T,
+    mode: Mode,
+    restore_best_weights: bool,
+    best: Option<T>,
+    best_epoch: Option<usize>,
+    best_parameters: Option<(Parameters<T>, Vec<ArrayD<T>>)>,
+    wait: usize,
+    stopped_epoch: Option<usize>,
+    error: Option<Error>,
+}
+
+impl<T: Zero> EarlyStopping<T> {
+    /// Returns an instance that monitors the value with the given name in the
+    /// epoch [Logs], e.g. `val_loss`.
+    pub fn new(monitor: &str, patience: usize) -> Self {
+        Self {
+            monitor: monitor.to_owned(),
+            patience,
+            min_delta: T::zero(),
+            mode: Mode::default(),
+            restore_best_weights: false,
+            best: None,
+            best_epoch: None,
+            best_parameters: None,
+            wait: 0,
+            stopped_epoch: None,
+            error: None,
+        }
+    }
+}
+
+impl<T> EarlyStopping<T> {
+    /// Minimum change of the monitored value that counts as an improvement.
+    pub fn min_delta(mut self, min_delta: T) -> Self {
+        self.min_delta = min_delta;
+        self
+    }
+
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Whether the weights and biases of the best epoch are restored when
+    /// the training ends, together with the parameters and state of layers
+    /// such as [BatchNorm1d](crate::layers::BatchNorm1d).
+    pub fn restore_best_weights(mut self, restore_best_weights: bool) -> Self {
+        self.restore_best_weights = restore_best_weights;
+        self
+    }
+
+    /// Best monitored value seen so far.
+    pub fn best(&self) -> Option<&T> {
+        self.best.as_ref()
+    }
+
+    /// Epoch in which the best value was seen.
+    pub fn best_epoch(&self) -> Option<usize> {
+        self.best_epoch
+    }
+
+    /// Epoch after which the training was stopped, if it was stopped early.
+    pub fn stopped_epoch(&self) -> Option<usize> {
+        self.stopped_epoch
+    }
+
+    /// Error that stopped the training, if the monitored value was missing
+    /// from the epoch logs.
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+}
+
+impl<T> EarlyStopping<T>
+where
+    T: Copy + PartialOrd + Add<Output = T> + Sub<Output = T>,
+{
+    fn is_improvement(&self, value: T) -> bool {
+        match self.best {
+            None => true,
+            Some(best) => match self.mode {
+                Mode::Min => value < best - self.min_delta,
+                Mode::Max => value > best + self.min_delta,
+            },
+        }
+    }
+}
+
+impl<T, A, L> Callback<T, A, L> for EarlyStopping<T>
+where
+    T: Copy + PartialOrd + Add<Output = T> + Sub<Output = T>,
+    A: Activation<T>,
+    L: Loss<T>,
+{
+    fn on_train_begin(&mut self, _model: &mut Model<A, L, T>, _logs: &Logs<T>) {
+        self.best = None;
+        self.best_epoch = None;
+        self.best_parameters = None;
+        self.wait = 0;
+        self.stopped_epoch = None;
+        self.error = None;
+    }
+
+    fn on_epoch_end(&mut self, epoch: usize, model: &mut Model<A, L, T>, logs: &Logs<T>) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let value = match logs.get(&self.monitor) {
+            Some(value) => *value,
+            None => {
+                let found = logs.iter().map(|(name, _)| name).collect::<Vec<_>>();
+                self.error = Some(Error::invalid_hyperparameter(
+                    "monitor",
+                    format!("{} is not in the epoch logs {:?}", self.monitor, found),
+                ));
+                self.stopped_epoch = Some(epoch);
+                model.stop_training();
+                return;
+            }
+        };
+
+        if self.is_improvement(value) {
+            self.best = Some(value);
+            self.best_epoch = Some(epoch);
+            self.wait = 0;
+
+            if self.restore_best_weights {
+                self.best_parameters = Some((
+                    (model.weights().to_vec(), model.biases().to_vec()),
+                    model.layer_tensors(),
+                ));
+            }
+
+            return;
+        }
+
+        self.wait += 1;
+
+        if self.wait >= self.patience {
+            self.stopped_epoch = Some(epoch);
+            model.stop_training();
+        }
+    }
+
+    fn on_train_end(&mut self, model: &mut Model<A, L, T>, _logs: &Logs<T>) {
+        if let Some(((weights, biases), layers)) = self.best_parameters.take() {
+            model.set_parameters(weights, biases);
+            model.set_layer_tensors(layers);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr1, arr2};
+
+    use super::*;
+    use crate::{initializers::RandomDistr, training::FitConfig};
+
+    fn logs(name: &str, value: f64) -> Logs {
+        let mut logs = Logs::new();
+        logs.insert(name, value);
+        logs
+    }
+
+    fn model() -> Model {
+        let mut model = Model::new(0.01);
+        model.add_layer((2, 1), RandomDistr::normal());
+        model
+    }
+
+    #[test]
+    fn test_stops_after_patience() {
+        let mut model = model();
+        let mut early_stopping = EarlyStopping::new("val_loss", 2);
+
+        for (epoch, value) in [0.5, 0.4, 0.45, 0.41].into_iter().enumerate() {
+            early_stopping.on_epoch_end(epoch, &mut model, &logs("val_loss", value));
+        }
+
+        assert_eq!(Some(3), early_stopping.stopped_epoch());
+        assert_eq!(Some(1), early_stopping.best_epoch());
+        assert_eq!(Some(&0.4), early_stopping.best());
+    }
+
+    #[test]
+    fn test_min_delta() {
+        let mut model = model();
+        let mut early_stopping = EarlyStopping::new("val_loss", 1).min_delta(0.1);
+
+        early_stopping.on_epoch_end(0, &mut model, &logs("val_loss", 0.5));
+        early_stopping.on_epoch_end(1, &mut model, &logs("val_loss", 0.45));
+
+        assert_eq!(Some(1), early_stopping.stopped_epoch());
+    }
+
+    #[test]
+    fn test_mode_max() {
+        let mut model = model();
+        let mut early_stopping = EarlyStopping::new("accuracy", 1).mode(Mode::Max);
+
+        early_stopping.on_epoch_end(0, &mut model, &logs("accuracy", 0.5));
+        early_stopping.on_epoch_end(1, &mut model, &logs("accuracy", 0.6));
+
+        assert_eq!(None, early_stopping.stopped_epoch());
+
+        early_stopping.on_epoch_end(2, &mut model, &logs("accuracy", 0.55));
+
+        assert_eq!(Some(2), early_stopping.stopped_epoch());
+    }
+
+    #[test]
+    fn test_missing_monitor() {
+        let x = arr2(&[[0.0, 1.0], [1.0, 0.0]]);
+        let y = arr2(&[[1.0], [0.0]]);
+        let mut model = model();
+        let mut early_stopping = EarlyStopping::new("val_loss", 5);
+        let config = FitConfig {
+            epochs: 10,
+            ..FitConfig::default()
+        };
+
+        let history = model.fit_with_callbacks(&x, &y, config, &mut early_stopping);
+
+        assert!(matches!(
+            early_stopping.error(),
+            Some(Error::InvalidHyperparameter {
+                name: "monitor",
+                ..
+            })
+        ));
+        assert_eq!(1, history.epochs());
+        assert_eq!(Some(0), early_stopping.stopped_epoch());
+        assert_eq!(None, early_stopping.best());
+    }
+
+    #[test]
+    fn test_restore_best_weights() {
+        let mut model = model();
+        let mut early_stopping = EarlyStopping::new("val_loss", 1).restore_best_weights(true);
+        let best_weights = model.weights().to_vec();
+
+        early_stopping.on_epoch_end(0, &mut model, &logs("val_loss", 0.1));
+
+        model.set_parameters(vec![arr2(&[[1.0], [1.0]])], vec![arr1(&[1.0])]);
+
+        early_stopping.on_epoch_end(1, &mut model, &logs("val_loss", 0.2));
+        early_stopping.on_train_end(&mut model, &Logs::new());
+
+        assert_eq!(best_weights, model.weights());
+    }
+}
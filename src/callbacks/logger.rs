@@ -0,0 +1,41 @@
+use std::fmt::Debug;
+
+use crate::{activations::Activation, losses::Loss, Model};
+
+use super::{Callback, Logs};
+
+/// Prints the logs of every epoch to stdout.
+///
+/// ```text
+/// [Epoch:3] loss = 0.0123, val_loss = 0.0131
+/// ```
+pub struct Logger;
+
+impl Logger {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self
+    }
+}
+
+impl<T, A, L> Callback<T, A, L> for Logger
+where
+    T: Debug,
+    A: Activation<T>,
+    L: Loss<T>,
+{
+    fn on_epoch_end(&mut self, epoch: usize, _model: &mut Model<A, L, T>, logs: &Logs<T>) {
+        let values = logs
+            .iter()
+            .map(|(name, value)| format!("{} = {:?}", name, value))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        println!("[Epoch:{}] {}", epoch, values);
+    }
+}
@@ -0,0 +1,215 @@
+mod early_stopping;
+mod logger;
+mod model_checkpoint;
+
+use std::collections::BTreeMap;
+
+pub use early_stopping::{EarlyStopping, Mode};
+pub use logger::Logger;
+pub use model_checkpoint::ModelCheckpoint;
+
+use crate::{
+    activations::{Activation, Relu},
+    losses::{Loss, MeanSquaredError},
+    Model,
+};
+
+/// Named values reported to a [Callback], e.g. `loss` and `val_loss`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Logs<T = f64> {
+    values: BTreeMap<String, T>,
+}
+
+impl<T> Logs<T> {
+    pub fn new() -> Self {
+        Self {
+            values: BTreeMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, name: &str, value: T) {
+        self.values.insert(name.to_owned(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&T> {
+        self.values.get(name)
+    }
+
+    /// Iterates over the values ordered by their name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl<T> Default for Logs<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Hooks into the training loop of [Model::fit_with_callbacks].
+///
+/// Every method does nothing by default, so implementations only override the
+/// events they are interested in. A callback can end the training early by
+/// calling [Model::stop_training].
+///
+/// # Examples
+///
+/// ```
+/// use robit::{callbacks::{Callback, Logs}, Model};
+///
+/// struct CountEpochs(usize);
+///
+/// impl Callback for CountEpochs {
+///     fn on_epoch_end(&mut self, epoch: usize, model: &mut Model, logs: &Logs) {
+///         self.0 += 1;
+///     }
+/// }
+/// ```
+pub trait Callback<T = f64, A = Relu, L = MeanSquaredError>
+where
+    A: Activation<T>,
+    L: Loss<T>,
+{
+    fn on_train_begin(&mut self, _model: &mut Model<A, L, T>, _logs: &Logs<T>) {}
+
+    fn on_train_end(&mut self, _model: &mut Model<A, L, T>, _logs: &Logs<T>) {}
+
+    fn on_epoch_begin(&mut self, _epoch: usize, _model: &mut Model<A, L, T>, _logs: &Logs<T>) {}
+
+    /// Called after every epoch with the training loss and, if available, the
+    /// validation loss.
+    fn on_epoch_end(&mut self, _epoch: usize, _model: &mut Model<A, L, T>, _logs: &Logs<T>) {}
+
+    fn on_batch_begin(&mut self, _batch: usize, _model: &mut Model<A, L, T>, _logs: &Logs<T>) {}
+
+    /// Called after every gradient update with the loss of the batch.
+    fn on_batch_end(&mut self, _batch: usize, _model: &mut Model<A, L, T>, _logs: &Logs<T>) {}
+}
+
+/// Ordered list of callbacks that are invoked one after another.
+///
+/// # Examples
+///
+/// ```
+/// use robit::callbacks::{CallbackList, Logger};
+///
+/// let mut callbacks: CallbackList = CallbackList::new();
+/// callbacks.push(Logger::new());
+/// ```
+pub struct CallbackList<T = f64, A = Relu, L = MeanSquaredError>
+where
+    A: Activation<T>,
+    L: Loss<T>,
+{
+    callbacks: Vec<Box<dyn Callback<T, A, L>>>,
+}
+
+impl<T, A, L> CallbackList<T, A, L>
+where
+    A: Activation<T>,
+    L: Loss<T>,
+{
+    pub fn new() -> Self {
+        Self { callbacks: vec![] }
+    }
+
+    pub fn push<C: Callback<T, A, L> + 'static>(&mut self, callback: C) {
+        self.callbacks.push(Box::new(callback));
+    }
+
+    pub fn len(&self) -> usize {
+        self.callbacks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.callbacks.is_empty()
+    }
+}
+
+impl<T, A, L> Default for CallbackList<T, A, L>
+where
+    A: Activation<T>,
+    L: Loss<T>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, A, L> From<Vec<Box<dyn Callback<T, A, L>>>> for CallbackList<T, A, L>
+where
+    A: Activation<T>,
+    L: Loss<T>,
+{
+    fn from(callbacks: Vec<Box<dyn Callback<T, A, L>>>) -> Self {
+        Self { callbacks }
+    }
+}
+
+impl<T, A, L> Callback<T, A, L> for CallbackList<T, A, L>
+where
+    A: Activation<T>,
+    L: Loss<T>,
+{
+    fn on_train_begin(&mut self, model: &mut Model<A, L, T>, logs: &Logs<T>) {
+        for callback in self.callbacks.iter_mut() {
+            callback.on_train_begin(model, logs);
+        }
+    }
+
+    fn on_train_end(&mut self, model: &mut Model<A, L, T>, logs: &Logs<T>) {
+        for callback in self.callbacks.iter_mut() {
+            callback.on_train_end(model, logs);
+        }
+    }
+
+    fn on_epoch_begin(&mut self, epoch: usize, model: &mut Model<A, L, T>, logs: &Logs<T>) {
+        for callback in self.callbacks.iter_mut() {
+            callback.on_epoch_begin(epoch, model, logs);
+        }
+    }
+
+    fn on_epoch_end(&mut self, epoch: usize, model: &mut Model<A, L, T>, logs: &Logs<T>) {
+        for callback in self.callbacks.iter_mut() {
+            callback.on_epoch_end(epoch, model, logs);
+        }
+    }
+
+    fn on_batch_begin(&mut self, batch: usize, model: &mut Model<A, L, T>, logs: &Logs<T>) {
+        for callback in self.callbacks.iter_mut() {
+            callback.on_batch_begin(batch, model, logs);
+        }
+    }
+
+    fn on_batch_end(&mut self, batch: usize, model: &mut Model<A, L, T>, logs: &Logs<T>) {
+        for callback in self.callbacks.iter_mut() {
+            callback.on_batch_end(batch, model, logs);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logs() {
+        let mut logs = Logs::new();
+        logs.insert("val_loss", 0.5);
+        logs.insert("loss", 0.25);
+
+        assert_eq!(Some(&0.25), logs.get("loss"));
+        assert_eq!(None, logs.get("accuracy"));
+        assert_eq!(
+            vec![("loss", &0.25), ("val_loss", &0.5)],
+            logs.iter().collect::<Vec<_>>()
+        );
+    }
+}
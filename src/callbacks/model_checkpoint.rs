@@ -0,0 +1,401 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    activations::Activation,
+    losses::Loss,
+    serialization::{write_checkpoint, write_training_checkpoint, Element},
+    Error, Model, Result,
+};
+
+use super::{Callback, Logs, Mode};
+
+/// Writes binary checkpoints of the model during training.
+///
+/// A checkpoint is written every `period` epochs to the path, in which
+/// `{epoch}` is replaced with the number of the epoch. With
+/// `save_best_only` it is only written if the monitored value improved since
+/// the last checkpoint. When `keep_last` is set, only the most recent
+/// checkpoints are kept and older ones are deleted.
+///
+/// The checkpoints include the training state by default, so the training
+/// can be resumed from them, see [Model::save_training_checkpoint].
+///
+/// If a checkpoint can not be written the training is stopped and the error
+/// is available from [ModelCheckpoint::error]. The training is stopped
+/// before the first epoch with an [Error::InvalidHyperparameter] if `period`
+/// or `keep_last` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use robit::callbacks::{ModelCheckpoint, Mode};
+///
+/// let checkpoint: ModelCheckpoint = ModelCheckpoint::new("checkpoints/epoch-{epoch}.bin")
+///     .period(5)
+///     .keep_last(3);
+///
+/// let best: ModelCheckpoint = ModelCheckpoint::new("checkpoints/best.bin")
+///     .monitor("val_accuracy")
+///     .mode(Mode::Max)
+///     .save_best_only(true);
+/// ```
+pub struct ModelCheckpoint<T = f64> {
+    path: String,
+    period: usize,
+    monitor: String,
+    mode: Mode,
+    save_best_only: bool,
+    training_state: bool,
+    keep_last: Option<usize>,
+    best: Option<T>,
+    files: Vec<PathBuf>,
+    error: Option<Error>,
+}
+
+impl<T> ModelCheckpoint<T> {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_owned(),
+            period: 1,
+            monitor: "val_loss".to_owned(),
+            mode: Mode::default(),
+            save_best_only: false,
+            training_state: true,
+            keep_last: None,
+            best: None,
+            files: vec![],
+            error: None,
+        }
+    }
+
+    /// Number of epochs between checkpoints.
+    pub fn period(mut self, period: usize) -> Self {
+        self.period = period;
+        self
+    }
+
+    /// Name of the value in the epoch [Logs] that decides which checkpoint
+    /// is the best, `val_loss` by default.
+    pub fn monitor(mut self, monitor: &str) -> Self {
+        self.monitor = monitor.to_owned();
+        self
+    }
+
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Whether checkpoints are only written when the monitored value
+    /// improved.
+    pub fn save_best_only(mut self, save_best_only: bool) -> Self {
+        self.save_best_only = save_best_only;
+        self
+    }
+
+    /// Whether the checkpoints include the training state, `true` by default.
+    pub fn training_state(mut self, training_state: bool) -> Self {
+        self.training_state = training_state;
+        self
+    }
+
+    /// Number of most recent checkpoints that are kept on disk.
+    pub fn keep_last(mut self, keep_last: usize) -> Self {
+        self.keep_last = Some(keep_last);
+        self
+    }
+
+    /// Best monitored value seen so far.
+    pub fn best(&self) -> Option<&T> {
+        self.best.as_ref()
+    }
+
+    /// Paths of the checkpoints that were written and not deleted yet, from
+    /// oldest to newest.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Error that stopped the training, if a checkpoint could not be
+    /// written or the configuration is invalid.
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.period == 0 {
+            return Err(Error::invalid_hyperparameter("period", "must be positive"));
+        }
+        if self.keep_last == Some(0) {
+            return Err(Error::invalid_hyperparameter(
+                "keep_last",
+                "must be positive",
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn path(&self, epoch: usize) -> PathBuf {
+        PathBuf::from(self.path.replace("{epoch}", &epoch.to_string()))
+    }
+
+    /// Forgets the oldest checkpoints that exceed `keep_last` and returns
+    /// them.
+    fn outdated(&mut self) -> Vec<PathBuf> {
+        match self.keep_last {
+            Some(keep_last) if self.files.len() > keep_last => {
+                let n = self.files.len() - keep_last;
+                self.files.drain(..n).collect()
+            }
+            _ => vec![],
+        }
+    }
+}
+
+impl<T: Copy + PartialOrd> ModelCheckpoint<T> {
+    fn is_improvement(&self, value: T) -> bool {
+        match self.best {
+            None => true,
+            Some(best) => match self.mode {
+                Mode::Min => value < best,
+                Mode::Max => value > best,
+            },
+        }
+    }
+
+    fn save<A, L>(&mut self, path: &Path, model: &Model<A, L, T>) -> io::Result<()>
+    where
+        A: Activation<T>,
+        L: Loss<T>,
+        T: Element,
+    {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let writer = BufWriter::new(File::create(path)?);
+
+        if self.training_state {
+            write_training_checkpoint(model, writer)?;
+        } else {
+            write_checkpoint(model, writer)?;
+        }
+
+        self.files.retain(|file| file != path);
+        self.files.push(path.to_owned());
+
+        for file in self.outdated() {
+            match fs::remove_file(&file) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<T, A, L> Callback<T, A, L> for ModelCheckpoint<T>
+where
+    T: Element + PartialOrd,
+    A: Activation<T>,
+    L: Loss<T>,
+{
+    fn on_train_begin(&mut self, model: &mut Model<A, L, T>, _logs: &Logs<T>) {
+        self.best = None;
+        self.error = self.validate().err();
+
+        if self.error.is_some() {
+            model.stop_training();
+        }
+    }
+
+    fn on_epoch_end(&mut self, epoch: usize, model: &mut Model<A, L, T>, logs: &Logs<T>) {
+        if self.error.is_some() || !(epoch + 1).is_multiple_of(self.period) {
+            return;
+        }
+
+        let value = logs.get(&self.monitor).copied();
+        let improved = value.is_some_and(|value| self.is_improvement(value));
+
+        if self.save_best_only && !improved {
+            return;
+        }
+
+        if improved {
+            self.best = value;
+        }
+
+        if let Err(err) = self.save(&self.path(epoch), model) {
+            self.error = Some(err.into());
+            model.stop_training();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::Array2;
+
+    use super::*;
+    use crate::{initializers::RandomDistr, training::FitConfig};
+
+    fn logs(name: &str, value: f64) -> Logs {
+        let mut logs = Logs::new();
+        logs.insert(name, value);
+        logs
+    }
+
+    fn model() -> Model {
+        let mut model = Model::new(0.01);
+        model.add_layer((2, 1), RandomDistr::normal());
+        model
+    }
+
+    fn directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&directory);
+        directory
+    }
+
+    fn template(directory: &Path) -> String {
+        directory
+            .join("epoch-{epoch}.bin")
+            .to_str()
+            .unwrap()
+            .to_owned()
+    }
+
+    #[test]
+    fn test_period() {
+        let directory = directory("robit_test_model_checkpoint_period");
+        let mut model = model();
+        let mut checkpoint = ModelCheckpoint::new(&template(&directory)).period(2);
+
+        for epoch in 0..5 {
+            checkpoint.on_epoch_end(epoch, &mut model, &logs("loss", 0.1));
+        }
+
+        assert_eq!(
+            vec![directory.join("epoch-1.bin"), directory.join("epoch-3.bin")],
+            checkpoint.files()
+        );
+
+        let loaded: Model = Model::load_checkpoint(&checkpoint.files()[1]).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(model.weights(), loaded.weights());
+    }
+
+    #[test]
+    fn test_keep_last() {
+        let directory = directory("robit_test_model_checkpoint_keep_last");
+        let mut model = model();
+        let mut checkpoint = ModelCheckpoint::new(&template(&directory)).keep_last(2);
+
+        for epoch in 0..4 {
+            checkpoint.on_epoch_end(epoch, &mut model, &logs("loss", 0.1));
+        }
+
+        let mut remaining: Vec<_> = fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        remaining.sort();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(checkpoint.files(), remaining);
+        assert_eq!(
+            vec![directory.join("epoch-2.bin"), directory.join("epoch-3.bin")],
+            remaining
+        );
+    }
+
+    #[test]
+    fn test_save_best_only() {
+        let directory = directory("robit_test_model_checkpoint_best_only");
+        let mut model = model();
+        let mut checkpoint = ModelCheckpoint::new(&template(&directory)).save_best_only(true);
+
+        for (epoch, value) in [0.5, 0.4, 0.45, 0.3].into_iter().enumerate() {
+            checkpoint.on_epoch_end(epoch, &mut model, &logs("val_loss", value));
+        }
+        checkpoint.on_epoch_end(4, &mut model, &logs("loss", 0.1));
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(Some(&0.3), checkpoint.best());
+        assert_eq!(
+            vec![
+                directory.join("epoch-0.bin"),
+                directory.join("epoch-1.bin"),
+                directory.join("epoch-3.bin")
+            ],
+            checkpoint.files()
+        );
+    }
+
+    #[test]
+    fn test_overwrites_single_file() {
+        let directory = directory("robit_test_model_checkpoint_single_file");
+        let path = directory.join("best.bin");
+        let mut model = model();
+        let mut checkpoint = ModelCheckpoint::new(path.to_str().unwrap())
+            .mode(Mode::Max)
+            .save_best_only(true)
+            .keep_last(1);
+
+        checkpoint.on_epoch_end(0, &mut model, &logs("val_loss", 0.5));
+        checkpoint.on_epoch_end(1, &mut model, &logs("val_loss", 0.6));
+        let exists = path.exists();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert!(exists);
+        assert_eq!(vec![path], checkpoint.files());
+    }
+
+    #[test]
+    fn test_error_stops_training() {
+        let directory = directory("robit_test_model_checkpoint_error");
+        fs::write(&directory, b"").unwrap();
+        let mut model = model();
+        let mut checkpoint = ModelCheckpoint::new(&template(&directory));
+
+        checkpoint.on_epoch_end(0, &mut model, &logs("loss", 0.1));
+        fs::remove_file(&directory).unwrap();
+
+        assert!(matches!(checkpoint.error(), Some(Error::Io(_))));
+        assert!(checkpoint.files().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_hyperparameters() {
+        let directory = directory("robit_test_model_checkpoint_invalid");
+        let (x, y) = (Array2::zeros((4, 2)), Array2::zeros((4, 1)));
+
+        for (mut checkpoint, parameter) in [
+            (
+                ModelCheckpoint::new(&template(&directory)).period(0),
+                "period",
+            ),
+            (
+                ModelCheckpoint::new(&template(&directory)).keep_last(0),
+                "keep_last",
+            ),
+        ] {
+            let mut model = model();
+            let history = model.fit_with_callbacks(&x, &y, FitConfig::default(), &mut checkpoint);
+
+            assert!(matches!(
+                checkpoint.error(),
+                Some(Error::InvalidHyperparameter { name, .. }) if *name == parameter
+            ));
+            assert_eq!(0, history.epochs());
+            assert!(!directory.exists());
+        }
+    }
+}
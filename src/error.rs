@@ -0,0 +1,151 @@
+use std::{
+    error,
+    fmt::{self, Display},
+    io,
+};
+
+use crate::serialization::{CheckpointError, SafetensorsError, TextFormatError};
+
+/// Error of the fallible operations of the crate.
+#[derive(Debug)]
+pub enum Error {
+    /// An array does not have the shape the operation expects, e.g. the
+    /// input of a model with a different number of features.
+    ShapeMismatch {
+        name: &'static str,
+        expected: Vec<usize>,
+        found: Vec<usize>,
+    },
+
+    /// The operation requires a model with at least one layer.
+    EmptyModel,
+
+    /// A hyperparameter is outside of its valid range.
+    InvalidHyperparameter {
+        name: &'static str,
+        message: String,
+    },
+
+    /// A value of the data can not be processed by the model, e.g. a token
+    /// that is not in the vocabulary of an embedding.
+    InvalidInput {
+        name: &'static str,
+        message: String,
+    },
+
+    Io(io::Error),
+
+    /// A model in the text format could not be read, e.g. because the file
+    /// is malformed.
+    Text(TextFormatError),
+
+    Checkpoint(CheckpointError),
+
+    Safetensors(SafetensorsError),
+}
+
+/// Result type of the fallible operations of the crate.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    pub(crate) fn invalid_hyperparameter(name: &'static str, message: impl Display) -> Self {
+        Error::InvalidHyperparameter {
+            name,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ShapeMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "shape mismatch of {}: expected {:?}, found {:?}",
+                name, expected, found
+            ),
+            Error::EmptyModel => write!(f, "model has no layers"),
+            Error::InvalidHyperparameter { name, message } => {
+                write!(f, "invalid {}: {}", name, message)
+            }
+            Error::InvalidInput { name, message } => {
+                write!(f, "invalid value of {}: {}", name, message)
+            }
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Text(err) => write!(f, "{}", err),
+            Error::Checkpoint(err) => write!(f, "{}", err),
+            Error::Safetensors(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Text(err) => Some(err),
+            Error::Checkpoint(err) => Some(err),
+            Error::Safetensors(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<TextFormatError> for Error {
+    fn from(err: TextFormatError) -> Self {
+        Error::Text(err)
+    }
+}
+
+impl From<CheckpointError> for Error {
+    fn from(err: CheckpointError) -> Self {
+        Error::Checkpoint(err)
+    }
+}
+
+impl From<SafetensorsError> for Error {
+    fn from(err: SafetensorsError) -> Self {
+        Error::Safetensors(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let err = Error::ShapeMismatch {
+            name: "input",
+            expected: vec![4, 3],
+            found: vec![4, 2],
+        };
+
+        assert_eq!(
+            "shape mismatch of input: expected [4, 3], found [4, 2]",
+            err.to_string()
+        );
+        assert_eq!(
+            "invalid batch_size: must be positive",
+            Error::invalid_hyperparameter("batch_size", "must be positive").to_string()
+        );
+    }
+
+    #[test]
+    fn test_source() {
+        let err = Error::from(CheckpointError::InvalidMagic);
+
+        assert!(error::Error::source(&err).is_some());
+        assert!(error::Error::source(&Error::EmptyModel).is_none());
+    }
+}
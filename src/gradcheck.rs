@@ -0,0 +1,330 @@
+use std::{fmt::Debug, ops::SubAssign};
+
+use ndarray::{Array, Array2, Dimension, ScalarOperand};
+use num_traits::{Float, FromPrimitive};
+
+use crate::{activations::Activation, losses::Loss, training::Rng, Model, Result};
+
+/// Maximum relative errors of the gradients of a single layer, see
+/// [gradcheck].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayerGradCheck<T = f64> {
+    pub weights: T,
+    pub biases: T,
+}
+
+impl<T: Float> LayerGradCheck<T> {
+    pub fn max_error(&self) -> T {
+        self.weights.max(self.biases)
+    }
+}
+
+/// Result of [gradcheck] with the errors of every layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradCheck<T = f64> {
+    /// Errors of the dense layers.
+    pub layers: Vec<LayerGradCheck<T>>,
+
+    /// Maximum relative errors of the layers added with [Model::add], one
+    /// per tensor in the order of
+    /// [Layer::parameters](crate::layers::Layer::parameters).
+    pub parameters: Vec<Vec<T>>,
+}
+
+impl<T: Float> GradCheck<T> {
+    /// Maximum relative error over all layers.
+    pub fn max_error(&self) -> T {
+        self.layers
+            .iter()
+            .map(LayerGradCheck::max_error)
+            .chain(self.parameters.iter().flatten().copied())
+            .fold(T::zero(), T::max)
+    }
+}
+
+/// Compares the gradients of the backward pass of the model with central
+/// finite differences of the loss on the given data.
+///
+/// Every parameter is shifted by `+epsilon` and `-epsilon` and the numeric
+/// gradient `(loss(p + epsilon) - loss(p - epsilon)) / (2 * epsilon)` is
+/// compared with the analytic gradient `a` by the relative error
+/// `|a - n| / max(|a|, |n|, 1e-8)`. The parameters are restored afterwards.
+///
+/// Errors around `1e-7` or lower indicate correct gradients with `f64`
+/// parameters. Larger errors can also be caused by inputs close to a kink of
+/// the activation function, such as zero for ReLU.
+///
+/// Fails like [Model::try_predict] if the data does not match the model.
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::arr2;
+/// use robit::{gradcheck, initializers::RandomDistr, Model};
+///
+/// let mut model: Model = Model::new(0.01);
+/// model.add_layer((2, 3), RandomDistr::normal_with(0.5, 0.1).unwrap());
+/// model.add_layer((3, 1), RandomDistr::normal_with(0.5, 0.1).unwrap());
+///
+/// let x = arr2(&[[0.1, 0.9], [0.5, 0.3]]);
+/// let y = arr2(&[[1.0], [0.0]]);
+///
+/// let check = gradcheck(&mut model, &x, &y, 1e-6).unwrap();
+///
+/// assert!(check.max_error() < 1e-6);
+/// ```
+pub fn gradcheck<A, L, T, D>(
+    model: &mut Model<A, L, T>,
+    x: &Array<T, D>,
+    y: &Array2<T>,
+    epsilon: T,
+) -> Result<GradCheck<T>>
+where
+    D: Dimension,
+    A: Activation<T>,
+    L: Loss<T>,
+    T: Float + FromPrimitive + ScalarOperand + Debug + SubAssign<T>,
+{
+    model.check_input("input", x)?;
+    model.check_targets("targets", x, y)?;
+
+    // every evaluation draws the same random numbers, e.g. dropout masks
+    let rng = Rng::new(0);
+    let gradients = model.gradients(x, y, &mut rng.clone());
+    let two = T::one() + T::one();
+
+    let numeric = |model: &mut Model<A, L, T>, parameter: Parameter| {
+        let original = *parameter.get(model);
+
+        *parameter.get(model) = original + epsilon;
+        let loss_plus = model.training_loss(x, y, &mut rng.clone());
+        *parameter.get(model) = original - epsilon;
+        let loss_minus = model.training_loss(x, y, &mut rng.clone());
+        *parameter.get(model) = original;
+
+        (loss_plus - loss_minus) / (two * epsilon)
+    };
+
+    let mut layers = vec![];
+
+    for (layer, (dw, db)) in gradients
+        .weights
+        .iter()
+        .zip(gradients.biases.iter())
+        .enumerate()
+    {
+        let mut check = LayerGradCheck {
+            weights: T::zero(),
+            biases: T::zero(),
+        };
+
+        for ((row, col), analytic) in dw.indexed_iter() {
+            let n = numeric(model, Parameter::Weight(layer, row, col));
+            check.weights = check.weights.max(relative_error(*analytic, n));
+        }
+
+        for (i, analytic) in db.iter().enumerate() {
+            let n = numeric(model, Parameter::Bias(layer, i));
+            check.biases = check.biases.max(relative_error(*analytic, n));
+        }
+
+        layers.push(check);
+    }
+
+    let mut parameters = vec![];
+
+    for (k, layer) in gradients.layers.iter().enumerate() {
+        let mut errors = vec![];
+
+        for (j, gradient) in layer.iter().enumerate() {
+            let mut error = T::zero();
+
+            for (i, analytic) in gradient.iter().enumerate() {
+                let n = numeric(model, Parameter::Layer(k, j, i));
+                error = error.max(relative_error(*analytic, n));
+            }
+
+            errors.push(error);
+        }
+
+        parameters.push(errors);
+    }
+
+    Ok(GradCheck { layers, parameters })
+}
+
+/// Position of a single parameter value in the model.
+#[derive(Clone, Copy)]
+enum Parameter {
+    Weight(usize, usize, usize),
+    Bias(usize, usize),
+
+    /// Layer added with [Model::add], tensor and index in logical order.
+    Layer(usize, usize, usize),
+}
+
+impl Parameter {
+    fn get<A, L, T>(self, model: &mut Model<A, L, T>) -> &mut T
+    where
+        A: Activation<T>,
+        L: Loss<T>,
+    {
+        if let Parameter::Layer(k, j, i) = self {
+            let tensor = model.layer_mut(k).parameters_mut().swap_remove(j);
+            return tensor.iter_mut().nth(i).unwrap();
+        }
+
+        let (weights, biases) = model.parameters_mut();
+
+        match self {
+            Parameter::Weight(layer, row, col) => &mut weights[layer][(row, col)],
+            Parameter::Bias(layer, i) => &mut biases[layer][i],
+            Parameter::Layer(..) => unreachable!(),
+        }
+    }
+}
+
+fn relative_error<T: Float>(analytic: T, numeric: T) -> T {
+    let scale = analytic
+        .abs()
+        .max(numeric.abs())
+        .max(T::from(1e-8).unwrap());
+
+    (analytic - numeric).abs() / scale
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr1, arr2, Array};
+
+    use super::*;
+    use crate::{
+        initializers::Zeros,
+        layers::{AvgPool2d, BatchNorm1d, Conv2d, Flatten, Layer, LayerNorm, RMSNorm},
+    };
+
+    fn model() -> Model {
+        let mut model = Model::new(0.01);
+        model.set_parameters(
+            vec![
+                arr2(&[[0.5, 0.3, 0.8], [0.2, 0.4, 0.1]]),
+                arr2(&[[0.7], [0.6], [0.9]]),
+            ],
+            vec![arr1(&[0.1, 0.2, 0.3]), arr1(&[0.05])],
+        );
+        model
+    }
+
+    #[test]
+    fn test_relative_error() {
+        assert_eq!(0.0, relative_error(2.0, 2.0));
+        assert!((relative_error(1.0, 1.1) - 0.1 / 1.1).abs() < 1e-12);
+        assert!(relative_error(1e-12, 2e-12) < 1e-3);
+    }
+
+    #[test]
+    fn test_gradcheck() {
+        let mut model = model();
+        let x = arr2(&[[0.1, 0.9], [0.5, 0.3], [0.8, 0.2], [0.4, 0.6]]);
+        let y = arr2(&[[1.0], [0.2], [0.7], [0.4]]);
+
+        let check = gradcheck(&mut model, &x, &y, 1e-6).unwrap();
+
+        assert_eq!(2, check.layers.len());
+        assert!(check.max_error() < 1e-7, "{:?}", check);
+    }
+
+    /// Returns [model] with the layer between the dense layers.
+    fn with_layer(layer: impl Layer + 'static) -> Model {
+        let source = model();
+        let mut model: Model = Model::new(0.01);
+        model.add_layer((2, 3), Zeros);
+        model.add(layer);
+        model.add_layer((3, 1), Zeros);
+        model.set_parameters(source.weights().to_vec(), source.biases().to_vec());
+        model
+    }
+
+    #[test]
+    fn test_batch_norm() {
+        let mut model = with_layer(BatchNorm1d::new(3));
+        let x = arr2(&[[0.1, 0.9], [0.5, 0.3], [0.8, 0.2], [0.4, 0.6]]);
+        let y = arr2(&[[1.0], [0.2], [0.7], [0.4]]);
+
+        let check = gradcheck(&mut model, &x, &y, 1e-6).unwrap();
+
+        assert_eq!(2, check.parameters[0].len());
+        assert!(check.parameters[0].iter().all(|&e| e < 1e-7), "{:?}", check);
+        assert!(check.layers[1].max_error() < 1e-7, "{:?}", check);
+        // the biases before the normalization do not change the loss, so
+        // only the rounding errors of the numeric gradients remain
+        assert!(check.layers[0].weights < 1e-6, "{:?}", check);
+    }
+
+    #[test]
+    fn test_layer_norm() {
+        let x = arr2(&[[0.1, 0.9], [0.5, 0.3], [0.8, 0.2], [0.4, 0.6]]);
+        let y = arr2(&[[1.0], [0.2], [0.7], [0.4]]);
+
+        let mut model = with_layer(LayerNorm::new(3));
+        let check = gradcheck(&mut model, &x, &y, 1e-6).unwrap();
+        assert_eq!(2, check.parameters[0].len());
+        assert!(check.max_error() < 1e-6, "{:?}", check);
+
+        let mut model = with_layer(RMSNorm::new(3));
+        let check = gradcheck(&mut model, &x, &y, 1e-6).unwrap();
+        assert_eq!(1, check.parameters[0].len());
+        assert!(check.max_error() < 1e-6, "{:?}", check);
+    }
+
+    #[test]
+    fn test_conv() {
+        let mut model: Model = Model::new(0.01);
+        model.add(Conv2d::new((2, 3), (2, 2), Zeros).unwrap().stride((1, 2)));
+        model.add(AvgPool2d::new((2, 1)));
+        model.add(Flatten);
+        model.add_layer((6, 2), Zeros);
+
+        for (i, p) in model.layer_mut(0).parameters_mut().into_iter().enumerate() {
+            *p = Array::linspace(0.0, 5.0, p.len())
+                .mapv(|v| (v + i as f64).cos() * 0.5)
+                .into_shape(p.raw_dim())
+                .unwrap();
+        }
+        model.set_parameters(
+            vec![Array::linspace(-0.6, 0.8, 12).into_shape((6, 2)).unwrap()],
+            vec![arr1(&[0.1, -0.2])],
+        );
+
+        let x = Array::from_shape_fn((3, 2, 4, 4), |(n, c, i, j)| {
+            ((n * 32 + c * 16 + i * 4 + j) as f64 * 0.9).sin()
+        });
+        let y = arr2(&[[1.0, 0.2], [0.7, 0.4], [0.1, 0.9]]);
+
+        let check = gradcheck(&mut model, &x, &y, 1e-6).unwrap();
+
+        assert_eq!(2, check.parameters[0].len());
+        assert!(check.max_error() < 1e-6, "{:?}", check);
+    }
+
+    #[test]
+    fn test_restores_parameters() {
+        let mut model = model();
+        let weights = model.weights().to_vec();
+        let x = arr2(&[[0.1, 0.9]]);
+        let y = arr2(&[[1.0]]);
+
+        gradcheck(&mut model, &x, &y, 1e-3).unwrap();
+
+        assert_eq!(weights, model.weights());
+    }
+
+    #[test]
+    fn test_shape_mismatch() {
+        let mut model = model();
+        let x = arr2(&[[0.1, 0.9, 0.5]]);
+        let y = arr2(&[[1.0]]);
+
+        assert!(gradcheck(&mut model, &x, &y, 1e-6).is_err());
+    }
+}
@@ -4,9 +4,9 @@ use ndarray::{Array, Dimension, ShapeBuilder, StrideShape};
 use ndarray_rand::RandomExt;
 use num_traits::Float;
 use rand::{rngs::ThreadRng, thread_rng, Rng};
-use rand_distr::{
-    uniform::SampleUniform, Distribution, Normal, NormalError, StandardNormal, Uniform,
-};
+use rand_distr::{uniform::SampleUniform, Distribution, Normal, StandardNormal, Uniform};
+
+use crate::{Error, Result};
 
 use super::Initializer;
 
@@ -121,10 +121,8 @@ impl RandomDistr<Normal<f64>, f64> {
     /// let values: [_; 2] = initializer.gen();
     /// ```
     pub fn normal() -> Self {
-        Self {
-            dist: Normal::new(DEFAULT_NORMAL_MEAN, DEFAULT_NORMAL_STD_DEV).unwrap(),
-            _phantom: PhantomData,
-        }
+        Self::normal_with(DEFAULT_NORMAL_MEAN, DEFAULT_NORMAL_STD_DEV)
+            .expect("default parameters are valid")
     }
 }
 
@@ -135,6 +133,9 @@ where
 {
     /// Initializer that generates values with a normal distribution.
     ///
+    /// Fails with an [Error::InvalidHyperparameter] if the standard deviation
+    /// is negative or not finite.
+    ///
     /// # Examples
     ///
     /// ```
@@ -147,9 +148,10 @@ where
     ///
     /// let values: [_; 2] = initializer.gen();
     /// ```
-    pub fn normal_with(mean: T, std_dev: T) -> Result<Self, NormalError> {
+    pub fn normal_with(mean: T, std_dev: T) -> Result<Self> {
         Ok(Self {
-            dist: Normal::new(mean, std_dev)?,
+            dist: Normal::new(mean, std_dev)
+                .map_err(|err| Error::invalid_hyperparameter("std_dev", err))?,
             _phantom: PhantomData,
         })
     }
@@ -172,32 +174,41 @@ impl RandomDistr<Uniform<f64>, f64> {
     /// let values: [_; 2] = initializer.gen();
     /// ```
     pub fn uniform() -> Self {
-        Self {
-            dist: Uniform::new(DEFAULT_UNIFORM_LOW, DEFAULT_UNIFORM_HIGH),
-            _phantom: PhantomData,
-        }
+        Self::uniform_with(DEFAULT_UNIFORM_LOW, DEFAULT_UNIFORM_HIGH)
+            .expect("default parameters are valid")
     }
 }
 
 impl<T> RandomDistr<Uniform<T>, T>
 where
-    T: SampleUniform,
+    T: SampleUniform + PartialOrd,
 {
-    /// Initializer that generates values with a uniform distribution.
+    /// Initializer that generates values with a uniform distribution in the
+    /// range `[low, high)`.
+    ///
+    /// Fails with an [Error::InvalidHyperparameter] if `low` is not less than
+    /// `high`.
     ///
     /// # Examples
     ///
     /// ```
     /// use robit::initializers::{RandomDistr, Initializer};
     ///
-    /// let mut initializer = RandomDistr::uniform_with(-0.05, 0.05);
+    /// let mut initializer = RandomDistr::uniform_with(-0.05, 0.05).unwrap();
     ///
     /// let values: [_; 2] = initializer.gen();
     /// ```
-    pub fn uniform_with(low: T, high: T) -> Self {
-        Self {
-            dist: Uniform::new(low, high),
-            _phantom: PhantomData,
+    pub fn uniform_with(low: T, high: T) -> Result<Self> {
+        if low < high {
+            Ok(Self {
+                dist: Uniform::new(low, high),
+                _phantom: PhantomData,
+            })
+        } else {
+            Err(Error::invalid_hyperparameter(
+                "high",
+                "must be greater than low",
+            ))
         }
     }
 }
@@ -0,0 +1,767 @@
+use ndarray::{ArrayD, ArrayView2, ScalarOperand};
+use num_traits::{Float, FromPrimitive, Zero};
+
+use crate::{
+    autograd::Var,
+    initializers::{Initializer, Zeros},
+    Error, Result,
+};
+
+use super::{
+    check_sequence, check_values, flag, positive_integer, Context, Dropout, Layer, LayerNorm,
+};
+
+/// Multi-head scaled dot-product self-attention over sequences with the
+/// shape `[batch, time, dim]`.
+///
+/// The input is projected to queries `Q`, keys `K` and values `V`, which
+/// are split into `num_heads` heads of `dim / num_heads` features. Every
+/// head computes `softmax(Q·Kᵀ / √(dim / num_heads))·V`, the heads are
+/// concatenated and projected back to `dim` features, so the output has the
+/// shape of the input.
+///
+/// A causal layer only attends to the current and earlier time steps, e.g.
+/// for autoregressive models. Padded time steps are masked with the
+/// [Context::padding_mask], e.g. of an earlier [Embedding](super::Embedding)
+/// with a padding index, or with [MultiHeadAttention::forward_with_mask] in
+/// custom layers.
+///
+/// The parameters are the weights with the shape `[dim, dim]` and biases
+/// with the shape `[dim]` of the query, key, value and output projections.
+///
+/// [Vaswani, Ashish, et al. “Attention Is All You Need.” Advances in Neural
+/// Information Processing Systems 30
+/// (2017).](https://arxiv.org/abs/1706.03762)
+///
+/// # Examples
+///
+/// ```
+/// use robit::{initializers::RandomDistr, layers::{Layer, MultiHeadAttention}};
+///
+/// let attention: MultiHeadAttention = MultiHeadAttention::new(64, 8, RandomDistr::normal())
+///     .unwrap()
+///     .causal(true);
+///
+/// assert_eq!(vec![10, 64], attention.output_shape(&[10, 64]).unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiHeadAttention<T = f64> {
+    num_heads: usize,
+    causal: bool,
+
+    /// Weight and bias of the query, key, value and output projections.
+    parameters: Vec<ArrayD<T>>,
+}
+
+impl<T> MultiHeadAttention<T> {
+    /// Creates a layer that attends to all time steps.
+    ///
+    /// Fails with an [Error::InvalidHyperparameter] if `dim` or `num_heads`
+    /// is zero or `num_heads` does not divide `dim`.
+    pub fn new<I: Initializer<T>>(dim: usize, num_heads: usize, init: I) -> Result<Self> {
+        Self::with_init(dim, num_heads, &init)
+    }
+
+    fn with_init<I: Initializer<T>>(dim: usize, num_heads: usize, init: &I) -> Result<Self> {
+        if dim == 0 {
+            return Err(Error::invalid_hyperparameter("dim", "must be positive"));
+        }
+        if num_heads == 0 || !dim.is_multiple_of(num_heads) {
+            return Err(Error::invalid_hyperparameter(
+                "num_heads",
+                format!("must divide the dimension {}", dim),
+            ));
+        }
+
+        Ok(Self {
+            num_heads,
+            causal: false,
+            parameters: (0..4)
+                .flat_map(|_| [init.gen(vec![dim, dim]), init.gen(vec![dim])])
+                .collect(),
+        })
+    }
+
+    /// Whether a time step only attends to itself and earlier time steps.
+    pub fn causal(mut self, causal: bool) -> Self {
+        self.causal = causal;
+        self
+    }
+
+    pub fn dim(&self) -> usize {
+        self.parameters[1].len()
+    }
+
+    pub fn num_heads(&self) -> usize {
+        self.num_heads
+    }
+
+    pub fn is_causal(&self) -> bool {
+        self.causal
+    }
+
+    /// Records the output like [Layer::forward] where the time steps marked
+    /// in `padding_mask` with the shape `[batch, time]` are ignored as keys,
+    /// e.g. the padding of shorter sequences.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mask does not match the batch size and number of time
+    /// steps of the input.
+    pub fn forward_with_mask<'t>(
+        &self,
+        input: Var<'t, T>,
+        padding_mask: Option<ArrayView2<bool>>,
+        context: &mut Context<'_, 't, T>,
+    ) -> Var<'t, T>
+    where
+        T: Float + ScalarOperand + 'static,
+    {
+        let [wq, bq, wk, bk, wv, bv, wo, bo] = context.parameters() else {
+            unreachable!("multi-head attention has eight parameters");
+        };
+        let shape = input.shape();
+        let [batch, time, dim] = shape[..] else {
+            panic!(
+                "attention expects sequences with three axes, found {:?}",
+                shape
+            );
+        };
+        let heads = self.num_heads;
+        let head_dim = dim / heads;
+
+        // [batch, heads, time, head_dim]
+        let split_heads = |projection: Var<'t, T>| {
+            projection
+                .reshape(&[batch, time, heads, head_dim])
+                .permute(&[0, 2, 1, 3])
+        };
+        let queries = split_heads(input.matmul(*wq) + *bq);
+        let keys = split_heads(input.matmul(*wk) + *bk);
+        let values = split_heads(input.matmul(*wv) + *bv);
+
+        // [batch, heads, time, time]
+        let mut scores = queries
+            .matmul(keys.permute(&[0, 1, 3, 2]))
+            .scale(T::one() / T::from(head_dim).unwrap().sqrt());
+
+        if let Some(padding_mask) = padding_mask {
+            assert_eq!(
+                &[batch, time],
+                padding_mask.shape(),
+                "padding mask must have the shape [batch, time]"
+            );
+        }
+        if self.causal || padding_mask.is_some() {
+            // large instead of infinite so that fully masked rows stay finite
+            let masked = T::from(-1e9).unwrap();
+            let mask = ArrayD::from_shape_fn(vec![batch, 1, time, time], |index| {
+                let (n, query, key) = (index[0], index[2], index[3]);
+
+                if (self.causal && key > query) || padding_mask.is_some_and(|m| m[[n, key]]) {
+                    masked
+                } else {
+                    T::zero()
+                }
+            });
+            scores = scores + input.tape().constant(mask);
+        }
+
+        scores
+            .softmax(3)
+            .matmul(values)
+            .permute(&[0, 2, 1, 3])
+            .reshape(&[batch, time, dim])
+            .matmul(*wo)
+            + *bo
+    }
+
+    pub(crate) fn from_config(config: &[f64], max_values: usize) -> Option<Self>
+    where
+        T: Zero + Clone,
+    {
+        let &[dim, num_heads, causal] = config else {
+            return None;
+        };
+        let dim = positive_integer(dim)?;
+        check_values(Self::values(dim), max_values)?;
+
+        Some(
+            Self::new(dim, positive_integer(num_heads)?, Zeros)
+                .ok()?
+                .causal(flag(causal)?),
+        )
+    }
+
+    /// Number of parameter values of the four projections, `None` on
+    /// overflow.
+    fn values(dim: usize) -> Option<usize> {
+        dim.checked_add(1)?.checked_mul(dim)?.checked_mul(4)
+    }
+}
+
+impl<T> Layer<T> for MultiHeadAttention<T>
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "multi_head_attention"
+    }
+
+    fn config(&self) -> Vec<f64> {
+        vec![
+            self.dim() as f64,
+            self.num_heads as f64,
+            self.causal as u8 as f64,
+        ]
+    }
+
+    /// Fails with an [Error::ShapeMismatch] if the input is not a non-empty
+    /// sequence with [MultiHeadAttention::dim] features.
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        check_sequence("multi_head_attention input", Some(self.dim()), input_shape)
+    }
+
+    fn keeps_time_axis(&self) -> bool {
+        true
+    }
+
+    /// Ignores the time steps marked in the [Context::padding_mask] as keys.
+    fn forward<'t>(&self, input: Var<'t, T>, context: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        let padding_mask = context.padding_mask().cloned();
+        self.forward_with_mask(input, padding_mask.as_ref().map(|m| m.view()), context)
+    }
+
+    fn parameters(&self) -> Vec<&ArrayD<T>> {
+        self.parameters.iter().collect()
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut ArrayD<T>> {
+        self.parameters.iter_mut().collect()
+    }
+}
+
+/// Transformer encoder block over sequences with the shape `[batch, time,
+/// dim]`.
+///
+/// The block applies [MultiHeadAttention] and a feed-forward network with
+/// `hidden` ReLU units, each followed by dropout, a residual connection and
+/// [LayerNorm]:
+///
+/// ```text
+/// x = norm_1(x + dropout(attention(x)))
+/// y = norm_2(x + dropout(relu(x·W1 + b1)·W2 + b2))
+/// ```
+///
+/// Stacking blocks after an [Embedding](super::Embedding) and a positional
+/// encoding, e.g. [SinusoidalPositionalEncoding](super::SinusoidalPositionalEncoding),
+/// gives the encoder of a transformer.
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::arr2;
+/// use robit::{
+///     initializers::RandomDistr,
+///     layers::{Dropout, Embedding, Flatten, SinusoidalPositionalEncoding, TransformerEncoderBlock},
+///     Model,
+/// };
+///
+/// let mut model: Model = Model::new(0.01);
+/// model.add(Embedding::new(100, 16, RandomDistr::normal()).unwrap());
+/// model.add(SinusoidalPositionalEncoding);
+/// model.add(
+///     TransformerEncoderBlock::new(16, 4, 32, RandomDistr::normal())
+///         .unwrap()
+///         .dropout(Dropout::new(0.1).unwrap()),
+/// );
+/// model.add(Flatten);
+/// model.add_layer((3 * 16, 1), RandomDistr::normal());
+///
+/// let tokens = arr2(&[[4.0, 42.0, 7.0], [7.0, 1.0, 0.0]]);
+///
+/// assert_eq!(&[2, 1], model.predict(&tokens).shape());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransformerEncoderBlock<T = f64> {
+    attention: MultiHeadAttention<T>,
+    norm_1: LayerNorm<T>,
+    norm_2: LayerNorm<T>,
+    dropout: Dropout,
+
+    /// Weights and biases of the two layers of the feed-forward network.
+    feed_forward: Vec<ArrayD<T>>,
+}
+
+impl<T: Float> TransformerEncoderBlock<T> {
+    /// Creates a block without dropout that attends to all time steps.
+    ///
+    /// Fails with an [Error::InvalidHyperparameter] if `dim`, `num_heads`
+    /// or `hidden` is zero or `num_heads` does not divide `dim`.
+    pub fn new<I: Initializer<T>>(
+        dim: usize,
+        num_heads: usize,
+        hidden: usize,
+        init: I,
+    ) -> Result<Self> {
+        let attention = MultiHeadAttention::with_init(dim, num_heads, &init)?;
+
+        if hidden == 0 {
+            return Err(Error::invalid_hyperparameter("hidden", "must be positive"));
+        }
+
+        Ok(Self {
+            attention,
+            norm_1: LayerNorm::new(dim),
+            norm_2: LayerNorm::new(dim),
+            dropout: Dropout::new(0.0)?,
+            feed_forward: vec![
+                init.gen(vec![dim, hidden]),
+                init.gen(vec![hidden]),
+                init.gen(vec![hidden, dim]),
+                init.gen(vec![dim]),
+            ],
+        })
+    }
+
+    /// Whether a time step only attends to itself and earlier time steps.
+    pub fn causal(mut self, causal: bool) -> Self {
+        self.attention = self.attention.causal(causal);
+        self
+    }
+
+    /// Dropout applied to the outputs of the attention and the feed-forward
+    /// network during training.
+    pub fn dropout(mut self, dropout: Dropout) -> Self {
+        self.dropout = dropout;
+        self
+    }
+
+    pub fn dim(&self) -> usize {
+        self.attention.dim()
+    }
+
+    pub fn num_heads(&self) -> usize {
+        self.attention.num_heads()
+    }
+
+    pub fn hidden(&self) -> usize {
+        self.feed_forward[1].len()
+    }
+
+    pub fn is_causal(&self) -> bool {
+        self.attention.is_causal()
+    }
+
+    /// Records the output like [Layer::forward] with a padding mask for the
+    /// attention, see [MultiHeadAttention::forward_with_mask].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mask does not match the batch size and number of time
+    /// steps of the input.
+    pub fn forward_with_mask<'t>(
+        &self,
+        input: Var<'t, T>,
+        padding_mask: Option<ArrayView2<bool>>,
+        context: &mut Context<'_, 't, T>,
+    ) -> Var<'t, T>
+    where
+        T: FromPrimitive + ScalarOperand + 'static,
+    {
+        let parameters = context.parameters();
+        let [wq, bq, wk, bk, wv, bv, wo, bo, gamma_1, beta_1, w1, b1, w2, b2, gamma_2, beta_2] =
+            *parameters
+        else {
+            unreachable!("transformer encoder blocks have sixteen parameters");
+        };
+
+        let attention = self.attention.forward_with_mask(
+            input,
+            padding_mask,
+            &mut context.sublayer(&[wq, bq, wk, bk, wv, bv, wo, bo]),
+        );
+        let attention = self.dropout.forward(attention, context);
+        let x = self
+            .norm_1
+            .forward(input + attention, &mut context.sublayer(&[gamma_1, beta_1]));
+
+        let feed_forward = (x.matmul(w1) + b1).relu().matmul(w2) + b2;
+        let feed_forward = self.dropout.forward(feed_forward, context);
+
+        self.norm_2
+            .forward(x + feed_forward, &mut context.sublayer(&[gamma_2, beta_2]))
+    }
+
+    pub(crate) fn from_config(config: &[f64], max_values: usize) -> Option<Self>
+    where
+        T: Clone,
+    {
+        let &[dim, num_heads, hidden, causal, dropout] = config else {
+            return None;
+        };
+        let (dim, hidden) = (positive_integer(dim)?, positive_integer(hidden)?);
+        check_values(Self::values(dim, hidden), max_values)?;
+
+        Some(
+            Self::new(dim, positive_integer(num_heads)?, hidden, Zeros)
+                .ok()?
+                .causal(flag(causal)?)
+                .dropout(Dropout::new(dropout).ok()?),
+        )
+    }
+
+    /// Number of parameter values of the attention, the two normalizations
+    /// and the feed-forward network, `None` on overflow.
+    fn values(dim: usize, hidden: usize) -> Option<usize> {
+        let norms = dim.checked_mul(4)?;
+        let feed_forward = dim.checked_mul(2)?.checked_add(1)?.checked_mul(hidden)?;
+
+        MultiHeadAttention::<T>::values(dim)?
+            .checked_add(norms)?
+            .checked_add(feed_forward)?
+            .checked_add(dim)
+    }
+}
+
+impl<T> Layer<T> for TransformerEncoderBlock<T>
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "transformer_encoder_block"
+    }
+
+    fn config(&self) -> Vec<f64> {
+        vec![
+            self.dim() as f64,
+            self.num_heads() as f64,
+            self.hidden() as f64,
+            self.is_causal() as u8 as f64,
+            self.dropout.rate(),
+        ]
+    }
+
+    /// Fails with an [Error::ShapeMismatch] if the input is not a non-empty
+    /// sequence with [TransformerEncoderBlock::dim] features.
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        check_sequence(
+            "transformer_encoder_block input",
+            Some(self.dim()),
+            input_shape,
+        )
+    }
+
+    fn keeps_time_axis(&self) -> bool {
+        true
+    }
+
+    /// Ignores the time steps marked in the [Context::padding_mask] as keys.
+    fn forward<'t>(&self, input: Var<'t, T>, context: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        let padding_mask = context.padding_mask().cloned();
+        self.forward_with_mask(input, padding_mask.as_ref().map(|m| m.view()), context)
+    }
+
+    /// Parameters of the attention, the first normalization, the
+    /// feed-forward network and the second normalization.
+    fn parameters(&self) -> Vec<&ArrayD<T>> {
+        let mut parameters = self.attention.parameters();
+        parameters.extend(self.norm_1.parameters());
+        parameters.extend(&self.feed_forward);
+        parameters.extend(self.norm_2.parameters());
+        parameters
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut ArrayD<T>> {
+        let mut parameters = self.attention.parameters_mut();
+        parameters.extend(self.norm_1.parameters_mut());
+        parameters.extend(&mut self.feed_forward);
+        parameters.extend(self.norm_2.parameters_mut());
+        parameters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, s, Array, Array1, Array2, Array3, Axis};
+
+    use super::*;
+    use crate::{
+        autograd::check,
+        layers::{
+            tests::{self, forward_with, values},
+            Mode,
+        },
+        training::Rng,
+    };
+
+    fn deterministic<L: Layer>(mut layer: L) -> L {
+        tests::deterministic(layer.parameters_mut());
+        layer
+    }
+
+    fn attention(causal: bool) -> MultiHeadAttention {
+        deterministic(MultiHeadAttention::new(4, 2, Zeros).unwrap().causal(causal))
+    }
+
+    fn sequences() -> Array3<f64> {
+        Array::from_shape_fn((2, 3, 4), |(n, t, f)| ((n * 12 + t * 4 + f) as f64).sin())
+    }
+
+    fn forward(
+        attention: &MultiHeadAttention,
+        x: &Array3<f64>,
+        padding_mask: Option<&Array2<bool>>,
+    ) -> Array3<f64> {
+        forward_with(
+            attention.parameters.iter().collect(),
+            &x.clone().into_dyn(),
+            Mode::Training,
+            |x, context| attention.forward_with_mask(x, padding_mask.map(|m| m.view()), context),
+        )
+        .output
+        .into_dimensionality()
+        .unwrap()
+    }
+
+    /// Attention computed with loops over the samples and heads.
+    fn reference(
+        attention: &MultiHeadAttention,
+        x: &Array3<f64>,
+        padding_mask: Option<&Array2<bool>>,
+    ) -> Array3<f64> {
+        let p = &attention.parameters;
+        let weight = |i: usize| -> Array2<f64> { p[i].clone().into_dimensionality().unwrap() };
+        let bias = |i: usize| -> Array1<f64> { p[i].clone().into_dimensionality().unwrap() };
+        let (batch, time, dim) = x.dim();
+        let head_dim = dim / attention.num_heads;
+        let mut output = Array3::zeros((batch, time, dim));
+
+        for n in 0..batch {
+            let x = x.index_axis(Axis(0), n);
+            let (q, k, v) = (
+                x.dot(&weight(0)) + bias(1),
+                x.dot(&weight(2)) + bias(3),
+                x.dot(&weight(4)) + bias(5),
+            );
+            let mut heads = Array2::zeros((time, dim));
+
+            for h in 0..attention.num_heads {
+                let columns = s![.., h * head_dim..(h + 1) * head_dim];
+                let mut scores =
+                    q.slice(columns).dot(&k.slice(columns).t()) / (head_dim as f64).sqrt();
+
+                for ((query, key), score) in scores.indexed_iter_mut() {
+                    let padded = padding_mask.is_some_and(|m| m[[n, key]]);
+                    if (attention.causal && key > query) || padded {
+                        *score = f64::NEG_INFINITY;
+                    }
+                }
+                for mut row in scores.rows_mut() {
+                    let max = row.fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+                    row.mapv_inplace(|s| (s - max).exp());
+                    let sum = row.sum();
+                    row /= sum;
+                }
+
+                heads
+                    .slice_mut(columns)
+                    .assign(&scores.dot(&v.slice(columns)));
+            }
+
+            output
+                .index_axis_mut(Axis(0), n)
+                .assign(&(heads.dot(&weight(6)) + bias(7)));
+        }
+
+        output
+    }
+
+    fn assert_close(found: &Array3<f64>, expected: &Array3<f64>) {
+        assert!(
+            found
+                .iter()
+                .zip(expected)
+                .all(|(a, b)| (a - b).abs() < 1e-12),
+            "{} != {}",
+            found,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_forward() {
+        let x = sequences();
+        let padding_mask = arr2(&[[false, false, true], [false, false, false]]);
+
+        for causal in [false, true] {
+            let attention = attention(causal);
+
+            assert_close(
+                &forward(&attention, &x, None),
+                &reference(&attention, &x, None),
+            );
+            assert_close(
+                &forward(&attention, &x, Some(&padding_mask)),
+                &reference(&attention, &x, Some(&padding_mask)),
+            );
+        }
+    }
+
+    #[test]
+    fn test_masks() {
+        let x = sequences();
+        let mut changed = x.clone();
+        changed.slice_mut(s![.., 2, ..]).fill(5.0);
+
+        // earlier time steps do not attend to the last one
+        let attention = attention(true);
+        let output = forward(&attention, &x, None);
+        let changed_output = forward(&attention, &changed, None);
+        assert_close(
+            &output.slice(s![.., ..2, ..]).to_owned(),
+            &changed_output.slice(s![.., ..2, ..]).to_owned(),
+        );
+        assert!((output[[0, 2, 0]] - changed_output[[0, 2, 0]]).abs() > 1e-6);
+
+        // no time step attends to the padded one
+        let attention = self::attention(false);
+        let padding_mask = Array2::from_shape_fn((2, 3), |(_, t)| t == 2);
+        let output = forward(&attention, &x, Some(&padding_mask));
+        let changed_output = forward(&attention, &changed, Some(&padding_mask));
+        assert_close(
+            &output.slice(s![.., ..2, ..]).to_owned(),
+            &changed_output.slice(s![.., ..2, ..]).to_owned(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "padding mask")]
+    fn test_invalid_mask() {
+        forward(
+            &attention(false),
+            &sequences(),
+            Some(&Array2::from_elem((2, 4), false)),
+        );
+    }
+
+    #[test]
+    fn test_gradients() {
+        let x = sequences().into_dyn();
+        let weights = Array::linspace(-1.0, 1.0, 24)
+            .into_shape((2, 3, 4))
+            .unwrap();
+        let padding_mask = arr2(&[[false, true, false], [false, false, false]]);
+
+        let attention = attention(true);
+        let mut inputs = vec![x.clone()];
+        inputs.extend(attention.parameters.iter().cloned());
+        check(&inputs, |v| {
+            let tape = v[0].tape();
+            let mut rng = Rng::new(0);
+            let mut context = Context::new(Mode::Training, &v[1..], &mut rng);
+
+            let y = attention.forward_with_mask(v[0], Some(padding_mask.view()), &mut context);
+            y * tape.constant(weights.clone())
+        });
+
+        let block = deterministic(TransformerEncoderBlock::new(4, 2, 6, Zeros).unwrap());
+        let mut inputs = vec![x];
+        inputs.extend(block.parameters().into_iter().cloned());
+        check(&inputs, |v| {
+            let tape = v[0].tape();
+            let mut rng = Rng::new(0);
+            let mut context = Context::new(Mode::Training, &v[1..], &mut rng);
+
+            block.forward(v[0], &mut context) * tape.constant(weights.clone())
+        });
+    }
+
+    #[test]
+    fn test_encoder_block() {
+        let block = deterministic(TransformerEncoderBlock::new(4, 2, 6, Zeros).unwrap());
+        let x = sequences();
+
+        let run = |block: &TransformerEncoderBlock, mode: Mode| {
+            tests::forward(block, &x.clone().into_dyn(), mode).output
+        };
+
+        let output = run(&block, Mode::Training);
+        assert_eq!(&[2, 3, 4], output.shape());
+
+        let dropout = block.clone().dropout(Dropout::new(0.5).unwrap());
+        assert_eq!(output, run(&dropout, Mode::Inference));
+        assert_ne!(output, run(&dropout, Mode::Training));
+        assert_eq!(16, Layer::<f64>::parameters(&block).len());
+    }
+
+    #[test]
+    fn test_output_shape() {
+        let attention = attention(false);
+        assert_eq!(vec![7, 4], attention.output_shape(&[7, 4]).unwrap());
+
+        let block: TransformerEncoderBlock = TransformerEncoderBlock::new(4, 2, 6, Zeros).unwrap();
+        for input_shape in [&[7, 3][..], &[0, 4], &[4]] {
+            assert!(matches!(
+                attention.output_shape(input_shape),
+                Err(Error::ShapeMismatch {
+                    name: "multi_head_attention input",
+                    ..
+                })
+            ));
+            assert!(block.output_shape(input_shape).is_err());
+        }
+    }
+
+    #[test]
+    fn test_invalid_hyperparameters() {
+        assert!(matches!(
+            MultiHeadAttention::<f64>::new(6, 4, Zeros),
+            Err(Error::InvalidHyperparameter {
+                name: "num_heads",
+                ..
+            })
+        ));
+        assert!(matches!(
+            TransformerEncoderBlock::<f64>::new(4, 2, 0, Zeros),
+            Err(Error::InvalidHyperparameter { name: "hidden", .. })
+        ));
+        assert!(TransformerEncoderBlock::<f64>::new(0, 1, 4, Zeros).is_err());
+    }
+
+    #[test]
+    fn test_from_config() {
+        let attention: MultiHeadAttention =
+            MultiHeadAttention::new(8, 2, Zeros).unwrap().causal(true);
+        assert_eq!(vec![8.0, 2.0, 1.0], attention.config());
+        assert_eq!(
+            Some(attention.clone()),
+            MultiHeadAttention::from_config(&attention.config(), values(&attention))
+        );
+        assert_eq!(
+            None,
+            MultiHeadAttention::<f64>::from_config(&attention.config(), values(&attention) - 1)
+        );
+
+        let block: TransformerEncoderBlock = TransformerEncoderBlock::new(8, 2, 16, Zeros)
+            .unwrap()
+            .dropout(Dropout::new(0.25).unwrap());
+        assert_eq!(vec![8.0, 2.0, 16.0, 0.0, 0.25], block.config());
+        assert_eq!(
+            Some(block.clone()),
+            TransformerEncoderBlock::from_config(&block.config(), values(&block))
+        );
+        assert_eq!(
+            None,
+            TransformerEncoderBlock::<f64>::from_config(&block.config(), values(&block) - 1)
+        );
+        assert_eq!(
+            None,
+            TransformerEncoderBlock::<f64>::from_config(&[8.0, 2.0, 16.0, 0.0, 1.0], usize::MAX)
+        );
+        assert_eq!(
+            None,
+            MultiHeadAttention::<f64>::from_config(&[8.0, 3.0, 0.0], usize::MAX)
+        );
+    }
+}
@@ -0,0 +1,363 @@
+use ndarray::{Array1, ArrayD, ScalarOperand};
+use num_traits::{Float, FromPrimitive};
+
+use crate::{autograd::Var, Error, Result};
+
+use super::{check_values, positive_integer, Context, Layer};
+
+/// Normalizes every feature to zero mean and unit variance over the batch,
+/// followed by a learnable scale `gamma` and shift `beta`.
+///
+/// During training the batch statistics are used and the running mean and
+/// variance are updated with `running = (1 - momentum) * running + momentum *
+/// batch`, where the variance of the batch is unbiased. During inference the
+/// running statistics are used instead, so the output does not depend on the
+/// other samples of the batch.
+///
+/// Inputs have the shape `[batch, features]` or `[batch, features, length]`,
+/// in the latter case the statistics are computed over the batch and length
+/// axes.
+///
+/// [Ioffe, Sergey, and Christian Szegedy. “Batch Normalization: Accelerating
+/// Deep Network Training by Reducing Internal Covariate Shift.” International
+/// Conference on Machine Learning (2015).](https://arxiv.org/abs/1502.03167)
+///
+/// # Examples
+///
+/// ```
+/// use robit::{initializers::RandomDistr, layers::BatchNorm1d, Model};
+///
+/// let mut model: Model = Model::new(0.01);
+/// model.add_layer((4, 16), RandomDistr::normal());
+/// model.add(BatchNorm1d::new(16).momentum(0.05));
+/// model.add_layer((16, 1), RandomDistr::normal());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchNorm1d<T = f64> {
+    momentum: f64,
+    epsilon: f64,
+    gamma: ArrayD<T>,
+    beta: ArrayD<T>,
+    running_mean: ArrayD<T>,
+    running_var: ArrayD<T>,
+}
+
+impl<T: Float> BatchNorm1d<T> {
+    /// Creates the layer with `gamma` set to one, `beta` set to zero, a
+    /// momentum of `0.1` and an epsilon of `1e-5`.
+    pub fn new(features: usize) -> Self {
+        Self {
+            momentum: 0.1,
+            epsilon: 1e-5,
+            gamma: ArrayD::ones(vec![features]),
+            beta: ArrayD::zeros(vec![features]),
+            running_mean: ArrayD::zeros(vec![features]),
+            running_var: ArrayD::ones(vec![features]),
+        }
+    }
+
+    /// Weight of the batch statistics in the update of the running
+    /// statistics.
+    pub fn momentum(mut self, momentum: f64) -> Self {
+        self.momentum = momentum;
+        self
+    }
+
+    /// Value added to the variance to avoid a division by zero.
+    pub fn epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    pub fn features(&self) -> usize {
+        self.gamma.len()
+    }
+
+    pub fn running_mean(&self) -> &ArrayD<T> {
+        &self.running_mean
+    }
+
+    pub fn running_var(&self) -> &ArrayD<T> {
+        &self.running_var
+    }
+
+    pub(crate) fn from_config(config: &[f64], max_values: usize) -> Option<Self> {
+        let [features, momentum, epsilon] = *config else {
+            return None;
+        };
+        let features = positive_integer(features)?;
+        // scale, shift, running mean and running variance
+        check_values(features.checked_mul(4), max_values)?;
+
+        Some(Self::new(features).momentum(momentum).epsilon(epsilon))
+    }
+
+    fn shape_mismatch(&self, expected: Vec<usize>, found: &[usize]) -> Error {
+        Error::ShapeMismatch {
+            name: "batch_norm_1d input",
+            expected,
+            found: found.to_vec(),
+        }
+    }
+}
+
+impl<T> Layer<T> for BatchNorm1d<T>
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "batch_norm_1d"
+    }
+
+    fn config(&self) -> Vec<f64> {
+        vec![self.features() as f64, self.momentum, self.epsilon]
+    }
+
+    /// Fails with an [Error::InvalidHyperparameter] if the momentum is not in
+    /// `[0, 1]` or epsilon is negative.
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        if !(0.0..=1.0).contains(&self.momentum) {
+            return Err(Error::invalid_hyperparameter(
+                "momentum",
+                format!("{} is not in [0, 1]", self.momentum),
+            ));
+        }
+        if self.epsilon.is_nan() || self.epsilon < 0.0 {
+            return Err(Error::invalid_hyperparameter(
+                "epsilon",
+                "must not be negative",
+            ));
+        }
+
+        match input_shape {
+            [features] | [features, _] if *features == self.features() => Ok(input_shape.to_vec()),
+            [_, length] => Err(self.shape_mismatch(vec![self.features(), *length], input_shape)),
+            _ => Err(self.shape_mismatch(vec![self.features()], input_shape)),
+        }
+    }
+
+    fn forward<'t>(&self, input: Var<'t, T>, context: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        let tape = input.tape();
+        let shape = input.shape();
+        let features = self.features();
+
+        // moves the features to the last axis and merges the other axes
+        let x = match shape[..] {
+            [batch, _, length] => input
+                .permute(&[0, 2, 1])
+                .reshape(&[batch * length, features]),
+            _ => input,
+        };
+
+        let [gamma, beta] = context.parameters() else {
+            unreachable!("batch normalization has two parameters");
+        };
+        let epsilon = T::from_f64(self.epsilon).unwrap();
+
+        let x_hat = if context.is_training() {
+            let mean = x.mean_axis(0);
+            let centered = x - mean;
+            let var = centered.square().mean_axis(0);
+
+            let n = T::from_usize(x.shape()[0]).unwrap();
+            let momentum = T::from_f64(self.momentum).unwrap();
+            let keep = T::one() - momentum;
+            let unbiased = if n > T::one() {
+                n / (n - T::one())
+            } else {
+                T::one()
+            };
+
+            context.update_state(vec![
+                &self.running_mean * keep + &mean.value() * momentum,
+                &self.running_var * keep + &var.value() * (momentum * unbiased),
+            ]);
+
+            centered / var.add_scalar(epsilon).sqrt()
+        } else {
+            let std: Array1<T> = self
+                .running_var
+                .iter()
+                .map(|&v| (v + epsilon).sqrt())
+                .collect();
+
+            (x - tape.constant(self.running_mean.clone())) / tape.constant(std)
+        };
+
+        let output = x_hat * *gamma + *beta;
+
+        match shape[..] {
+            [batch, _, length] => output
+                .reshape(&[batch, length, features])
+                .permute(&[0, 2, 1]),
+            _ => output,
+        }
+    }
+
+    fn parameters(&self) -> Vec<&ArrayD<T>> {
+        vec![&self.gamma, &self.beta]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut ArrayD<T>> {
+        vec![&mut self.gamma, &mut self.beta]
+    }
+
+    fn state(&self) -> Vec<&ArrayD<T>> {
+        vec![&self.running_mean, &self.running_var]
+    }
+
+    fn state_mut(&mut self) -> Vec<&mut ArrayD<T>> {
+        vec![&mut self.running_mean, &mut self.running_var]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr1, arr2, Array, Axis};
+
+    use super::*;
+    use crate::{
+        autograd::check,
+        layers::{
+            tests::{forward, values},
+            Mode,
+        },
+        training::Rng,
+    };
+
+    #[test]
+    fn test_output_shape() {
+        let layer: BatchNorm1d = BatchNorm1d::new(3);
+
+        assert_eq!(vec![3], layer.output_shape(&[3]).unwrap());
+        assert_eq!(vec![3, 5], layer.output_shape(&[3, 5]).unwrap());
+        assert!(matches!(
+            layer.output_shape(&[4]),
+            Err(Error::ShapeMismatch { expected, .. }) if expected == vec![3]
+        ));
+    }
+
+    #[test]
+    fn test_invalid_hyperparameters() {
+        for momentum in [-0.1, 1.5, f64::NAN] {
+            assert!(matches!(
+                Layer::<f64>::output_shape(&BatchNorm1d::new(3).momentum(momentum), &[3]),
+                Err(Error::InvalidHyperparameter {
+                    name: "momentum",
+                    ..
+                })
+            ));
+        }
+        assert!(matches!(
+            Layer::<f64>::output_shape(&BatchNorm1d::new(3).epsilon(-1e-5), &[3]),
+            Err(Error::InvalidHyperparameter {
+                name: "epsilon",
+                ..
+            })
+        ));
+        assert!(
+            Layer::<f64>::output_shape(&BatchNorm1d::new(3).momentum(1.0).epsilon(0.0), &[3])
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_training() {
+        let layer = BatchNorm1d::new(2).momentum(0.5);
+        let x = arr2(&[[1.0, 10.0], [3.0, 10.0], [5.0, 40.0]]).into_dyn();
+
+        let result = forward(&layer, &x, Mode::Training);
+        let (y, state) = (result.output, result.state);
+
+        for column in y.axis_iter(Axis(1)) {
+            assert!(column.mean().unwrap().abs() < 1e-12);
+            assert!((column.mapv(|v| v * v).mean().unwrap() - 1.0).abs() < 1e-4);
+        }
+
+        let state = state.unwrap();
+        assert_eq!(arr1(&[1.5, 10.0]).into_dyn(), state[0]);
+        // unbiased variances 4 and 300
+        assert_eq!(arr1(&[2.5, 150.5]).into_dyn(), state[1]);
+    }
+
+    #[test]
+    fn test_inference() {
+        let mut layer = BatchNorm1d::new(2);
+        layer.running_mean = arr1(&[1.0, -2.0]).into_dyn();
+        layer.running_var = arr1(&[4.0, 1.0]).into_dyn();
+        layer.gamma = arr1(&[2.0, 1.0]).into_dyn();
+        layer.beta = arr1(&[0.5, 0.0]).into_dyn();
+
+        let result = forward(
+            &layer.epsilon(0.0),
+            &arr2(&[[3.0, 0.0]]).into_dyn(),
+            Mode::Inference,
+        );
+        let (y, state) = (result.output, result.state);
+
+        assert_eq!(arr2(&[[2.5, 2.0]]).into_dyn(), y);
+        assert!(state.is_none());
+    }
+
+    #[test]
+    fn test_sequence() {
+        let layer = BatchNorm1d::new(2);
+        let x = Array::from_shape_fn((2, 2, 3), |(i, j, k)| {
+            (i * 6 + j * 3 + k) as f64 * (j as f64 + 1.0)
+        });
+
+        let result = forward(&layer, &x.into_dyn(), Mode::Training);
+        let (y, state) = (result.output, result.state);
+
+        assert_eq!(&[2, 2, 3], y.shape());
+        for feature in y.axis_iter(Axis(1)) {
+            assert!(feature.mean().unwrap().abs() < 1e-12);
+        }
+        assert_eq!(2, state.unwrap()[0].len());
+    }
+
+    #[test]
+    fn test_gradients() {
+        let x = arr2(&[
+            [0.5, -1.0, 2.0],
+            [1.5, 0.3, -0.2],
+            [-0.7, 0.9, 0.4],
+            [0.1, 0.2, 1.1],
+        ]);
+        let gamma = arr1(&[1.5, 0.5, -1.0]);
+        let beta = arr1(&[0.1, -0.2, 0.3]);
+        let weights = x.mapv(f64::sin);
+
+        check(&[x.into_dyn(), gamma.into_dyn(), beta.into_dyn()], |v| {
+            let layer = BatchNorm1d::new(3);
+            let mut rng = Rng::new(0);
+            let parameters = [v[1], v[2]];
+            let mut context = Context::new(Mode::Training, &parameters, &mut rng);
+
+            // weighted so that the gradient of the normalization is not zero
+            layer.forward(v[0], &mut context) * v[0].tape().constant(weights.clone())
+        });
+    }
+
+    #[test]
+    fn test_from_config() {
+        let layer: BatchNorm1d = BatchNorm1d::new(4).momentum(0.2).epsilon(1e-3);
+
+        assert_eq!(
+            Some(layer.clone()),
+            BatchNorm1d::from_config(&layer.config(), values(&layer))
+        );
+        assert_eq!(
+            None,
+            BatchNorm1d::<f64>::from_config(&layer.config(), values(&layer) - 1)
+        );
+        assert_eq!(
+            None,
+            BatchNorm1d::<f64>::from_config(&[0.0, 0.1, 1e-5], usize::MAX)
+        );
+        assert_eq!(
+            None,
+            BatchNorm1d::<f64>::from_config(&[2.5, 0.1, 1e-5], usize::MAX)
+        );
+    }
+}
@@ -0,0 +1,465 @@
+use ndarray::{ArrayD, ScalarOperand};
+use num_traits::{Float, FromPrimitive, Zero};
+
+use crate::{
+    autograd::Var,
+    initializers::{Initializer, Zeros},
+    Error, Result,
+};
+
+use super::{check_values, non_negative_integer, positive_integer, window::Window, Context, Layer};
+
+/// 2D convolution over images with the shape `[batch, channels, height,
+/// width]` (NCHW).
+///
+/// The kernel slides over the zero padded image with the given stride,
+/// skipping `dilation - 1` pixels between the kernel elements. With `groups`
+/// greater than one the channels are split into groups that are convolved
+/// independently, e.g. a depthwise convolution has a group per input
+/// channel.
+///
+/// The weight has the shape `[out_channels, in_channels / groups,
+/// kernel_height, kernel_width]` and the bias the shape `[out_channels]`.
+/// Every window is copied into a row of a matrix (im2col) so that the
+/// convolution of a group is a single matrix product.
+///
+/// # Examples
+///
+/// ```
+/// use robit::{initializers::RandomDistr, layers::{Conv2d, Layer}};
+///
+/// let conv: Conv2d = Conv2d::new((1, 8), (3, 3), RandomDistr::normal())
+///     .unwrap()
+///     .stride((2, 2))
+///     .padding((1, 1));
+///
+/// assert_eq!(vec![8, 14, 14], conv.output_shape(&[1, 28, 28]).unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conv2d<T = f64> {
+    window: Window,
+    groups: usize,
+    weight: ArrayD<T>,
+    bias: ArrayD<T>,
+}
+
+impl<T> Conv2d<T> {
+    /// Creates the layer with `channels.0` input and `channels.1` output
+    /// channels, a stride and dilation of one and no padding.
+    ///
+    /// Fails with an [Error::InvalidHyperparameter] if a channel count or
+    /// the kernel size is zero.
+    pub fn new<I: Initializer<T>>(
+        channels: (usize, usize),
+        kernel_size: (usize, usize),
+        init: I,
+    ) -> Result<Self> {
+        Self::grouped(channels, kernel_size, 1, init)
+    }
+
+    /// Creates the layer like [Conv2d::new] with the channels split into
+    /// `groups` groups.
+    ///
+    /// Fails with an [Error::InvalidHyperparameter] if a channel count or
+    /// the kernel size is zero, or the channel counts are not divisible by
+    /// the number of groups.
+    pub fn grouped<I: Initializer<T>>(
+        channels: (usize, usize),
+        kernel_size: (usize, usize),
+        groups: usize,
+        init: I,
+    ) -> Result<Self> {
+        let (in_channels, out_channels) = channels;
+
+        if in_channels == 0 || out_channels == 0 {
+            return Err(Error::invalid_hyperparameter(
+                "channels",
+                "must be positive",
+            ));
+        }
+        if kernel_size.0 == 0 || kernel_size.1 == 0 {
+            return Err(Error::invalid_hyperparameter(
+                "kernel_size",
+                "must be positive",
+            ));
+        }
+        if groups == 0 || in_channels % groups != 0 || out_channels % groups != 0 {
+            return Err(Error::invalid_hyperparameter(
+                "groups",
+                format!(
+                    "must divide the {} input and {} output channels",
+                    in_channels, out_channels
+                ),
+            ));
+        }
+
+        Ok(Self {
+            window: Window {
+                kernel: kernel_size,
+                stride: (1, 1),
+                padding: (0, 0),
+                dilation: (1, 1),
+            },
+            groups,
+            weight: init.gen(vec![
+                out_channels,
+                in_channels / groups,
+                kernel_size.0,
+                kernel_size.1,
+            ]),
+            bias: init.gen(vec![out_channels]),
+        })
+    }
+
+    /// Step between two windows along the height and width.
+    pub fn stride(mut self, stride: (usize, usize)) -> Self {
+        self.window.stride = stride;
+        self
+    }
+
+    /// Number of zeros added at both sides of the height and width.
+    pub fn padding(mut self, padding: (usize, usize)) -> Self {
+        self.window.padding = padding;
+        self
+    }
+
+    /// Step between two kernel elements along the height and width.
+    pub fn dilation(mut self, dilation: (usize, usize)) -> Self {
+        self.window.dilation = dilation;
+        self
+    }
+
+    pub fn in_channels(&self) -> usize {
+        self.weight.shape()[1] * self.groups
+    }
+
+    pub fn out_channels(&self) -> usize {
+        self.weight.shape()[0]
+    }
+
+    pub fn kernel_size(&self) -> (usize, usize) {
+        self.window.kernel
+    }
+
+    pub fn groups(&self) -> usize {
+        self.groups
+    }
+
+    pub(crate) fn from_config(config: &[f64], max_values: usize) -> Option<Self>
+    where
+        T: Zero + Clone,
+    {
+        let &[in_channels, out_channels, kh, kw, sh, sw, ph, pw, dh, dw, groups] = config else {
+            return None;
+        };
+        let pair = |a, b| Some((positive_integer(a)?, positive_integer(b)?));
+        let channels = pair(in_channels, out_channels)?;
+        let kernel_size = pair(kh, kw)?;
+        let groups = positive_integer(groups)?;
+        // weight and bias, the groups are validated by Conv2d::grouped
+        check_values(
+            (channels.0 / groups)
+                .checked_mul(channels.1)
+                .and_then(|n| n.checked_mul(kernel_size.0))
+                .and_then(|n| n.checked_mul(kernel_size.1))
+                .and_then(|n| n.checked_add(channels.1)),
+            max_values,
+        )?;
+
+        Some(
+            Self::grouped(channels, kernel_size, groups, Zeros)
+                .ok()?
+                .stride(pair(sh, sw)?)
+                .padding((non_negative_integer(ph)?, non_negative_integer(pw)?))
+                .dilation(pair(dh, dw)?),
+        )
+    }
+}
+
+impl<T> Layer<T> for Conv2d<T>
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "conv_2d"
+    }
+
+    fn config(&self) -> Vec<f64> {
+        let Window {
+            kernel,
+            stride,
+            padding,
+            dilation,
+        } = self.window;
+
+        [
+            self.in_channels(),
+            self.out_channels(),
+            kernel.0,
+            kernel.1,
+            stride.0,
+            stride.1,
+            padding.0,
+            padding.1,
+            dilation.0,
+            dilation.1,
+            self.groups,
+        ]
+        .iter()
+        .map(|&v| v as f64)
+        .collect()
+    }
+
+    /// Fails with an [Error::ShapeMismatch] if the input is not an image with
+    /// [Conv2d::in_channels] channels that is at least as large as the
+    /// dilated kernel and with an [Error::InvalidHyperparameter] if the
+    /// stride or dilation is zero.
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        self.window.check()?;
+
+        let (height, width) = match *input_shape {
+            [channels, height, width] if channels == self.in_channels() => {
+                if let Some((height, width)) = self.window.output_size((height, width)) {
+                    return Ok(vec![self.out_channels(), height, width]);
+                }
+
+                let (min_height, min_width) = self.window.min_input_size();
+                (height.max(min_height), width.max(min_width))
+            }
+            [_, height, width] => (height, width),
+            _ => self.window.min_input_size(),
+        };
+
+        Err(Error::ShapeMismatch {
+            name: "conv_2d input",
+            expected: vec![self.in_channels(), height, width],
+            found: input_shape.to_vec(),
+        })
+    }
+
+    fn forward<'t>(&self, input: Var<'t, T>, context: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        let [weight, bias] = context.parameters() else {
+            unreachable!("convolution has two parameters");
+        };
+        let shape = input.shape();
+        let [batch, _, height, width] = shape[..] else {
+            panic!("conv_2d expects images with four axes, found {:?}", shape);
+        };
+        let (out_height, out_width) = self
+            .window
+            .output_size((height, width))
+            .expect("conv_2d input is smaller than the kernel");
+
+        let groups = self.groups;
+        let out_channels = self.out_channels();
+        let [_, group_channels, kh, kw] = weight.shape()[..] else {
+            unreachable!("convolution weights have four axes");
+        };
+
+        // [groups, batch * out_height * out_width, group_channels * kh * kw]
+        let columns = self.window.columns(input, groups, T::zero());
+
+        // [groups, group_channels * kh * kw, out_channels / groups]
+        let kernels = weight
+            .reshape(&[groups, out_channels / groups, group_channels * kh * kw])
+            .permute(&[0, 2, 1]);
+
+        columns
+            .matmul(kernels)
+            .reshape(&[groups, batch, out_height * out_width, out_channels / groups])
+            .permute(&[1, 0, 3, 2])
+            .reshape(&[batch, out_channels, out_height, out_width])
+            + bias.reshape(&[out_channels, 1, 1])
+    }
+
+    fn parameters(&self) -> Vec<&ArrayD<T>> {
+        vec![&self.weight, &self.bias]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut ArrayD<T>> {
+        vec![&mut self.weight, &mut self.bias]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr1, Array, Array4};
+
+    use super::*;
+    use crate::{
+        autograd::check,
+        initializers::Ones,
+        layers::{
+            tests::{forward, values},
+            Mode,
+        },
+        training::Rng,
+    };
+
+    /// Direct convolution with nested loops.
+    fn reference(layer: &Conv2d, input: &Array4<f64>) -> ArrayD<f64> {
+        let (batch, channels, height, width) = input.dim();
+        let window = layer.window;
+        let (out_height, out_width) = window.output_size((height, width)).unwrap();
+        let group_channels = channels / layer.groups;
+        let out_group = layer.out_channels() / layer.groups;
+
+        Array::from_shape_fn(
+            (batch, layer.out_channels(), out_height, out_width),
+            |(n, o, i, j)| {
+                let g = o / out_group;
+                let mut sum = layer.bias[o];
+
+                for c in 0..group_channels {
+                    for ki in 0..window.kernel.0 {
+                        for kj in 0..window.kernel.1 {
+                            let y = (i * window.stride.0 + ki * window.dilation.0) as isize
+                                - window.padding.0 as isize;
+                            let x = (j * window.stride.1 + kj * window.dilation.1) as isize
+                                - window.padding.1 as isize;
+
+                            if (0..height as isize).contains(&y) && (0..width as isize).contains(&x)
+                            {
+                                sum += layer.weight[[o, c, ki, kj]]
+                                    * input[[n, g * group_channels + c, y as usize, x as usize]];
+                            }
+                        }
+                    }
+                }
+
+                sum
+            },
+        )
+        .into_dyn()
+    }
+
+    fn input(shape: (usize, usize, usize, usize)) -> Array4<f64> {
+        Array::from_shape_fn(shape, |(n, c, y, x)| {
+            ((n * 7 + c * 5 + y * 3 + x) as f64 * 0.37).sin()
+        })
+    }
+
+    fn layer(channels: (usize, usize), kernel_size: (usize, usize), groups: usize) -> Conv2d {
+        let mut layer = Conv2d::grouped(channels, kernel_size, groups, Ones).unwrap();
+        let n = layer.weight.len();
+        layer.weight = Array::linspace(0.0, n as f64, n)
+            .mapv(|v| (v * 0.71).cos())
+            .into_shape(layer.weight.raw_dim())
+            .unwrap();
+        layer.bias = Array::linspace(-0.5, 0.5, channels.1).into_dyn();
+        layer
+    }
+
+    #[test]
+    fn test_forward() {
+        let layer = Conv2d::new((1, 1), (2, 2), Ones).unwrap();
+        let x = Array::from_shape_vec((1, 1, 3, 3), (1..=9).map(f64::from).collect()).unwrap();
+
+        // sums of the 2x2 windows plus the bias of one
+        let expected = Array::from_shape_vec((1, 1, 2, 2), vec![13.0, 17.0, 25.0, 29.0]).unwrap();
+
+        assert_eq!(
+            expected.into_dyn(),
+            forward(&layer, &x.into_dyn(), Mode::Training).output
+        );
+    }
+
+    #[test]
+    fn test_reference() {
+        let x = input((2, 4, 7, 6));
+        let layers = [
+            layer((4, 3), (3, 3), 1),
+            layer((4, 2), (3, 2), 1).stride((2, 3)).padding((1, 2)),
+            layer((4, 3), (2, 2), 1).dilation((2, 3)).padding((1, 0)),
+            layer((4, 6), (3, 3), 2).stride((2, 1)),
+            layer((4, 4), (3, 3), 4).padding((1, 1)),
+        ];
+
+        for layer in layers.iter() {
+            let y = forward(layer, &x.clone().into_dyn(), Mode::Training).output;
+            let expected = reference(layer, &x);
+
+            assert_eq!(expected.shape(), y.shape());
+            assert!(
+                (y - expected).iter().all(|d| d.abs() < 1e-12),
+                "{:?}",
+                layer.config()
+            );
+        }
+    }
+
+    #[test]
+    fn test_gradients() {
+        let layers = [
+            layer((2, 3), (2, 2), 1).stride((2, 1)).padding((1, 1)),
+            layer((4, 2), (2, 3), 2).dilation((2, 1)),
+        ];
+
+        for layer in layers {
+            let x = input((2, layer.in_channels(), 5, 4));
+            let weights = reference(&layer, &x).mapv(f64::sin);
+            let inputs = [x.into_dyn(), layer.weight.clone(), layer.bias.clone()];
+
+            check(&inputs, |v| {
+                let mut rng = Rng::new(0);
+                let mut context = Context::new(Mode::Training, &v[1..], &mut rng);
+
+                layer.forward(v[0], &mut context) * v[0].tape().constant(weights.clone())
+            });
+        }
+    }
+
+    #[test]
+    fn test_output_shape() {
+        let layer = layer((3, 8), (3, 3), 1).stride((2, 2)).padding((1, 1));
+
+        assert_eq!(vec![8, 14, 14], layer.output_shape(&[3, 28, 28]).unwrap());
+        assert_eq!(vec![8, 1, 1], layer.output_shape(&[3, 1, 1]).unwrap());
+        assert!(matches!(
+            layer.output_shape(&[1, 28, 28]),
+            Err(Error::ShapeMismatch { expected, .. }) if expected == vec![3, 28, 28]
+        ));
+        assert!(matches!(
+            layer.output_shape(&[3 * 28 * 28]),
+            Err(Error::ShapeMismatch { expected, .. }) if expected == vec![3, 1, 1]
+        ));
+
+        let dilated = layer.clone().padding((0, 0)).dilation((2, 1));
+        assert!(matches!(
+            dilated.output_shape(&[3, 4, 2]),
+            Err(Error::ShapeMismatch { expected, .. }) if expected == vec![3, 5, 3]
+        ));
+        assert!(matches!(
+            layer.stride((0, 1)).output_shape(&[3, 28, 28]),
+            Err(Error::InvalidHyperparameter { name: "stride", .. })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_hyperparameters() {
+        let err = |result: Result<Conv2d>| match result {
+            Err(Error::InvalidHyperparameter { name, .. }) => name,
+            _ => panic!("expected an invalid hyperparameter"),
+        };
+
+        assert_eq!("channels", err(Conv2d::new((0, 4), (3, 3), Ones)));
+        assert_eq!("kernel_size", err(Conv2d::new((1, 4), (3, 0), Ones)));
+        assert_eq!("groups", err(Conv2d::grouped((4, 6), (3, 3), 4, Ones)));
+        assert_eq!("groups", err(Conv2d::grouped((4, 4), (3, 3), 0, Ones)));
+    }
+
+    #[test]
+    fn test_from_config() {
+        let layer = layer((4, 6), (3, 2), 2)
+            .stride((2, 1))
+            .padding((0, 1))
+            .dilation((1, 2));
+        let restored = Conv2d::<f64>::from_config(&layer.config(), values(&layer)).unwrap();
+
+        assert_eq!(layer.config(), restored.config());
+        assert_eq!(layer.weight.shape(), restored.weight.shape());
+        assert_eq!(arr1(&[0.0; 6]).into_dyn(), restored.bias);
+        assert!(Conv2d::<f64>::from_config(&layer.config(), values(&layer) - 1).is_none());
+        assert!(Conv2d::<f64>::from_config(&[4.0, 6.0, 3.0], usize::MAX).is_none());
+    }
+}
@@ -0,0 +1,257 @@
+use ndarray::{ArrayD, ScalarOperand};
+use num_traits::{Float, FromPrimitive};
+
+use crate::{autograd::Var, Error, Result};
+
+use super::{Context, Layer};
+
+/// Negative saturation value of SELU, `-λα`.
+const ALPHA_PRIME: f64 = -1.758_099_340_847_376_6;
+
+/// Sets every input to zero with probability `rate` during training and
+/// scales the remaining inputs by `1 / (1 - rate)`, so that the expected sum
+/// of the inputs does not change (inverted dropout). Inputs are passed
+/// through unchanged during inference.
+///
+/// [Srivastava, Nitish, et al. “Dropout: A Simple Way to Prevent Neural
+/// Networks from Overfitting.” Journal of Machine Learning Research 15
+/// (2014).](https://jmlr.org/papers/v15/srivastava14a.html)
+///
+/// # Examples
+///
+/// ```
+/// use robit::{layers::Dropout, Error};
+///
+/// let dropout = Dropout::new(0.5).unwrap();
+/// assert_eq!(0.5, dropout.rate());
+///
+/// assert!(matches!(
+///     Dropout::new(1.0),
+///     Err(Error::InvalidHyperparameter { .. })
+/// ));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dropout {
+    rate: f64,
+}
+
+impl Dropout {
+    /// Fails with an [Error::InvalidHyperparameter] if the rate is outside
+    /// of `[0, 1)`.
+    pub fn new(rate: f64) -> Result<Self> {
+        check_rate(rate)?;
+
+        Ok(Self { rate })
+    }
+
+    /// Probability of an input to be set to zero.
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    pub(crate) fn from_config(config: &[f64]) -> Option<Self> {
+        let [rate] = config else {
+            return None;
+        };
+
+        Self::new(*rate).ok()
+    }
+}
+
+impl<T> Layer<T> for Dropout
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "dropout"
+    }
+
+    fn config(&self) -> Vec<f64> {
+        vec![self.rate]
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        Ok(input_shape.to_vec())
+    }
+
+    fn keeps_time_axis(&self) -> bool {
+        true
+    }
+
+    fn forward<'t>(&self, input: Var<'t, T>, context: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        if !context.is_training() || self.rate == 0.0 {
+            return input;
+        }
+
+        let keep = 1.0 - self.rate;
+        let scale = T::from_f64(1.0 / keep).unwrap();
+        let mask = ArrayD::from_shape_simple_fn(input.shape(), || {
+            if context.random() < keep {
+                scale
+            } else {
+                T::zero()
+            }
+        });
+
+        input * input.tape().constant(mask)
+    }
+}
+
+/// Dropout for self-normalizing networks, which keeps the mean and variance
+/// of inputs with zero mean and unit variance.
+///
+/// During training every input is set to the negative saturation value of
+/// SELU with probability `rate`, followed by an affine transformation that
+/// restores the mean and variance. Inputs are passed through unchanged
+/// during inference.
+///
+/// [Klambauer, Günter, et al. “Self-Normalizing Neural Networks.” Advances
+/// in Neural Information Processing Systems 30
+/// (2017).](https://arxiv.org/abs/1706.02515)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlphaDropout {
+    rate: f64,
+}
+
+impl AlphaDropout {
+    /// Fails with an [Error::InvalidHyperparameter] if the rate is outside
+    /// of `[0, 1)`.
+    pub fn new(rate: f64) -> Result<Self> {
+        check_rate(rate)?;
+
+        Ok(Self { rate })
+    }
+
+    /// Probability of an input to be dropped.
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    pub(crate) fn from_config(config: &[f64]) -> Option<Self> {
+        let [rate] = config else {
+            return None;
+        };
+
+        Self::new(*rate).ok()
+    }
+}
+
+impl<T> Layer<T> for AlphaDropout
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "alpha_dropout"
+    }
+
+    fn config(&self) -> Vec<f64> {
+        vec![self.rate]
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        Ok(input_shape.to_vec())
+    }
+
+    fn keeps_time_axis(&self) -> bool {
+        true
+    }
+
+    fn forward<'t>(&self, input: Var<'t, T>, context: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        if !context.is_training() || self.rate == 0.0 {
+            return input;
+        }
+
+        let keep = 1.0 - self.rate;
+        let a = 1.0 / (keep * (1.0 + self.rate * ALPHA_PRIME * ALPHA_PRIME)).sqrt();
+        let b = -a * ALPHA_PRIME * self.rate;
+
+        // a * (x * mask + α' * (1 - mask)) + b
+        let mask: ArrayD<bool> =
+            ArrayD::from_shape_simple_fn(input.shape(), || context.random() < keep);
+        let factor = mask.mapv(|kept| T::from_f64(if kept { a } else { 0.0 }).unwrap());
+        let offset =
+            mask.mapv(|kept| T::from_f64(if kept { b } else { a * ALPHA_PRIME + b }).unwrap());
+
+        let tape = input.tape();
+
+        input * tape.constant(factor) + tape.constant(offset)
+    }
+}
+
+fn check_rate(rate: f64) -> Result<()> {
+    if (0.0..1.0).contains(&rate) {
+        Ok(())
+    } else {
+        Err(Error::invalid_hyperparameter("rate", "must be in [0, 1)"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::Array;
+
+    use super::*;
+    use crate::layers::{tests::forward, Mode};
+
+    fn input(n: usize) -> ArrayD<f64> {
+        Array::from_shape_fn(n, |i| if i % 2 == 0 { 1.0 } else { -1.0 }).into_dyn()
+    }
+
+    #[test]
+    fn test_invalid_rate() {
+        assert!(Dropout::new(-0.1).is_err());
+        assert!(Dropout::new(1.0).is_err());
+        assert!(Dropout::new(f64::NAN).is_err());
+        assert!(AlphaDropout::new(1.5).is_err());
+        assert!(Dropout::new(0.0).is_ok());
+    }
+
+    #[test]
+    fn test_inference() {
+        let x = input(10);
+
+        assert_eq!(
+            x,
+            forward(&Dropout::new(0.5).unwrap(), &x, Mode::Inference).output
+        );
+        assert_eq!(
+            x,
+            forward(&AlphaDropout::new(0.5).unwrap(), &x, Mode::Inference).output
+        );
+    }
+
+    #[test]
+    fn test_dropout() {
+        let x = input(10_000).mapv(f64::abs);
+        let result = forward(&Dropout::new(0.25).unwrap(), &x, Mode::Training);
+        let (y, gradient) = (result.output, result.input_gradient.unwrap());
+
+        let dropped = y.iter().filter(|&&v| v == 0.0).count() as f64 / 10_000.0;
+        assert!((dropped - 0.25).abs() < 0.02, "{}", dropped);
+        assert!(y
+            .iter()
+            .all(|&v| v == 0.0 || (v - 1.0 / 0.75).abs() < 1e-12));
+
+        // the backward pass uses the same mask
+        assert_eq!(y, gradient);
+    }
+
+    #[test]
+    fn test_alpha_dropout() {
+        let x = input(100_000);
+        let result = forward(&AlphaDropout::new(0.2).unwrap(), &x, Mode::Training);
+        let (y, gradient) = (result.output, result.input_gradient.unwrap());
+
+        let mean = y.mean().unwrap();
+        let variance = y.mapv(|v| (v - mean).powi(2)).mean().unwrap();
+
+        assert!(mean.abs() < 0.02, "{}", mean);
+        assert!((variance - 1.0).abs() < 0.02, "{}", variance);
+
+        // dropped inputs have no gradient, kept inputs are scaled by `a`
+        let a = gradient.fold(0.0, |max: f64, &g| max.max(g));
+        let dropped = gradient.iter().filter(|&&g| g == 0.0).count() as f64 / 100_000.0;
+        assert!(gradient.iter().all(|&g| g == 0.0 || g == a));
+        assert!((dropped - 0.2).abs() < 0.01, "{}", dropped);
+    }
+}
@@ -0,0 +1,476 @@
+use ndarray::{Array2, ArrayD, ArrayViewD, Axis, ScalarOperand};
+use num_traits::{Float, FromPrimitive, Zero};
+
+use crate::{
+    autograd::Var,
+    initializers::{Initializer, Zeros},
+    Error, Result,
+};
+
+use super::{check_values, non_negative_integer, positive_integer, Context, Layer};
+
+/// Maps indices in `0..vocab_size`, e.g. token ids or categories, to dense
+/// vectors of length `dim`.
+///
+/// The input contains the indices as integer values of any shape, the output
+/// has an additional last axis of length `dim`. Only the looked up rows of
+/// the weight with the shape `[vocab_size, dim]` are updated, and with
+/// [Sgd](crate::optimizers::Sgd) or [Adam](crate::optimizers::Adam) only
+/// their momentum, see
+/// [Optimizer::sparse_step](crate::optimizers::Optimizer::sparse_step). No
+/// gradient flows to the input.
+///
+/// The row of the padding index is zero and never updated. For inputs with
+/// the shape `[batch, time]`, the time steps with the padding index are
+/// passed on as [Context::padding_mask], so that a later
+/// [MultiHeadAttention](super::MultiHeadAttention) ignores them. With a maximum
+/// norm, looked up vectors with a larger euclidean norm are scaled down to
+/// it, the scale is treated as a constant in the backward pass.
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::arr2;
+/// use robit::{
+///     initializers::RandomDistr,
+///     layers::{Embedding, Flatten},
+///     Model,
+/// };
+///
+/// let mut model: Model = Model::new(0.01);
+/// model.add(
+///     Embedding::new(100, 8, RandomDistr::normal())
+///         .unwrap()
+///         .padding_idx(0),
+/// );
+/// model.add(Flatten);
+/// model.add_layer((3 * 8, 1), RandomDistr::normal());
+///
+/// let tokens = arr2(&[[4.0, 42.0, 0.0], [7.0, 0.0, 0.0]]);
+///
+/// assert_eq!(&[2, 1], model.predict(&tokens).shape());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Embedding<T = f64> {
+    weight: ArrayD<T>,
+    padding_idx: Option<usize>,
+    max_norm: Option<f64>,
+}
+
+impl<T> Embedding<T> {
+    /// Creates the layer without padding index and maximum norm.
+    ///
+    /// Fails with an [Error::InvalidHyperparameter] if `vocab_size` or `dim`
+    /// is zero.
+    pub fn new<I: Initializer<T>>(vocab_size: usize, dim: usize, init: I) -> Result<Self> {
+        if vocab_size == 0 {
+            return Err(Error::invalid_hyperparameter(
+                "vocab_size",
+                "must be positive",
+            ));
+        }
+        if dim == 0 {
+            return Err(Error::invalid_hyperparameter("dim", "must be positive"));
+        }
+
+        Ok(Self {
+            weight: init.gen(vec![vocab_size, dim]),
+            padding_idx: None,
+            max_norm: None,
+        })
+    }
+
+    /// Index whose vector is zero and not trained, e.g. for padded
+    /// sequences.
+    pub fn padding_idx(mut self, padding_idx: usize) -> Self
+    where
+        T: Zero + Clone,
+    {
+        if padding_idx < self.vocab_size() {
+            self.weight
+                .index_axis_mut(Axis(0), padding_idx)
+                .fill(T::zero());
+        }
+        self.padding_idx = Some(padding_idx);
+        self
+    }
+
+    /// Largest euclidean norm of a looked up vector.
+    pub fn max_norm(mut self, max_norm: f64) -> Self {
+        self.max_norm = Some(max_norm);
+        self
+    }
+
+    pub fn vocab_size(&self) -> usize {
+        self.weight.shape()[0]
+    }
+
+    pub fn dim(&self) -> usize {
+        self.weight.shape()[1]
+    }
+
+    pub(crate) fn from_config(config: &[f64], max_values: usize) -> Option<Self>
+    where
+        T: Zero + Clone,
+    {
+        let &[vocab_size, dim, padding_idx, max_norm] = config else {
+            return None;
+        };
+        let (vocab_size, dim) = (positive_integer(vocab_size)?, positive_integer(dim)?);
+        check_values(vocab_size.checked_mul(dim), max_values)?;
+        let mut embedding = Self::new(vocab_size, dim, Zeros).ok()?;
+
+        if padding_idx != -1.0 {
+            embedding = embedding.padding_idx(non_negative_integer(padding_idx)?);
+        }
+        if max_norm != 0.0 {
+            embedding = embedding.max_norm(max_norm);
+        }
+
+        Some(embedding)
+    }
+
+    /// Converts an input value to a row of the weight, `None` if it is not
+    /// an index in `0..vocab_size`.
+    fn index(&self, value: T) -> Option<usize>
+    where
+        T: Float,
+    {
+        value
+            .to_usize()
+            .filter(|&index| T::from(index) == Some(value) && index < self.vocab_size())
+    }
+
+    fn invalid_index(&self, value: T) -> String
+    where
+        T: Float,
+    {
+        format!(
+            "embedding index {:?} is not an integer in 0..{}",
+            value.to_f64(),
+            self.vocab_size()
+        )
+    }
+}
+
+impl<T> Layer<T> for Embedding<T>
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "embedding"
+    }
+
+    fn config(&self) -> Vec<f64> {
+        vec![
+            self.vocab_size() as f64,
+            self.dim() as f64,
+            self.padding_idx.map_or(-1.0, |index| index as f64),
+            self.max_norm.unwrap_or(0.0),
+        ]
+    }
+
+    /// Fails with an [Error::InvalidHyperparameter] if the padding index is
+    /// not smaller than the vocabulary size or the maximum norm is not
+    /// positive.
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        if let Some(padding_idx) = self.padding_idx {
+            if padding_idx >= self.vocab_size() {
+                return Err(Error::invalid_hyperparameter(
+                    "padding_idx",
+                    format!("must be smaller than {}", self.vocab_size()),
+                ));
+            }
+        }
+        if let Some(max_norm) = self.max_norm {
+            if max_norm.is_nan() || max_norm <= 0.0 {
+                return Err(Error::invalid_hyperparameter(
+                    "max_norm",
+                    "must be positive",
+                ));
+            }
+        }
+
+        let mut shape = input_shape.to_vec();
+        shape.push(self.dim());
+
+        Ok(shape)
+    }
+
+    /// Fails with an [Error::InvalidInput] if an input value is not an index
+    /// in `0..vocab_size`.
+    fn check_input(&self, input: ArrayViewD<T>) -> Result<()> {
+        match input.iter().find(|&&value| self.index(value).is_none()) {
+            Some(&value) => Err(Error::InvalidInput {
+                name: "embedding input",
+                message: self.invalid_index(value),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn keeps_time_axis(&self) -> bool {
+        true
+    }
+
+    /// Panics if an input value is not an index in `0..vocab_size`.
+    fn forward<'t>(&self, input: Var<'t, T>, context: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        let [weight] = context.parameters() else {
+            unreachable!("embedding has one parameter");
+        };
+        let indices: Vec<usize> = input.with_value(|x| {
+            x.iter()
+                .map(|&v| {
+                    self.index(v)
+                        .unwrap_or_else(|| panic!("{}", self.invalid_index(v)))
+                })
+                .collect()
+        });
+
+        let mut rows = indices.clone();
+        rows.sort_unstable();
+        rows.dedup();
+        rows.retain(|&row| Some(row) != self.padding_idx);
+        context.set_sparse_rows(0, rows);
+
+        if let (Some(padding_idx), &[batch, time]) = (self.padding_idx, &input.shape()[..]) {
+            let mask =
+                Array2::from_shape_fn((batch, time), |(n, t)| indices[n * time + t] == padding_idx);
+            context.set_padding_mask(mask);
+        }
+
+        let mut shape = input.shape();
+        shape.push(self.dim());
+
+        let mut output = lookup(*weight, indices, self.padding_idx);
+
+        if let Some(max_norm) = self.max_norm {
+            let max_norm = T::from(max_norm).unwrap();
+            let scale = output.with_value(|vectors| {
+                vectors
+                    .map_axis(Axis(1), |v| {
+                        let norm = v
+                            .iter()
+                            .map(|&x| x * x)
+                            .fold(T::zero(), |a, b| a + b)
+                            .sqrt();
+                        if norm > max_norm {
+                            max_norm / norm
+                        } else {
+                            T::one()
+                        }
+                    })
+                    .insert_axis(Axis(1))
+            });
+
+            output = output * output.tape().constant(scale);
+        }
+
+        output.reshape(&shape)
+    }
+
+    fn parameters(&self) -> Vec<&ArrayD<T>> {
+        vec![&self.weight]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut ArrayD<T>> {
+        vec![&mut self.weight]
+    }
+}
+
+/// Selects the rows of the weight with the given indices into a matrix, the
+/// gradients of repeated rows are summed and the padding row gets none.
+fn lookup<T: Float + 'static>(
+    weight: Var<'_, T>,
+    indices: Vec<usize>,
+    padding_idx: Option<usize>,
+) -> Var<'_, T> {
+    let value = weight.with_value(|w| w.select(Axis(0), &indices));
+
+    weight.tape().op(&[weight], value, move |grad, inputs, _| {
+        let mut weight_grad = ArrayD::zeros(inputs[0].shape());
+
+        for (&index, g) in indices.iter().zip(grad.outer_iter()) {
+            if Some(index) != padding_idx {
+                weight_grad
+                    .index_axis_mut(Axis(0), index)
+                    .zip_mut_with(&g, |w, &g| *w = *w + g);
+            }
+        }
+
+        vec![weight_grad]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, Array};
+
+    use super::*;
+    use crate::{
+        autograd::{check, Tape},
+        initializers::Ones,
+        layers::{
+            tests::{forward, values},
+            Mode,
+        },
+        training::Rng,
+    };
+
+    fn embedding() -> Embedding {
+        let mut embedding = Embedding::new(5, 3, Zeros).unwrap();
+        embedding.weight = Array::range(0.0, 15.0, 1.0)
+            .into_shape((5, 3))
+            .unwrap()
+            .into_dyn();
+        embedding
+    }
+
+    #[test]
+    fn test_lookup() {
+        let embedding = embedding();
+        let input = arr2(&[[4.0, 1.0], [1.0, 0.0]]).into_dyn();
+        let result = forward(&embedding, &input, Mode::Training);
+
+        assert_eq!(&[2, 2, 3], result.output.shape());
+        assert_eq!(
+            vec![12.0, 13.0, 14.0, 3.0, 4.0, 5.0, 3.0, 4.0, 5.0, 0.0, 1.0, 2.0],
+            result.output.into_raw_vec()
+        );
+        assert_eq!(vec![(0, vec![0, 1, 4])], result.sparse_rows);
+        assert_eq!(
+            vec![7, 2, 3],
+            Layer::<f64>::output_shape(&embedding, &[7, 2]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_padding_idx() {
+        let embedding = Embedding::new(4, 2, Ones).unwrap().padding_idx(2);
+        let input = arr2(&[[2.0, 1.0, 2.0]]).into_dyn();
+        let result = forward(&embedding, &input, Mode::Training);
+
+        assert_eq!(
+            vec![0.0, 0.0, 1.0, 1.0, 0.0, 0.0],
+            result.output.into_raw_vec()
+        );
+        assert_eq!(vec![(0, vec![1])], result.sparse_rows);
+
+        let embedding = embedding.padding_idx(4);
+        assert!(matches!(
+            Layer::<f64>::output_shape(&embedding, &[3]),
+            Err(Error::InvalidHyperparameter {
+                name: "padding_idx",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_max_norm() {
+        let mut embedding = embedding().max_norm(5.0);
+        embedding.weight[[0, 0]] = 3.0;
+        embedding.weight[[0, 1]] = 4.0;
+        embedding.weight[[0, 2]] = 0.0;
+        let output = forward(&embedding, &arr2(&[[0.0, 1.0]]).into_dyn(), Mode::Training).output;
+
+        let norms = output.map_axis(Axis(2), |v| v.dot(&v).sqrt());
+        assert!((norms[[0, 0]] - 5.0).abs() < 1e-12);
+        assert!((norms[[0, 1]] - 5.0).abs() < 1e-12);
+        assert_eq!(3.0, output[[0, 0, 0]]);
+
+        assert!(Layer::<f64>::output_shape(&embedding.max_norm(0.0), &[3]).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "embedding index")]
+    fn test_invalid_index() {
+        forward(&embedding(), &arr2(&[[5.0]]).into_dyn(), Mode::Training);
+    }
+
+    #[test]
+    fn test_check_input() {
+        let embedding = embedding();
+
+        assert!(embedding
+            .check_input(arr2(&[[4.0, 0.0]]).into_dyn().view())
+            .is_ok());
+        for value in [5.0, 0.5, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                embedding.check_input(arr2(&[[1.0, value]]).into_dyn().view()),
+                Err(Error::InvalidInput { message, .. }) if message.starts_with("embedding index")
+            ));
+        }
+    }
+
+    #[test]
+    fn test_gradients() {
+        let weight = embedding().weight.mapv(|v| (v * 0.7).sin());
+        let input = arr2(&[[4.0, 1.0, 3.0], [1.0, 0.0, 4.0]]).into_dyn();
+        let weights = Array::linspace(-1.0, 1.0, 18)
+            .into_shape((2, 3, 3))
+            .unwrap();
+
+        check(&[weight], |v| {
+            let tape = v[0].tape();
+            let mut rng = Rng::new(0);
+            let mut context = Context::new(Mode::Training, v, &mut rng);
+
+            let y = embedding().forward(tape.constant(input.clone()), &mut context);
+            y * tape.constant(weights.clone())
+        });
+    }
+
+    #[test]
+    fn test_sparse_gradients() {
+        let layer = embedding().padding_idx(2).max_norm(5.0);
+        let tape = Tape::new();
+        let weight = tape.var(layer.weight.clone());
+        let mut rng = Rng::new(0);
+        let parameters = [weight];
+        let mut context = Context::new(Mode::Training, &parameters, &mut rng);
+
+        let input = arr2(&[[2.0, 0.0, 0.0, 1.0]]).into_dyn();
+        let output = layer.forward(tape.constant(input), &mut context);
+        let gradient = tape.backward(output).take(weight).unwrap();
+
+        // row 0 has a norm of 2.24 and row 1 of 7.07
+        let expected = arr2(&[
+            [2.0, 2.0, 2.0],
+            [0.5_f64.sqrt(); 3],
+            [0.0; 3],
+            [0.0; 3],
+            [0.0; 3],
+        ]);
+        let error = (gradient - expected).mapv(f64::abs);
+        assert!(error.iter().all(|&e| e < 1e-12));
+    }
+
+    #[test]
+    fn test_from_config() {
+        let embedding: Embedding = Embedding::new(10, 4, Zeros)
+            .unwrap()
+            .padding_idx(0)
+            .max_norm(1.5);
+
+        assert_eq!(
+            Some(embedding.clone()),
+            Embedding::from_config(&embedding.config(), values(&embedding))
+        );
+        assert_eq!(
+            None,
+            Embedding::<f64>::from_config(&embedding.config(), values(&embedding) - 1)
+        );
+
+        let embedding: Embedding = Embedding::new(10, 4, Zeros).unwrap();
+        assert_eq!(vec![10.0, 4.0, -1.0, 0.0], embedding.config());
+        assert_eq!(
+            Some(embedding),
+            Embedding::from_config(&[10.0, 4.0, -1.0, 0.0], 40)
+        );
+        assert_eq!(
+            None,
+            Embedding::<f64>::from_config(&[0.0, 4.0, -1.0, 0.0], usize::MAX)
+        );
+    }
+}
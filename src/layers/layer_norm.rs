@@ -0,0 +1,396 @@
+use ndarray::{ArrayD, ScalarOperand};
+use num_traits::{Float, FromPrimitive};
+
+use crate::{autograd::Var, Error, Result};
+
+use super::{check_values, positive_integer, Context, Layer};
+
+/// Normalizes every sample to zero mean and unit variance over the last
+/// axis, followed by a learnable scale `gamma` and shift `beta`.
+///
+/// Unlike [BatchNorm1d](super::BatchNorm1d) the statistics do not depend on
+/// the other samples of the batch, so training and inference behave the
+/// same. Inputs have the shape `[batch, ..., features]`, e.g. `[batch,
+/// length, features]` for sequences.
+///
+/// [Ba, Jimmy Lei, Jamie Ryan Kiros, and Geoffrey E. Hinton. “Layer
+/// Normalization.” (2016).](https://arxiv.org/abs/1607.06450)
+///
+/// # Examples
+///
+/// ```
+/// use robit::{initializers::RandomDistr, layers::LayerNorm, Model};
+///
+/// let mut model: Model = Model::new(0.01);
+/// model.add_layer((4, 16), RandomDistr::normal());
+/// model.add(LayerNorm::new(16).epsilon(1e-6));
+/// model.add_layer((16, 1), RandomDistr::normal());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerNorm<T = f64> {
+    epsilon: f64,
+    gamma: ArrayD<T>,
+    beta: ArrayD<T>,
+}
+
+impl<T: Float> LayerNorm<T> {
+    /// Creates the layer with `gamma` set to one, `beta` set to zero and an
+    /// epsilon of `1e-5`.
+    pub fn new(features: usize) -> Self {
+        Self {
+            epsilon: 1e-5,
+            gamma: ArrayD::ones(vec![features]),
+            beta: ArrayD::zeros(vec![features]),
+        }
+    }
+
+    /// Value added to the variance to avoid a division by zero.
+    pub fn epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    pub fn features(&self) -> usize {
+        self.gamma.len()
+    }
+
+    pub(crate) fn from_config(config: &[f64], max_values: usize) -> Option<Self> {
+        let [features, epsilon] = *config else {
+            return None;
+        };
+        let features = positive_integer(features)?;
+        check_values(features.checked_mul(2), max_values)?;
+
+        Some(Self::new(features).epsilon(epsilon))
+    }
+}
+
+impl<T> Layer<T> for LayerNorm<T>
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "layer_norm"
+    }
+
+    fn config(&self) -> Vec<f64> {
+        vec![self.features() as f64, self.epsilon]
+    }
+
+    /// Fails with an [Error::InvalidHyperparameter] if epsilon is negative.
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        check_epsilon(self.epsilon)?;
+        check_features("layer_norm input", self.features(), input_shape)
+    }
+
+    fn keeps_time_axis(&self) -> bool {
+        true
+    }
+
+    fn forward<'t>(&self, input: Var<'t, T>, context: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        let [gamma, beta] = context.parameters() else {
+            unreachable!("layer normalization has two parameters");
+        };
+        let axis = input.ndim() - 1;
+        let epsilon = T::from_f64(self.epsilon).unwrap();
+
+        let centered = input - input.mean_axis(axis).insert_axis(axis);
+        let var = centered.square().mean_axis(axis).insert_axis(axis);
+
+        centered / var.add_scalar(epsilon).sqrt() * *gamma + *beta
+    }
+
+    fn parameters(&self) -> Vec<&ArrayD<T>> {
+        vec![&self.gamma, &self.beta]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut ArrayD<T>> {
+        vec![&mut self.gamma, &mut self.beta]
+    }
+}
+
+/// Divides every sample by the root mean square of its values over the last
+/// axis, followed by a learnable scale `gamma`.
+///
+/// A cheaper variant of [LayerNorm] without centering and shift, common in
+/// transformer models.
+///
+/// [Zhang, Biao, and Rico Sennrich. “Root Mean Square Layer Normalization.”
+/// Advances in Neural Information Processing Systems 32
+/// (2019).](https://arxiv.org/abs/1910.07467)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RMSNorm<T = f64> {
+    epsilon: f64,
+    gamma: ArrayD<T>,
+}
+
+impl<T: Float> RMSNorm<T> {
+    /// Creates the layer with `gamma` set to one and an epsilon of `1e-6`.
+    pub fn new(features: usize) -> Self {
+        Self {
+            epsilon: 1e-6,
+            gamma: ArrayD::ones(vec![features]),
+        }
+    }
+
+    /// Value added to the mean square to avoid a division by zero.
+    pub fn epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    pub fn features(&self) -> usize {
+        self.gamma.len()
+    }
+
+    pub(crate) fn from_config(config: &[f64], max_values: usize) -> Option<Self> {
+        let [features, epsilon] = *config else {
+            return None;
+        };
+        let features = positive_integer(features)?;
+        check_values(Some(features), max_values)?;
+
+        Some(Self::new(features).epsilon(epsilon))
+    }
+}
+
+impl<T> Layer<T> for RMSNorm<T>
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "rms_norm"
+    }
+
+    fn kind(&self) -> String {
+        "RMSNorm".to_owned()
+    }
+
+    fn config(&self) -> Vec<f64> {
+        vec![self.features() as f64, self.epsilon]
+    }
+
+    /// Fails with an [Error::InvalidHyperparameter] if epsilon is negative.
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        check_epsilon(self.epsilon)?;
+        check_features("rms_norm input", self.features(), input_shape)
+    }
+
+    fn keeps_time_axis(&self) -> bool {
+        true
+    }
+
+    fn forward<'t>(&self, input: Var<'t, T>, context: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        let [gamma] = context.parameters() else {
+            unreachable!("RMS normalization has one parameter");
+        };
+        let axis = input.ndim() - 1;
+        let epsilon = T::from_f64(self.epsilon).unwrap();
+
+        let rms = input
+            .square()
+            .mean_axis(axis)
+            .insert_axis(axis)
+            .add_scalar(epsilon)
+            .sqrt();
+
+        input / rms * *gamma
+    }
+
+    fn parameters(&self) -> Vec<&ArrayD<T>> {
+        vec![&self.gamma]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut ArrayD<T>> {
+        vec![&mut self.gamma]
+    }
+}
+
+fn check_epsilon(epsilon: f64) -> Result<()> {
+    if epsilon.is_nan() || epsilon < 0.0 {
+        return Err(Error::invalid_hyperparameter(
+            "epsilon",
+            "must not be negative",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks that the last axis of an input sample has `features` values.
+fn check_features(
+    name: &'static str,
+    features: usize,
+    input_shape: &[usize],
+) -> Result<Vec<usize>> {
+    match input_shape.last() {
+        Some(&last) if last == features => Ok(input_shape.to_vec()),
+        _ => {
+            let mut expected = input_shape.to_vec();
+            expected.pop();
+            expected.push(features);
+
+            Err(Error::ShapeMismatch {
+                name,
+                expected,
+                found: input_shape.to_vec(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr1, arr2, Array, Axis};
+
+    use super::*;
+    use crate::{
+        autograd::check,
+        layers::{
+            tests::{forward, values},
+            Mode,
+        },
+        training::Rng,
+    };
+
+    /// Checks the gradients of the input and parameters, weighted so that
+    /// the gradient of the normalization is not zero.
+    fn check_gradients<L: Layer>(layer: &L, input: ArrayD<f64>) {
+        let weights = input.mapv(f64::sin);
+        let mut inputs = vec![input];
+        inputs.extend(layer.parameters().into_iter().map(|p| p.mapv(|v| v + 0.3)));
+
+        check(&inputs, |v| {
+            let mut rng = Rng::new(0);
+            let mut context = Context::new(Mode::Training, &v[1..], &mut rng);
+
+            layer.forward(v[0], &mut context) * v[0].tape().constant(weights.clone())
+        });
+    }
+
+    fn input() -> ArrayD<f64> {
+        arr2(&[
+            [0.5, -1.0, 2.0, 0.1],
+            [1.5, 0.3, -0.2, 0.0],
+            [-0.7, 0.9, 0.4, 3.0],
+        ])
+        .into_dyn()
+    }
+
+    #[test]
+    fn test_layer_norm() {
+        let y = forward(&LayerNorm::new(4), &input(), Mode::Training).output;
+
+        for row in y.axis_iter(Axis(0)) {
+            assert!(row.mean().unwrap().abs() < 1e-12);
+            assert!((row.mapv(|v| v * v).mean().unwrap() - 1.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_layer_norm_affine() {
+        let mut layer = LayerNorm::new(2).epsilon(0.0);
+        layer.gamma = arr1(&[2.0, 3.0]).into_dyn();
+        layer.beta = arr1(&[0.5, -0.5]).into_dyn();
+
+        let y = forward(&layer, &arr2(&[[1.0, 3.0]]).into_dyn(), Mode::Training).output;
+
+        assert_eq!(arr2(&[[-1.5, 2.5]]).into_dyn(), y);
+    }
+
+    #[test]
+    fn test_rms_norm() {
+        let mut layer = RMSNorm::new(2).epsilon(0.0);
+        layer.gamma = arr1(&[1.0, 2.0]).into_dyn();
+
+        // root mean square of 3 and 4 is 5 / √2
+        let y = forward(&layer, &arr2(&[[3.0, 4.0]]).into_dyn(), Mode::Training).output;
+        let expected = arr2(&[[3.0, 8.0]]).into_dyn() * 2f64.sqrt() / 5.0;
+
+        assert!((y - expected).iter().all(|d| d.abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_sequence() {
+        let x =
+            Array::from_shape_fn((2, 3, 4), |(i, j, k)| (i + 2 * j) as f64 * k as f64).into_dyn();
+        let y = forward(&LayerNorm::new(4), &x, Mode::Training).output;
+
+        assert_eq!(&[2, 3, 4], y.shape());
+        assert!(y
+            .mean_axis(Axis(2))
+            .unwrap()
+            .iter()
+            .all(|m| m.abs() < 1e-12));
+        assert_eq!(
+            &[2, 3, 4],
+            forward(&RMSNorm::new(4), &x, Mode::Training).output.shape()
+        );
+    }
+
+    #[test]
+    fn test_gradients() {
+        check_gradients(&LayerNorm::new(4), input());
+        check_gradients(&RMSNorm::new(4), input());
+
+        let x = Array::from_shape_fn((2, 2, 3), |(i, j, k)| {
+            (i * 6 + j * 3 + k) as f64 * 0.37 - 1.0
+        });
+        check_gradients(&LayerNorm::new(3), x.clone().into_dyn());
+        check_gradients(&RMSNorm::new(3), x.into_dyn());
+    }
+
+    #[test]
+    fn test_output_shape() {
+        let layer: LayerNorm = LayerNorm::new(4);
+
+        assert_eq!(vec![3, 4], layer.output_shape(&[3, 4]).unwrap());
+        assert!(matches!(
+            layer.output_shape(&[3, 5]),
+            Err(Error::ShapeMismatch { expected, .. }) if expected == vec![3, 4]
+        ));
+        assert!(Layer::<f64>::output_shape(&RMSNorm::new(4), &[]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_epsilon() {
+        assert!(matches!(
+            Layer::<f64>::output_shape(&LayerNorm::new(4).epsilon(-1e-5), &[4]),
+            Err(Error::InvalidHyperparameter {
+                name: "epsilon",
+                ..
+            })
+        ));
+        assert!(matches!(
+            Layer::<f64>::output_shape(&RMSNorm::new(4).epsilon(f64::NAN), &[4]),
+            Err(Error::InvalidHyperparameter {
+                name: "epsilon",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_from_config() {
+        let layer: LayerNorm = LayerNorm::new(4).epsilon(1e-3);
+        let rms: RMSNorm = RMSNorm::new(2);
+
+        assert_eq!(
+            Some(layer.clone()),
+            LayerNorm::from_config(&layer.config(), values(&layer))
+        );
+        assert_eq!(
+            Some(rms.clone()),
+            RMSNorm::from_config(&rms.config(), values(&rms))
+        );
+        assert_eq!(
+            None,
+            LayerNorm::<f64>::from_config(&layer.config(), values(&layer) - 1)
+        );
+        assert_eq!(
+            None,
+            RMSNorm::<f64>::from_config(&rms.config(), values(&rms) - 1)
+        );
+        assert_eq!(None, RMSNorm::<f64>::from_config(&[0.0, 1e-6], usize::MAX));
+    }
+}
@@ -1,16 +1,472 @@
-mod dense;
-mod input;
-// mod pipe;
+//! Layers that can be added to a [Model] with [Model::add], in addition to
+//! the dense layers added with [Model::add_layer].
+//!
+//! Layers only define their forward pass on the tape of the
+//! [autograd](crate::autograd) module, the gradients of their inputs and
+//! parameters are derived from it.
+//!
+//! # Examples
+//!
+//! ```
+//! use robit::{initializers::RandomDistr, layers::Dropout, Model};
+//!
+//! let mut model: Model = Model::new(0.01);
+//! model.add_layer((784, 128), RandomDistr::normal());
+//! model.add(Dropout::new(0.2).unwrap());
+//! model.add_layer((128, 10), RandomDistr::normal());
+//! ```
+//!
+//! [Model]: crate::Model
+//! [Model::add]: crate::Model::add
+//! [Model::add_layer]: crate::Model::add_layer
 
-pub use dense::Dense;
-pub use input::Input;
-use ndarray::{Array, Array1, Array2, Dimension, Shape};
-// pub use pipe::Pipe;
+mod attention;
+mod batch_norm;
+mod conv;
+mod dropout;
+mod embedding;
+mod layer_norm;
+mod pooling;
+mod positional_encoding;
+mod recurrent;
+mod reshape;
+mod window;
 
+pub use attention::{MultiHeadAttention, TransformerEncoderBlock};
+pub use batch_norm::BatchNorm1d;
+pub use conv::Conv2d;
+pub use dropout::{AlphaDropout, Dropout};
+pub use embedding::Embedding;
+pub use layer_norm::{LayerNorm, RMSNorm};
+pub use pooling::{AvgPool2d, GlobalAvgPool2d, GlobalMaxPool2d, MaxPool2d};
+pub use positional_encoding::{LearnedPositionalEncoding, SinusoidalPositionalEncoding};
+pub use recurrent::{Gru, Lstm, SimpleRnn};
+pub use reshape::{Flatten, Permute, Reshape};
+
+use std::mem;
+
+use ndarray::{Array2, ArrayD, ArrayViewD, ScalarOperand};
+use num_traits::{Float, FromPrimitive};
+
+use crate::{autograd::Var, training::Rng, Error, Result};
+
+/// Whether a forward pass belongs to the training or to inference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Used by [Model::fit](crate::Model::fit) and related methods, e.g.
+    /// dropout is applied.
+    Training,
+
+    /// Used by [Model::predict](crate::Model::predict) and related methods,
+    /// the output is deterministic.
+    Inference,
+}
+
+/// A layer of a [Model](crate::Model).
 pub trait Layer<T = f64> {
-    fn predict_step(&self, w: &Array2<T>, b: &Array1<T>, a: Array1<T>) -> Array1<T>;
+    /// Identifier of the layer type in saved models, e.g. `dropout`.
+    fn name(&self) -> &'static str;
+
+    /// Type of the layer in [Model::summary](crate::Model::summary), by
+    /// default the name in camel case, e.g. `AlphaDropout` for
+    /// `alpha_dropout`.
+    fn kind(&self) -> String {
+        kind(self.name())
+    }
+
+    /// Hyperparameters that recreate the layer in saved models.
+    fn config(&self) -> Vec<f64>;
+
+    /// Returns the shape of an output sample for an input sample of the
+    /// given shape, both without the batch axis.
+    ///
+    /// Fails with an [Error::ShapeMismatch](crate::Error::ShapeMismatch) if
+    /// the layer can not process the input.
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>>;
+
+    /// Checks the values of an input batch before the forward pass, e.g.
+    /// that they are valid indices. Called with the data by the methods of
+    /// [Model](crate::Model) that return a [Result] if the layer is the first
+    /// layer of the model.
+    ///
+    /// Fails with an [Error::InvalidInput](crate::Error::InvalidInput) if
+    /// [Layer::forward] would panic on the input. Accepts every input by
+    /// default.
+    fn check_input(&self, _input: ArrayViewD<T>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Whether the output of a sequence `[batch, time, ...]` keeps the batch
+    /// and time axes of the input, so that the [Context::padding_mask] of the
+    /// input is passed on to the following layers. The mask is dropped by
+    /// default, unless the layer sets a new one.
+    fn keeps_time_axis(&self) -> bool {
+        false
+    }
+
+    /// Records the output of the layer for a batch on the tape of `input`.
+    ///
+    /// The parameters are available from the context, in the order of
+    /// [Layer::parameters].
+    fn forward<'t>(&self, input: Var<'t, T>, context: &mut Context<'_, 't, T>) -> Var<'t, T>;
+
+    /// Parameters that are updated by the optimizer.
+    fn parameters(&self) -> Vec<&ArrayD<T>> {
+        vec![]
+    }
+
+    /// Mutable access to the values of [Layer::parameters], in the same
+    /// order.
+    fn parameters_mut(&mut self) -> Vec<&mut ArrayD<T>> {
+        vec![]
+    }
+
+    /// Values that are not updated by the optimizer but are stored with the
+    /// model, e.g. running statistics.
+    fn state(&self) -> Vec<&ArrayD<T>> {
+        vec![]
+    }
+
+    /// Mutable access to the values of [Layer::state], in the same order.
+    fn state_mut(&mut self) -> Vec<&mut ArrayD<T>> {
+        vec![]
+    }
+}
+
+/// Information about a single forward pass that is passed to
+/// [Layer::forward].
+pub struct Context<'a, 't, T> {
+    mode: Mode,
+    parameters: &'a [Var<'t, T>],
+    rng: &'a mut Rng,
+    state: Option<Vec<ArrayD<T>>>,
+    sparse_rows: SparseRows,
+    padding_mask: Option<Array2<bool>>,
+    output_padding_mask: Option<Array2<bool>>,
+}
+
+/// Indices of the parameters of a layer that only received gradients in some
+/// rows, together with the rows, see [Context::set_sparse_rows].
+pub(crate) type SparseRows = Vec<(usize, Vec<usize>)>;
+
+impl<'a, 't, T> Context<'a, 't, T> {
+    pub(crate) fn new(mode: Mode, parameters: &'a [Var<'t, T>], rng: &'a mut Rng) -> Self {
+        Self {
+            mode,
+            parameters,
+            rng,
+            state: None,
+            sparse_rows: vec![],
+            padding_mask: None,
+            output_padding_mask: None,
+        }
+    }
+
+    pub(crate) fn with_padding_mask(mut self, padding_mask: Option<Array2<bool>>) -> Self {
+        self.padding_mask = padding_mask;
+        self
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn is_training(&self) -> bool {
+        self.mode == Mode::Training
+    }
+
+    /// Returns the parameters of the layer recorded on the tape.
+    pub fn parameters(&self) -> &'a [Var<'t, T>] {
+        self.parameters
+    }
+
+    /// Returns a uniformly distributed random number in `[0, 1)` from the
+    /// random number generator of the model, so that training is
+    /// reproducible with [Model::set_seed](crate::Model::set_seed).
+    pub fn random(&mut self) -> f64 {
+        self.rng.next_f64()
+    }
+
+    /// Replaces the values of [Layer::state] after the forward pass, e.g.
+    /// with updated running statistics.
+    pub fn update_state(&mut self, state: Vec<ArrayD<T>>) {
+        self.state = Some(state);
+    }
+
+    /// Marks that only the given rows, i.e. indices along the first axis, of
+    /// the parameter with the index `parameter` receive gradients in this
+    /// forward pass, e.g. the looked up rows of an embedding.
+    ///
+    /// The other rows keep their values when the parameters are updated,
+    /// see [Optimizer::sparse_step](crate::optimizers::Optimizer::sparse_step).
+    pub fn set_sparse_rows(&mut self, parameter: usize, rows: Vec<usize>) {
+        self.sparse_rows.push((parameter, rows));
+    }
+
+    /// Returns the time steps of the input with the shape `[batch, time]`
+    /// that are padding, e.g. marked by an [Embedding] with a padding index
+    /// in an earlier layer of the model.
+    ///
+    /// The mask is passed on from layer to layer as long as the layers keep
+    /// the batch and time axes, see [Layer::keeps_time_axis].
+    pub fn padding_mask(&self) -> Option<&Array2<bool>> {
+        self.padding_mask.as_ref()
+    }
+
+    /// Marks the time steps of the output with the shape `[batch, time]`
+    /// that are padding, so that later layers can ignore them, see
+    /// [Context::padding_mask]. Replaces the mask of the input for the
+    /// following layers.
+    pub fn set_padding_mask(&mut self, padding_mask: Array2<bool>) {
+        self.output_padding_mask = Some(padding_mask);
+    }
+
+    /// Returns a context for a layer that is part of this layer, e.g. the
+    /// normalization of a transformer block, with a slice of the parameters
+    /// of this layer and the padding mask of this layer.
+    ///
+    /// State updates, sparse rows and padding masks of the inner layer are
+    /// ignored.
+    pub fn sublayer<'b>(&'b mut self, parameters: &'b [Var<'t, T>]) -> Context<'b, 't, T> {
+        Context::new(self.mode, parameters, self.rng).with_padding_mask(self.padding_mask.clone())
+    }
+
+    pub(crate) fn take_sparse_rows(&mut self) -> SparseRows {
+        mem::take(&mut self.sparse_rows)
+    }
+
+    /// Returns the padding mask of the output, the one set by the layer or
+    /// else the one of the input if the layer keeps the time axis.
+    pub(crate) fn take_padding_mask(&mut self, keeps_time_axis: bool) -> Option<Array2<bool>> {
+        let input_mask = self.padding_mask.take().filter(|_| keeps_time_axis);
+        self.output_padding_mask.take().or(input_mask)
+    }
+
+    pub(crate) fn into_state(self) -> Option<Vec<ArrayD<T>>> {
+        self.state
+    }
+}
+
+/// Recreates the layer with the given name from its configuration, returns
+/// `None` if the name is unknown, the configuration does not belong to the
+/// layer or the layer has more than `max_values` parameter and state values.
+///
+/// Readers pass the number of values that can still be read, so that a
+/// corrupted configuration does not allocate more memory than the data it
+/// is read from.
+pub(crate) fn layer_from_config<T>(
+    name: &str,
+    config: &[f64],
+    max_values: usize,
+) -> Option<Box<dyn Layer<T>>>
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    match name {
+        "dropout" => Some(Box::new(Dropout::from_config(config)?)),
+        "alpha_dropout" => Some(Box::new(AlphaDropout::from_config(config)?)),
+        "batch_norm_1d" => Some(Box::new(BatchNorm1d::from_config(config, max_values)?)),
+        "layer_norm" => Some(Box::new(LayerNorm::from_config(config, max_values)?)),
+        "rms_norm" => Some(Box::new(RMSNorm::from_config(config, max_values)?)),
+        "conv_2d" => Some(Box::new(Conv2d::from_config(config, max_values)?)),
+        "max_pool_2d" => Some(Box::new(MaxPool2d::from_config(config)?)),
+        "avg_pool_2d" => Some(Box::new(AvgPool2d::from_config(config)?)),
+        "global_avg_pool_2d" => Some(Box::new(GlobalAvgPool2d::from_config(config)?)),
+        "global_max_pool_2d" => Some(Box::new(GlobalMaxPool2d::from_config(config)?)),
+        "flatten" => Some(Box::new(Flatten::from_config(config)?)),
+        "reshape" => Some(Box::new(Reshape::from_config(config)?)),
+        "permute" => Some(Box::new(Permute::from_config(config)?)),
+        "embedding" => Some(Box::new(Embedding::from_config(config, max_values)?)),
+        "simple_rnn" => Some(Box::new(SimpleRnn::from_config(config, max_values)?)),
+        "lstm" => Some(Box::new(Lstm::from_config(config, max_values)?)),
+        "gru" => Some(Box::new(Gru::from_config(config, max_values)?)),
+        "multi_head_attention" => Some(Box::new(MultiHeadAttention::from_config(
+            config, max_values,
+        )?)),
+        "transformer_encoder_block" => Some(Box::new(TransformerEncoderBlock::from_config(
+            config, max_values,
+        )?)),
+        "sinusoidal_positional_encoding" => {
+            Some(Box::new(SinusoidalPositionalEncoding::from_config(config)?))
+        }
+        "learned_positional_encoding" => Some(Box::new(LearnedPositionalEncoding::from_config(
+            config, max_values,
+        )?)),
+        _ => None,
+    }
+}
+
+/// Converts a layer name to camel case, e.g. `AlphaDropout` for
+/// `alpha_dropout`.
+fn kind(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<String>>()
+        .concat()
+}
+
+/// Converts a configuration value to a count such as the number of
+/// features, `None` if it is not a positive integer.
+pub(crate) fn positive_integer(value: f64) -> Option<usize> {
+    (value >= 1.0 && value.fract() == 0.0).then_some(value as usize)
+}
+
+/// Converts a configuration value to a size that may be zero, such as the
+/// padding, `None` if it is not a non-negative integer.
+pub(crate) fn non_negative_integer(value: f64) -> Option<usize> {
+    (value >= 0.0 && value.fract() == 0.0).then_some(value as usize)
+}
+
+/// Checks that a sample is a non-empty sequence with the shape `[time,
+/// features]`, with `dim` features if given, and returns its shape.
+pub(crate) fn check_sequence(
+    name: &'static str,
+    dim: Option<usize>,
+    input_shape: &[usize],
+) -> Result<Vec<usize>> {
+    match *input_shape {
+        [time, features] if time > 0 && features > 0 && dim.unwrap_or(features) == features => {
+            Ok(input_shape.to_vec())
+        }
+        _ => Err(Error::ShapeMismatch {
+            name,
+            expected: vec![
+                input_shape.first().copied().unwrap_or(1).max(1),
+                dim.unwrap_or_else(|| input_shape.get(1).copied().unwrap_or(1).max(1)),
+            ],
+            found: input_shape.to_vec(),
+        }),
+    }
+}
+
+/// Checks that the number of values `n` of a layer, which is `None` if it
+/// overflowed, does not exceed `max_values`, see [layer_from_config].
+pub(crate) fn check_values(n: Option<usize>, max_values: usize) -> Option<()> {
+    n.filter(|&n| n <= max_values).map(|_| ())
+}
+
+/// Converts a configuration value of zero or one to a boolean option such
+/// as causal masking, `None` otherwise.
+pub(crate) fn flag(value: f64) -> Option<bool> {
+    match value {
+        0.0 => Some(false),
+        1.0 => Some(true),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use ndarray::Array;
+
+    use super::*;
+    use crate::autograd::Tape;
+
+    /// Result of a forward pass run by [forward] or [forward_with].
+    pub(crate) struct Forward {
+        pub(crate) output: ArrayD<f64>,
+
+        /// Gradient of the sum of the output with respect to the input, if
+        /// the output depends on it.
+        pub(crate) input_gradient: Option<ArrayD<f64>>,
+        pub(crate) state: Option<Vec<ArrayD<f64>>>,
+        pub(crate) sparse_rows: SparseRows,
+    }
+
+    /// Runs the layer on `input` with its parameters recorded on a new tape.
+    pub(crate) fn forward<L: Layer + ?Sized>(
+        layer: &L,
+        input: &ArrayD<f64>,
+        mode: Mode,
+    ) -> Forward {
+        forward_with(layer.parameters(), input, mode, |x, context| {
+            layer.forward(x, context)
+        })
+    }
+
+    /// Runs `f` like [forward] with the given parameters, e.g. to pass a mask
+    /// to the layer.
+    pub(crate) fn forward_with<F>(
+        parameters: Vec<&ArrayD<f64>>,
+        input: &ArrayD<f64>,
+        mode: Mode,
+        f: F,
+    ) -> Forward
+    where
+        F: for<'a, 't> FnOnce(Var<'t, f64>, &mut Context<'a, 't, f64>) -> Var<'t, f64>,
+    {
+        let tape = Tape::new();
+        let parameters: Vec<_> = parameters
+            .into_iter()
+            .map(|p| tape.var(p.clone()))
+            .collect();
+        let x = tape.var(input.clone());
+        let mut rng = Rng::new(0);
+        let mut context = Context::new(mode, &parameters, &mut rng);
+
+        let output = f(x, &mut context);
+        let mut gradients = tape.backward(output);
+
+        Forward {
+            output: output.value(),
+            input_gradient: gradients.take(x),
+            sparse_rows: context.take_sparse_rows(),
+            state: context.into_state(),
+        }
+    }
+
+    /// Number of parameter and state values of the layer, the smallest
+    /// `max_values` for which [layer_from_config] recreates it.
+    pub(crate) fn values<L: Layer + ?Sized>(layer: &L) -> usize {
+        layer
+            .parameters()
+            .into_iter()
+            .chain(layer.state())
+            .map(|tensor| tensor.len())
+            .sum()
+    }
+
+    /// Replaces the parameters with deterministic values that differ between
+    /// the parameters.
+    pub(crate) fn deterministic(parameters: Vec<&mut ArrayD<f64>>) {
+        for (i, p) in parameters.into_iter().enumerate() {
+            *p = Array::linspace(-1.0, 1.0, p.len())
+                .mapv(|v| (v * 2.0 + i as f64).cos() * 0.5)
+                .into_shape(p.raw_dim())
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_kind() {
+        assert_eq!("Dropout", kind("dropout"));
+        assert_eq!("AlphaDropout", kind("alpha_dropout"));
+        assert_eq!("BatchNorm1d", kind("batch_norm_1d"));
+        assert_eq!("Conv2d", kind("conv_2d"));
+        assert_eq!("GlobalMaxPool2d", kind("global_max_pool_2d"));
+        assert_eq!("SimpleRnn", kind("simple_rnn"));
+        assert_eq!("TransformerEncoderBlock", kind("transformer_encoder_block"));
+    }
+
+    #[test]
+    fn test_layer_from_config() {
+        let layer = layer_from_config::<f64>("dropout", &[0.25], 0).unwrap();
 
-    fn gen_weights(&mut self) -> Array2<T>;
+        assert_eq!("dropout", layer.name());
+        assert_eq!(vec![0.25], layer.config());
+        assert!(layer_from_config::<f64>("dropout", &[], 0).is_none());
+        assert!(layer_from_config::<f64>("unknown", &[], 0).is_none());
 
-    fn gen_biases(&mut self) -> Array1<T>;
+        let config = [4.0, 3.0, -1.0, 0.0];
+        assert!(layer_from_config::<f64>("embedding", &config, 12).is_some());
+        assert!(layer_from_config::<f64>("embedding", &config, 11).is_none());
+        let config = [1e11, 1e11, -1.0, 0.0];
+        assert!(layer_from_config::<f64>("embedding", &config, usize::MAX).is_none());
+    }
 }
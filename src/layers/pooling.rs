@@ -0,0 +1,586 @@
+use ndarray::{ArrayD, ScalarOperand};
+use num_traits::{Float, FromPrimitive};
+
+use crate::{autograd::Var, Error, Result};
+
+use super::{non_negative_integer, positive_integer, window::Window, Context, Layer};
+
+/// Maximum of every window of images with the shape `[batch, channels,
+/// height, width]`, computed for every channel separately.
+///
+/// The stride defaults to the kernel size, so that the windows do not
+/// overlap. The padding is ignored by the maximum and may be at most half of
+/// the kernel size. The position of the maximum of every window is stored
+/// for the backward pass, the gradient flows to this position only.
+///
+/// # Examples
+///
+/// ```
+/// use robit::layers::{Layer, MaxPool2d};
+///
+/// let pool = MaxPool2d::new((2, 2));
+///
+/// assert_eq!(
+///     vec![8, 14, 14],
+///     Layer::<f64>::output_shape(&pool, &[8, 28, 28]).unwrap()
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaxPool2d {
+    window: Window,
+}
+
+impl MaxPool2d {
+    /// Creates the layer with a stride equal to the kernel size and no
+    /// padding.
+    pub fn new(kernel_size: (usize, usize)) -> Self {
+        Self {
+            window: pooling_window(kernel_size),
+        }
+    }
+
+    /// Step between two windows along the height and width.
+    pub fn stride(mut self, stride: (usize, usize)) -> Self {
+        self.window.stride = stride;
+        self
+    }
+
+    /// Number of values added at both sides of the height and width.
+    pub fn padding(mut self, padding: (usize, usize)) -> Self {
+        self.window.padding = padding;
+        self
+    }
+
+    pub fn kernel_size(&self) -> (usize, usize) {
+        self.window.kernel
+    }
+
+    pub(crate) fn from_config(config: &[f64]) -> Option<Self> {
+        Some(Self {
+            window: window_from_config(config)?,
+        })
+    }
+}
+
+impl<T> Layer<T> for MaxPool2d
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "max_pool_2d"
+    }
+
+    fn config(&self) -> Vec<f64> {
+        window_config(&self.window)
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        pooled_shape("max_pool_2d input", &self.window, input_shape)
+    }
+
+    fn forward<'t>(&self, input: Var<'t, T>, _: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        let (channels, size) = pooled_size(&self.window, input);
+
+        // [channels, batch * out_height * out_width, kh * kw]
+        let columns = self.window.columns(input, channels, T::neg_infinity());
+
+        unpool(max_last_axis(columns), channels, size)
+    }
+}
+
+/// Mean of every window of images with the shape `[batch, channels, height,
+/// width]`, computed for every channel separately.
+///
+/// The stride defaults to the kernel size, so that the windows do not
+/// overlap. The padding is filled with zeros that are included in the mean
+/// and may be at most half of the kernel size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AvgPool2d {
+    window: Window,
+}
+
+impl AvgPool2d {
+    /// Creates the layer with a stride equal to the kernel size and no
+    /// padding.
+    pub fn new(kernel_size: (usize, usize)) -> Self {
+        Self {
+            window: pooling_window(kernel_size),
+        }
+    }
+
+    /// Step between two windows along the height and width.
+    pub fn stride(mut self, stride: (usize, usize)) -> Self {
+        self.window.stride = stride;
+        self
+    }
+
+    /// Number of zeros added at both sides of the height and width.
+    pub fn padding(mut self, padding: (usize, usize)) -> Self {
+        self.window.padding = padding;
+        self
+    }
+
+    pub fn kernel_size(&self) -> (usize, usize) {
+        self.window.kernel
+    }
+
+    pub(crate) fn from_config(config: &[f64]) -> Option<Self> {
+        Some(Self {
+            window: window_from_config(config)?,
+        })
+    }
+}
+
+impl<T> Layer<T> for AvgPool2d
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "avg_pool_2d"
+    }
+
+    fn config(&self) -> Vec<f64> {
+        window_config(&self.window)
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        pooled_shape("avg_pool_2d input", &self.window, input_shape)
+    }
+
+    fn forward<'t>(&self, input: Var<'t, T>, _: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        let (channels, size) = pooled_size(&self.window, input);
+
+        // [channels, batch * out_height * out_width, kh * kw]
+        let columns = self.window.columns(input, channels, T::zero());
+
+        unpool(columns.mean_axis(2), channels, size)
+    }
+}
+
+/// Mean over the height and width of images with the shape `[batch,
+/// channels, height, width]`, which results in the shape `[batch,
+/// channels]`.
+///
+/// # Examples
+///
+/// ```
+/// use robit::{
+///     initializers::RandomDistr,
+///     layers::{Conv2d, GlobalAvgPool2d},
+///     Model,
+/// };
+///
+/// let mut model: Model = Model::new(0.01);
+/// model.add(Conv2d::new((1, 16), (3, 3), RandomDistr::normal()).unwrap());
+/// model.add(GlobalAvgPool2d);
+/// model.add_layer((16, 10), RandomDistr::normal());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GlobalAvgPool2d;
+
+impl GlobalAvgPool2d {
+    pub(crate) fn from_config(config: &[f64]) -> Option<Self> {
+        config.is_empty().then_some(Self)
+    }
+}
+
+impl<T> Layer<T> for GlobalAvgPool2d
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "global_avg_pool_2d"
+    }
+
+    fn config(&self) -> Vec<f64> {
+        vec![]
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        global_shape("global_avg_pool_2d input", input_shape)
+    }
+
+    fn forward<'t>(&self, input: Var<'t, T>, _: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        flatten_images(input).mean_axis(2)
+    }
+}
+
+/// Maximum over the height and width of images with the shape `[batch,
+/// channels, height, width]`, which results in the shape `[batch,
+/// channels]`.
+///
+/// The gradient flows to the position of the maximum only, like in
+/// [MaxPool2d].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GlobalMaxPool2d;
+
+impl GlobalMaxPool2d {
+    pub(crate) fn from_config(config: &[f64]) -> Option<Self> {
+        config.is_empty().then_some(Self)
+    }
+}
+
+impl<T> Layer<T> for GlobalMaxPool2d
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "global_max_pool_2d"
+    }
+
+    fn config(&self) -> Vec<f64> {
+        vec![]
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        global_shape("global_max_pool_2d input", input_shape)
+    }
+
+    fn forward<'t>(&self, input: Var<'t, T>, _: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        max_last_axis(flatten_images(input))
+    }
+}
+
+fn pooling_window(kernel_size: (usize, usize)) -> Window {
+    Window {
+        kernel: kernel_size,
+        stride: kernel_size,
+        padding: (0, 0),
+        dilation: (1, 1),
+    }
+}
+
+/// Kernel size, stride and padding.
+fn window_config(window: &Window) -> Vec<f64> {
+    [
+        window.kernel.0,
+        window.kernel.1,
+        window.stride.0,
+        window.stride.1,
+        window.padding.0,
+        window.padding.1,
+    ]
+    .iter()
+    .map(|&v| v as f64)
+    .collect()
+}
+
+fn window_from_config(config: &[f64]) -> Option<Window> {
+    let &[kh, kw, sh, sw, ph, pw] = config else {
+        return None;
+    };
+
+    Some(Window {
+        kernel: (positive_integer(kh)?, positive_integer(kw)?),
+        stride: (positive_integer(sh)?, positive_integer(sw)?),
+        padding: (non_negative_integer(ph)?, non_negative_integer(pw)?),
+        dilation: (1, 1),
+    })
+}
+
+/// Returns the shape `[channels, out_height, out_width]` of pooled images.
+///
+/// Fails with an [Error::ShapeMismatch] if the input is not an image that is
+/// at least as large as the kernel and with an [Error::InvalidHyperparameter]
+/// if the kernel size or stride is zero or the padding is larger than half
+/// of the kernel size.
+fn pooled_shape(name: &'static str, window: &Window, input_shape: &[usize]) -> Result<Vec<usize>> {
+    window.check()?;
+
+    if 2 * window.padding.0 > window.kernel.0 || 2 * window.padding.1 > window.kernel.1 {
+        return Err(Error::invalid_hyperparameter(
+            "padding",
+            "must be at most half of the kernel size",
+        ));
+    }
+
+    let (min_height, min_width) = window.min_input_size();
+
+    match *input_shape {
+        [channels, height, width] => match window.output_size((height, width)) {
+            Some((height, width)) => Ok(vec![channels, height, width]),
+            None => Err(Error::ShapeMismatch {
+                name,
+                expected: vec![channels, height.max(min_height), width.max(min_width)],
+                found: input_shape.to_vec(),
+            }),
+        },
+        _ => Err(Error::ShapeMismatch {
+            name,
+            expected: vec![
+                input_shape.first().copied().unwrap_or(1),
+                min_height,
+                min_width,
+            ],
+            found: input_shape.to_vec(),
+        }),
+    }
+}
+
+/// Returns the number of channels and the height and width of the output of
+/// a pooling layer for the images.
+fn pooled_size<T>(window: &Window, images: Var<'_, T>) -> (usize, (usize, usize)) {
+    let shape = images.shape();
+    let [_, channels, height, width] = shape[..] else {
+        panic!("pooling expects images with four axes, found {:?}", shape);
+    };
+    let size = window
+        .output_size((height, width))
+        .expect("pooling input is smaller than the kernel");
+
+    (channels, size)
+}
+
+/// Converts the pooled values with the shape
+/// `[channels, batch * out_height * out_width]` to images with the shape
+/// `[batch, channels, out_height, out_width]`.
+fn unpool<'t, T>(pooled: Var<'t, T>, channels: usize, (height, width): (usize, usize)) -> Var<'t, T>
+where
+    T: Float + ScalarOperand + 'static,
+{
+    let batch = pooled.shape()[1] / (height * width);
+
+    pooled
+        .reshape(&[channels, batch, height, width])
+        .permute(&[1, 0, 2, 3])
+}
+
+/// Checks that the input is an image with the shape `[channels, height,
+/// width]` and returns the shape `[channels]`.
+fn global_shape(name: &'static str, input_shape: &[usize]) -> Result<Vec<usize>> {
+    match *input_shape {
+        [channels, height, width] if height > 0 && width > 0 => Ok(vec![channels]),
+        _ => Err(Error::ShapeMismatch {
+            name,
+            expected: vec![input_shape.first().copied().unwrap_or(1), 1, 1],
+            found: input_shape.to_vec(),
+        }),
+    }
+}
+
+/// Merges the height and width of images, `[batch, channels, height *
+/// width]`.
+fn flatten_images<T>(images: Var<'_, T>) -> Var<'_, T>
+where
+    T: Float + ScalarOperand + 'static,
+{
+    let shape = images.shape();
+    let [batch, channels, height, width] = shape[..] else {
+        panic!("pooling expects images with four axes, found {:?}", shape);
+    };
+
+    images.reshape(&[batch, channels, height * width])
+}
+
+/// Maximum along the last axis, which is removed.
+///
+/// The position of the first maximum of every row is stored for the backward
+/// pass, the other values get a gradient of zero.
+fn max_last_axis<T: Float + 'static>(input: Var<'_, T>) -> Var<'_, T> {
+    let mut shape = input.shape();
+    let n = shape.pop().unwrap();
+
+    let (values, argmax): (Vec<T>, Vec<usize>) = input.with_value(|x| {
+        let x = x.as_standard_layout();
+
+        x.as_slice()
+            .unwrap()
+            .chunks(n)
+            .enumerate()
+            .map(|(row, values)| {
+                let mut k = 0;
+                for (i, &v) in values.iter().enumerate() {
+                    if v > values[k] {
+                        k = i;
+                    }
+                }
+
+                (values[k], row * n + k)
+            })
+            .unzip()
+    });
+    let value = ArrayD::from_shape_vec(shape, values).unwrap();
+
+    input.tape().op(&[input], value, move |grad, inputs, _| {
+        let mut input_grad = ArrayD::zeros(inputs[0].shape());
+        let values = input_grad.as_slice_mut().unwrap();
+
+        for (&g, &i) in grad.iter().zip(argmax.iter()) {
+            values[i] = g;
+        }
+
+        vec![input_grad]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{s, Array};
+
+    use super::*;
+    use crate::{
+        autograd::check,
+        layers::{tests::forward, Mode},
+        training::Rng,
+    };
+
+    fn check_gradients<L: Layer>(layer: &L, input: ArrayD<f64>) {
+        let output = forward(layer, &input, Mode::Training).output;
+        let weights = Array::linspace(0.0, 3.0, output.len())
+            .mapv(f64::cos)
+            .into_shape(output.raw_dim())
+            .unwrap();
+
+        check(&[input], |v| {
+            let mut rng = Rng::new(0);
+            let mut context = Context::new(Mode::Training, &[], &mut rng);
+
+            layer.forward(v[0], &mut context) * v[0].tape().constant(weights.clone())
+        });
+    }
+
+    /// Distinct values, so that the maximum of every window is unique.
+    fn input(shape: (usize, usize, usize, usize)) -> ArrayD<f64> {
+        Array::from_shape_fn(shape, |(n, c, y, x)| {
+            ((n * 13 + c * 7 + y * 5 + x) as f64 * 1.37).sin()
+        })
+        .into_dyn()
+    }
+
+    fn image(values: &[f64], size: usize) -> ArrayD<f64> {
+        Array::from_shape_vec((1, 1, size, size), values.to_vec())
+            .unwrap()
+            .into_dyn()
+    }
+
+    #[test]
+    fn test_max_pool() {
+        let x = image(&(1..=16).map(f64::from).collect::<Vec<_>>(), 4);
+
+        assert_eq!(
+            image(&[6.0, 8.0, 14.0, 16.0], 2),
+            forward(&MaxPool2d::new((2, 2)), &x, Mode::Training).output
+        );
+        assert_eq!(
+            image(&[1.0, 3.0, 4.0, 9.0, 11.0, 12.0, 13.0, 15.0, 16.0], 3),
+            forward(&MaxPool2d::new((2, 2)).padding((1, 1)), &x, Mode::Training).output
+        );
+    }
+
+    #[test]
+    fn test_max_pool_backward() {
+        let x = image(&[1.0, 5.0, 2.0, 0.0, 3.0, -1.0, 4.0, 2.0, 0.5], 3);
+
+        // overlapping windows with the maxima 5, 5, 4 and 3
+        let result = forward(&MaxPool2d::new((2, 2)).stride((1, 1)), &x, Mode::Training);
+
+        assert_eq!(
+            Some(image(&[0.0, 2.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0], 3)),
+            result.input_gradient
+        );
+    }
+
+    #[test]
+    fn test_avg_pool() {
+        let x = image(&(1..=16).map(f64::from).collect::<Vec<_>>(), 4);
+
+        assert_eq!(
+            image(&[3.5, 5.5, 11.5, 13.5], 2),
+            forward(&AvgPool2d::new((2, 2)), &x, Mode::Training).output
+        );
+        // the padding counts as zero
+        assert_eq!(
+            image(&[0.25, 1.25, 1.0, 3.5, 8.5, 5.0, 3.25, 7.25, 4.0], 3),
+            forward(&AvgPool2d::new((2, 2)).padding((1, 1)), &x, Mode::Training).output
+        );
+    }
+
+    #[test]
+    fn test_global_pool() {
+        let x = input((2, 3, 4, 5));
+        let avg = forward(&GlobalAvgPool2d, &x, Mode::Training).output;
+        let max = forward(&GlobalMaxPool2d, &x, Mode::Training).output;
+
+        assert_eq!(&[2, 3], avg.shape());
+        assert_eq!(&[2, 3], max.shape());
+        for n in 0..2 {
+            for c in 0..3 {
+                let image = x.slice(s![n, c, .., ..]);
+                assert!((image.mean().unwrap() - avg[[n, c]]).abs() < 1e-12);
+                assert_eq!(image.fold(f64::MIN, |a, &b| a.max(b)), max[[n, c]]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gradients() {
+        check_gradients(&MaxPool2d::new((2, 2)), input((2, 2, 4, 4)));
+        check_gradients(
+            &MaxPool2d::new((3, 2)).stride((2, 1)).padding((1, 1)),
+            input((1, 2, 5, 4)),
+        );
+        check_gradients(&AvgPool2d::new((2, 2)), input((2, 2, 4, 4)));
+        check_gradients(
+            &AvgPool2d::new((3, 3)).stride((2, 2)).padding((1, 1)),
+            input((1, 2, 5, 6)),
+        );
+        check_gradients(&GlobalAvgPool2d, input((2, 3, 3, 2)));
+        check_gradients(&GlobalMaxPool2d, input((2, 3, 3, 2)));
+    }
+
+    #[test]
+    fn test_output_shape() {
+        let shape = |layer: &dyn Layer, input: &[usize]| layer.output_shape(input);
+
+        assert_eq!(
+            vec![8, 14, 14],
+            shape(&MaxPool2d::new((2, 2)), &[8, 28, 28]).unwrap()
+        );
+        assert_eq!(
+            vec![8, 15, 15],
+            shape(&AvgPool2d::new((2, 2)).padding((1, 1)), &[8, 28, 28]).unwrap()
+        );
+        assert_eq!(vec![8], shape(&GlobalMaxPool2d, &[8, 3, 3]).unwrap());
+        assert!(matches!(
+            shape(&MaxPool2d::new((3, 3)), &[8, 2, 5]),
+            Err(Error::ShapeMismatch { expected, .. }) if expected == vec![8, 3, 5]
+        ));
+        assert!(matches!(
+            shape(&AvgPool2d::new((2, 2)), &[16]),
+            Err(Error::ShapeMismatch { expected, .. }) if expected == vec![16, 2, 2]
+        ));
+        assert!(matches!(
+            shape(&MaxPool2d::new((2, 2)).padding((2, 0)), &[1, 8, 8]),
+            Err(Error::InvalidHyperparameter {
+                name: "padding",
+                ..
+            })
+        ));
+        assert!(matches!(
+            shape(&AvgPool2d::new((2, 2)).stride((1, 0)), &[1, 8, 8]),
+            Err(Error::InvalidHyperparameter { name: "stride", .. })
+        ));
+        assert!(shape(&GlobalAvgPool2d, &[16]).is_err());
+    }
+
+    #[test]
+    fn test_from_config() {
+        let max = MaxPool2d::new((3, 2)).stride((1, 2)).padding((1, 0));
+        let avg = AvgPool2d::new((2, 2));
+
+        assert_eq!(
+            Some(max),
+            MaxPool2d::from_config(&Layer::<f64>::config(&max))
+        );
+        assert_eq!(
+            Some(avg),
+            AvgPool2d::from_config(&Layer::<f64>::config(&avg))
+        );
+        assert_eq!(
+            None,
+            MaxPool2d::from_config(&[2.0, 2.0, 0.0, 2.0, 0.0, 0.0])
+        );
+        assert_eq!(Some(GlobalAvgPool2d), GlobalAvgPool2d::from_config(&[]));
+        assert_eq!(None, GlobalMaxPool2d::from_config(&[1.0]));
+    }
+}
@@ -0,0 +1,296 @@
+use ndarray::{Array2, ArrayD, ScalarOperand};
+use num_traits::{Float, FromPrimitive, Zero};
+
+use crate::{
+    autograd::Var,
+    initializers::{Initializer, Zeros},
+    Error, Result,
+};
+
+use super::{check_sequence, check_values, positive_integer, Context, Layer};
+
+/// Adds fixed sine and cosine waves of different frequencies to sequences
+/// with the shape `[batch, time, dim]`, so that attention can tell the time
+/// steps apart.
+///
+/// The encoding of time step `t` is `sin(t / 10000^(2i / dim))` at the even
+/// feature `2i` and `cos(t / 10000^(2i / dim))` at the odd feature `2i + 1`.
+/// It has no parameters and works for sequences of any length.
+///
+/// # Examples
+///
+/// ```
+/// use robit::layers::{Layer, SinusoidalPositionalEncoding};
+///
+/// assert_eq!(
+///     vec![10, 16],
+///     Layer::<f64>::output_shape(&SinusoidalPositionalEncoding, &[10, 16]).unwrap()
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SinusoidalPositionalEncoding;
+
+impl SinusoidalPositionalEncoding {
+    /// Returns the encoding with the shape `[time, dim]`.
+    pub fn encoding<T: Float>(time: usize, dim: usize) -> Array2<T> {
+        Array2::from_shape_fn((time, dim), |(t, feature)| {
+            let exponent = (feature - feature % 2) as f64 / dim as f64;
+            let angle = t as f64 / 10000f64.powf(exponent);
+            let value = if feature % 2 == 0 {
+                angle.sin()
+            } else {
+                angle.cos()
+            };
+
+            T::from(value).unwrap()
+        })
+    }
+
+    pub(crate) fn from_config(config: &[f64]) -> Option<Self> {
+        config.is_empty().then_some(Self)
+    }
+}
+
+impl<T> Layer<T> for SinusoidalPositionalEncoding
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "sinusoidal_positional_encoding"
+    }
+
+    fn config(&self) -> Vec<f64> {
+        vec![]
+    }
+
+    /// Fails with an [Error::ShapeMismatch] if the input is not a sequence.
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        check_sequence("sinusoidal_positional_encoding input", None, input_shape)
+    }
+
+    fn keeps_time_axis(&self) -> bool {
+        true
+    }
+
+    fn forward<'t>(&self, input: Var<'t, T>, _: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        let shape = input.shape();
+        let [_, time, dim] = shape[..] else {
+            panic!(
+                "positional encodings expect sequences with three axes, found {:?}",
+                shape
+            );
+        };
+
+        input + input.tape().constant(Self::encoding::<T>(time, dim))
+    }
+}
+
+/// Adds a trained vector for every time step to sequences with the shape
+/// `[batch, time, dim]`, for sequences of up to `max_len` time steps.
+///
+/// The weight has the shape `[max_len, dim]`, the first `time` rows are
+/// added to the input.
+///
+/// # Examples
+///
+/// ```
+/// use robit::{
+///     initializers::RandomDistr,
+///     layers::{Layer, LearnedPositionalEncoding},
+/// };
+///
+/// let encoding: LearnedPositionalEncoding =
+///     LearnedPositionalEncoding::new(512, 16, RandomDistr::normal()).unwrap();
+///
+/// assert_eq!(vec![10, 16], encoding.output_shape(&[10, 16]).unwrap());
+/// assert!(encoding.output_shape(&[1000, 16]).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LearnedPositionalEncoding<T = f64> {
+    weight: ArrayD<T>,
+}
+
+impl<T> LearnedPositionalEncoding<T> {
+    /// Fails with an [Error::InvalidHyperparameter] if `max_len` or `dim` is
+    /// zero.
+    pub fn new<I: Initializer<T>>(max_len: usize, dim: usize, init: I) -> Result<Self> {
+        if max_len == 0 {
+            return Err(Error::invalid_hyperparameter("max_len", "must be positive"));
+        }
+        if dim == 0 {
+            return Err(Error::invalid_hyperparameter("dim", "must be positive"));
+        }
+
+        Ok(Self {
+            weight: init.gen(vec![max_len, dim]),
+        })
+    }
+
+    pub fn max_len(&self) -> usize {
+        self.weight.shape()[0]
+    }
+
+    pub fn dim(&self) -> usize {
+        self.weight.shape()[1]
+    }
+
+    pub(crate) fn from_config(config: &[f64], max_values: usize) -> Option<Self>
+    where
+        T: Zero + Clone,
+    {
+        let &[max_len, dim] = config else {
+            return None;
+        };
+        let (max_len, dim) = (positive_integer(max_len)?, positive_integer(dim)?);
+        check_values(max_len.checked_mul(dim), max_values)?;
+
+        Self::new(max_len, dim, Zeros).ok()
+    }
+}
+
+impl<T> Layer<T> for LearnedPositionalEncoding<T>
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "learned_positional_encoding"
+    }
+
+    fn config(&self) -> Vec<f64> {
+        vec![self.max_len() as f64, self.dim() as f64]
+    }
+
+    /// Fails with an [Error::ShapeMismatch] if the input is not a sequence
+    /// of at most [LearnedPositionalEncoding::max_len] time steps with
+    /// [LearnedPositionalEncoding::dim] features.
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        if input_shape
+            .first()
+            .is_some_and(|&time| time > self.max_len())
+        {
+            return Err(Error::ShapeMismatch {
+                name: "learned_positional_encoding input",
+                expected: vec![self.max_len(), self.dim()],
+                found: input_shape.to_vec(),
+            });
+        }
+
+        check_sequence(
+            "learned_positional_encoding input",
+            Some(self.dim()),
+            input_shape,
+        )
+    }
+
+    fn keeps_time_axis(&self) -> bool {
+        true
+    }
+
+    fn forward<'t>(&self, input: Var<'t, T>, context: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        let [weight] = context.parameters() else {
+            unreachable!("learned positional encodings have one parameter");
+        };
+        let time = input.shape()[1];
+
+        input + weight.slice_axis(0, 0..time)
+    }
+
+    fn parameters(&self) -> Vec<&ArrayD<T>> {
+        vec![&self.weight]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut ArrayD<T>> {
+        vec![&mut self.weight]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{Array, Array3, Axis};
+
+    use super::*;
+    use crate::{
+        autograd::check,
+        layers::{
+            tests::{forward, values},
+            Mode,
+        },
+        training::Rng,
+    };
+
+    #[test]
+    fn test_sinusoidal() {
+        let encoding = SinusoidalPositionalEncoding::encoding::<f64>(3, 4);
+
+        assert_eq!(&[0.0, 1.0, 0.0, 1.0], encoding.row(0).as_slice().unwrap());
+        assert_eq!(1f64.sin(), encoding[[1, 0]]);
+        assert_eq!(1f64.cos(), encoding[[1, 1]]);
+        assert!((0.02f64.sin() - encoding[[2, 2]]).abs() < 1e-15);
+
+        let x = Array3::ones((2, 3, 4)).into_dyn();
+        let y = forward(&SinusoidalPositionalEncoding, &x, Mode::Inference).output;
+
+        assert_eq!((encoding + 1.0).into_dyn(), y.index_axis_move(Axis(0), 1));
+        assert!(Layer::<f64>::output_shape(&SinusoidalPositionalEncoding, &[3]).is_err());
+    }
+
+    #[test]
+    fn test_learned() {
+        let mut encoding: LearnedPositionalEncoding =
+            LearnedPositionalEncoding::new(5, 2, Zeros).unwrap();
+        encoding.weight = Array::range(0.0, 10.0, 1.0)
+            .into_shape((5, 2))
+            .unwrap()
+            .into_dyn();
+
+        let x = Array::from_shape_fn((2, 3, 2), |(n, t, f)| (n * 6 + t * 2 + f) as f64 * 0.1)
+            .into_dyn();
+        let weights = Array::linspace(-1.0, 1.0, 12)
+            .into_shape((2, 3, 2))
+            .unwrap();
+        check(&[x.clone(), encoding.weight.clone()], |v| {
+            let tape = v[0].tape();
+            let mut rng = Rng::new(0);
+            let mut context = Context::new(Mode::Training, &v[1..], &mut rng);
+
+            encoding.forward(v[0], &mut context).square() * tape.constant(weights.clone())
+        });
+
+        let y = forward(&encoding, &x, Mode::Training).output;
+        assert_eq!(x[[1, 2, 1]] + 5.0, y[[1, 2, 1]]);
+
+        assert_eq!(vec![5, 2], encoding.output_shape(&[5, 2]).unwrap());
+        assert!(matches!(
+            encoding.output_shape(&[6, 2]),
+            Err(Error::ShapeMismatch { expected, .. }) if expected == vec![5, 2]
+        ));
+        assert!(encoding.output_shape(&[3, 3]).is_err());
+    }
+
+    #[test]
+    fn test_from_config() {
+        let encoding: LearnedPositionalEncoding =
+            LearnedPositionalEncoding::new(5, 2, Zeros).unwrap();
+
+        assert_eq!(
+            Some(encoding.clone()),
+            LearnedPositionalEncoding::from_config(&encoding.config(), values(&encoding))
+        );
+        assert_eq!(
+            None,
+            LearnedPositionalEncoding::<f64>::from_config(&encoding.config(), 9)
+        );
+        assert_eq!(
+            None,
+            LearnedPositionalEncoding::<f64>::from_config(&[0.0, 2.0], usize::MAX)
+        );
+        assert_eq!(
+            Some(SinusoidalPositionalEncoding),
+            SinusoidalPositionalEncoding::from_config(&[])
+        );
+        assert!(matches!(
+            LearnedPositionalEncoding::<f64>::new(5, 0, Zeros),
+            Err(Error::InvalidHyperparameter { name: "dim", .. })
+        ));
+    }
+}
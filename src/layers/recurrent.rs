@@ -0,0 +1,805 @@
+use ndarray::{ArrayD, ScalarOperand};
+use num_traits::{Float, FromPrimitive, Zero};
+
+use crate::{
+    autograd::Var,
+    initializers::{Initializer, Zeros},
+    Error, Result,
+};
+
+use super::{check_values, flag, positive_integer, Context, Layer};
+
+/// Defines a public recurrent layer that wraps [Recurrent] with a fixed
+/// cell, with the builders, accessors and the [Layer] implementation.
+macro_rules! recurrent_layer {
+    ($(#[$attr:meta])* $layer:ident, $cell:expr, $name:literal) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct $layer<T = f64> {
+            rnn: Recurrent<T>,
+        }
+
+        impl<T> $layer<T> {
+            /// Creates a unidirectional layer that returns the last hidden
+            /// state.
+            ///
+            /// Fails with an [Error::InvalidHyperparameter] if `input_size` or
+            /// `units` is zero.
+            pub fn new<I: Initializer<T>>(
+                input_size: usize,
+                units: usize,
+                init: I,
+            ) -> Result<Self> {
+                Recurrent::new($cell, input_size, units, false, init).map(|rnn| Self { rnn })
+            }
+
+            #[doc = concat!("Creates the layer like [", stringify!($layer), "::new] with a second")]
+            /// set of weights for the reversed sequence.
+            pub fn bidirectional<I: Initializer<T>>(
+                input_size: usize,
+                units: usize,
+                init: I,
+            ) -> Result<Self> {
+                Recurrent::new($cell, input_size, units, true, init).map(|rnn| Self { rnn })
+            }
+
+            /// Whether the output contains the hidden state after every time
+            /// step instead of only the last one.
+            pub fn return_sequences(mut self, return_sequences: bool) -> Self {
+                self.rnn.return_sequences = return_sequences;
+                self
+            }
+
+            /// Whether the final states are appended to the output along the
+            /// time axis, see
+            #[doc = concat!("[", stringify!($layer), "::forward_with_state]")]
+            /// to get them separately.
+            ///
+            /// The output has the shape `[batch, steps + states, units]` where
+            /// `steps` is the number of time steps with
+            #[doc = concat!("[", stringify!($layer), "::return_sequences]")]
+            /// and one otherwise, and `states` is two for the hidden and cell
+            /// state of an [Lstm] and one otherwise.
+            pub fn return_state(mut self, return_state: bool) -> Self {
+                self.rnn.return_state = return_state;
+                self
+            }
+
+            pub fn input_size(&self) -> usize {
+                self.rnn.input_size()
+            }
+
+            pub fn units(&self) -> usize {
+                self.rnn.units()
+            }
+
+            pub fn is_bidirectional(&self) -> bool {
+                self.rnn.directions() == 2
+            }
+
+            /// Records the output like [Layer::forward] without the states and
+            /// returns the final states separately, e.g. to initialize a
+            /// decoder in a custom layer.
+            pub fn forward_with_state<'t>(
+                &self,
+                input: Var<'t, T>,
+                context: &mut Context<'_, 't, T>,
+            ) -> (Var<'t, T>, Vec<Var<'t, T>>)
+            where
+                T: Float + ScalarOperand + 'static,
+            {
+                self.rnn.forward_with_state(input, context)
+            }
+
+            pub(crate) fn from_config(config: &[f64], max_values: usize) -> Option<Self>
+            where
+                T: Zero + Clone,
+            {
+                Recurrent::from_config($cell, config, max_values).map(|rnn| Self { rnn })
+            }
+        }
+
+        impl<T> Layer<T> for $layer<T>
+        where
+            T: Float + FromPrimitive + ScalarOperand + 'static,
+        {
+            fn name(&self) -> &'static str {
+                $name
+            }
+
+            fn config(&self) -> Vec<f64> {
+                self.rnn.config()
+            }
+
+            /// Fails with an [Error::ShapeMismatch] if the input is not a
+            /// non-empty sequence with
+            #[doc = concat!("[", stringify!($layer), "::input_size] features.")]
+            fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+                self.rnn.output_shape(concat!($name, " input"), input_shape)
+            }
+
+            /// Only keeps the time axis with
+            #[doc = concat!("[", stringify!($layer), "::return_sequences]")]
+            /// and without
+            #[doc = concat!("[", stringify!($layer), "::return_state].")]
+            fn keeps_time_axis(&self) -> bool {
+                self.rnn.return_sequences && !self.rnn.return_state
+            }
+
+            fn forward<'t>(
+                &self,
+                input: Var<'t, T>,
+                context: &mut Context<'_, 't, T>,
+            ) -> Var<'t, T> {
+                self.rnn.forward(input, context)
+            }
+
+            fn parameters(&self) -> Vec<&ArrayD<T>> {
+                self.rnn.parameters.iter().collect()
+            }
+
+            fn parameters_mut(&mut self) -> Vec<&mut ArrayD<T>> {
+                self.rnn.parameters.iter_mut().collect()
+            }
+        }
+    };
+}
+
+recurrent_layer!(
+    /// Fully connected recurrent layer over sequences with the shape `[batch,
+    /// time, features]`, `h = tanh(x·W + h·U + b)`.
+    ///
+    /// By default the output is the hidden state `h` after the last time step
+    /// with the shape `[batch, units]`. With [SimpleRnn::return_sequences] it is
+    /// the hidden state after every time step with the shape `[batch, time,
+    /// units]`, e.g. to stack recurrent layers. A bidirectional layer also runs
+    /// over the reversed sequence with separate weights, the outputs of both
+    /// directions are concatenated to `2 * units` features.
+    ///
+    /// The weights of every direction are the kernel `W` with the shape
+    /// `[input_size, units]`, the recurrent kernel `U` with the shape `[units,
+    /// units]` and the bias `b` with the shape `[units]`. The gradients are
+    /// backpropagated through time on the tape.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ndarray::Array3;
+    /// use robit::{initializers::RandomDistr, layers::SimpleRnn, Model};
+    ///
+    /// let mut model: Model = Model::new(0.01);
+    /// model.add(SimpleRnn::new(3, 16, RandomDistr::normal()).unwrap());
+    /// model.add_layer((16, 1), RandomDistr::normal());
+    ///
+    /// // 4 sequences of 10 time steps with 3 features
+    /// let sequences = Array3::zeros((4, 10, 3));
+    ///
+    /// assert_eq!(&[4, 1], model.predict(&sequences).shape());
+    /// ```
+    SimpleRnn,
+    Cell::Simple,
+    "simple_rnn"
+);
+
+recurrent_layer!(
+    /// Long short-term memory layer over sequences with the shape `[batch, time,
+    /// features]`.
+    ///
+    /// Every time step computes the input, forget, cell and output gates
+    ///
+    /// ```text
+    /// i = σ(x·Wi + h·Ui + bi)    f = σ(x·Wf + h·Uf + bf)
+    /// g = tanh(x·Wg + h·Ug + bg) o = σ(x·Wo + h·Uo + bo)
+    /// ```
+    ///
+    /// and updates the cell state `c = f * c + i * g` and the hidden state `h =
+    /// o * tanh(c)`. The kernels of the gates are concatenated in this order to
+    /// weights with `4 * units` columns. The outputs, bidirectional layers and
+    /// the training are the same as for [SimpleRnn], with the cell state as a
+    /// second state for [Lstm::return_state].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ndarray::Array3;
+    /// use robit::{
+    ///     initializers::RandomDistr,
+    ///     layers::{Flatten, Lstm},
+    ///     Model,
+    /// };
+    ///
+    /// let mut model: Model = Model::new(0.01);
+    /// model.add(
+    ///     Lstm::bidirectional(3, 8, RandomDistr::normal())
+    ///         .unwrap()
+    ///         .return_sequences(true),
+    /// );
+    /// model.add(Flatten);
+    /// model.add_layer((10 * 16, 1), RandomDistr::normal());
+    ///
+    /// let sequences = Array3::zeros((4, 10, 3));
+    ///
+    /// assert_eq!(&[4, 1], model.predict(&sequences).shape());
+    /// ```
+    Lstm,
+    Cell::Lstm,
+    "lstm"
+);
+
+recurrent_layer!(
+    /// Gated recurrent unit layer over sequences with the shape `[batch, time,
+    /// features]`.
+    ///
+    /// Every time step computes the update and reset gates and the candidate
+    ///
+    /// ```text
+    /// z = σ(x·Wz + h·Uz + bz)    r = σ(x·Wr + h·Ur + br)
+    /// n = tanh(x·Wn + (r * h)·Un + bn)
+    /// ```
+    ///
+    /// and updates the hidden state `h = z * h + (1 - z) * n`. The kernels are
+    /// concatenated in this order to weights with `3 * units` columns. The
+    /// outputs, bidirectional layers and the training are the same as for
+    /// [SimpleRnn].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ndarray::Array3;
+    /// use robit::{initializers::RandomDistr, layers::Gru, Model};
+    ///
+    /// let mut model: Model = Model::new(0.01);
+    /// model.add(Gru::new(3, 16, RandomDistr::normal()).unwrap());
+    /// model.add_layer((16, 1), RandomDistr::normal());
+    ///
+    /// let sequences = Array3::zeros((4, 10, 3));
+    ///
+    /// assert_eq!(&[4, 1], model.predict(&sequences).shape());
+    /// ```
+    Gru,
+    Cell::Gru,
+    "gru"
+);
+
+/// Update of the states in a single time step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Simple,
+    Lstm,
+    Gru,
+}
+
+impl Cell {
+    /// Number of blocks of `units` columns in the weights, e.g. the four
+    /// gates of an LSTM.
+    fn gates(self) -> usize {
+        match self {
+            Cell::Simple => 1,
+            Cell::Lstm => 4,
+            Cell::Gru => 3,
+        }
+    }
+
+    /// Number of states that are carried from one time step to the next, the
+    /// first one is the hidden state.
+    fn n_states(self) -> usize {
+        match self {
+            Cell::Lstm => 2,
+            Cell::Simple | Cell::Gru => 1,
+        }
+    }
+
+    /// Returns the states after a time step, `x` is the input of the time
+    /// step multiplied with the kernel plus the bias.
+    fn step<'t, T>(
+        self,
+        x: Var<'t, T>,
+        states: &[Var<'t, T>],
+        recurrent_kernel: Var<'t, T>,
+    ) -> Vec<Var<'t, T>>
+    where
+        T: Float + ScalarOperand + 'static,
+    {
+        let h = states[0];
+        let units = h.shape()[1];
+        let gate = |z: Var<'t, T>, k: usize| z.slice_axis(1, k * units..(k + 1) * units);
+
+        match self {
+            Cell::Simple => vec![(x + h.matmul(recurrent_kernel)).tanh()],
+            Cell::Lstm => {
+                let z = x + h.matmul(recurrent_kernel);
+                let i = gate(z, 0).sigmoid();
+                let f = gate(z, 1).sigmoid();
+                let g = gate(z, 2).tanh();
+                let o = gate(z, 3).sigmoid();
+                let c = f * states[1] + i * g;
+
+                vec![o * c.tanh(), c]
+            }
+            Cell::Gru => {
+                let update_reset = (x.slice_axis(1, 0..2 * units)
+                    + h.matmul(recurrent_kernel.slice_axis(1, 0..2 * units)))
+                .sigmoid();
+                let z = gate(update_reset, 0);
+                let r = gate(update_reset, 1);
+                let n = (gate(x, 2) + (r * h).matmul(gate(recurrent_kernel, 2))).tanh();
+
+                // z * h + (1 - z) * n
+                vec![n + z * (h - n)]
+            }
+        }
+    }
+}
+
+/// Weights and options shared by [SimpleRnn], [Lstm] and [Gru].
+#[derive(Debug, Clone, PartialEq)]
+struct Recurrent<T> {
+    cell: Cell,
+    return_sequences: bool,
+    return_state: bool,
+
+    /// Kernel, recurrent kernel and bias of every direction.
+    parameters: Vec<ArrayD<T>>,
+}
+
+impl<T> Recurrent<T> {
+    fn new<I: Initializer<T>>(
+        cell: Cell,
+        input_size: usize,
+        units: usize,
+        bidirectional: bool,
+        init: I,
+    ) -> Result<Self> {
+        if input_size == 0 {
+            return Err(Error::invalid_hyperparameter(
+                "input_size",
+                "must be positive",
+            ));
+        }
+        if units == 0 {
+            return Err(Error::invalid_hyperparameter("units", "must be positive"));
+        }
+
+        let columns = cell.gates() * units;
+        let directions = if bidirectional { 2 } else { 1 };
+
+        Ok(Self {
+            cell,
+            return_sequences: false,
+            return_state: false,
+            parameters: (0..directions)
+                .flat_map(|_| {
+                    [
+                        init.gen(vec![input_size, columns]),
+                        init.gen(vec![units, columns]),
+                        init.gen(vec![columns]),
+                    ]
+                })
+                .collect(),
+        })
+    }
+
+    fn input_size(&self) -> usize {
+        self.parameters[0].shape()[0]
+    }
+
+    fn units(&self) -> usize {
+        self.parameters[1].shape()[0]
+    }
+
+    fn directions(&self) -> usize {
+        self.parameters.len() / 3
+    }
+
+    fn config(&self) -> Vec<f64> {
+        vec![
+            self.input_size() as f64,
+            self.units() as f64,
+            (self.directions() == 2) as u8 as f64,
+            self.return_sequences as u8 as f64,
+            self.return_state as u8 as f64,
+        ]
+    }
+
+    fn from_config(cell: Cell, config: &[f64], max_values: usize) -> Option<Self>
+    where
+        T: Zero + Clone,
+    {
+        let &[input_size, units, bidirectional, return_sequences, return_state] = config else {
+            return None;
+        };
+        let (input_size, units) = (positive_integer(input_size)?, positive_integer(units)?);
+        let bidirectional = flag(bidirectional)?;
+        // kernel, recurrent kernel and bias of every direction
+        check_values(
+            input_size
+                .checked_add(units)
+                .and_then(|n| n.checked_add(1))
+                .and_then(|n| n.checked_mul(units))
+                .and_then(|n| n.checked_mul(cell.gates() * if bidirectional { 2 } else { 1 })),
+            max_values,
+        )?;
+        let mut rnn = Self::new(cell, input_size, units, bidirectional, Zeros).ok()?;
+        rnn.return_sequences = flag(return_sequences)?;
+        rnn.return_state = flag(return_state)?;
+
+        Some(rnn)
+    }
+
+    fn output_shape(&self, name: &'static str, input_shape: &[usize]) -> Result<Vec<usize>> {
+        let time = match *input_shape {
+            [time, features] if time > 0 && features == self.input_size() => time,
+            _ => {
+                return Err(Error::ShapeMismatch {
+                    name,
+                    expected: vec![
+                        input_shape.first().copied().unwrap_or(1).max(1),
+                        self.input_size(),
+                    ],
+                    found: input_shape.to_vec(),
+                })
+            }
+        };
+        let width = self.directions() * self.units();
+        let steps = if self.return_sequences { time } else { 1 };
+
+        Ok(match (self.return_sequences, self.return_state) {
+            (false, false) => vec![width],
+            (true, false) => vec![time, width],
+            (_, true) => vec![steps + self.cell.n_states(), width],
+        })
+    }
+
+    /// Returns the output without the states and the final states, each
+    /// with the directions concatenated along the last axis.
+    fn forward_with_state<'t>(
+        &self,
+        input: Var<'t, T>,
+        context: &mut Context<'_, 't, T>,
+    ) -> (Var<'t, T>, Vec<Var<'t, T>>)
+    where
+        T: Float + ScalarOperand + 'static,
+    {
+        let shape = input.shape();
+        let [batch, time, _] = shape[..] else {
+            panic!(
+                "recurrent layers expect sequences with three axes, found {:?}",
+                shape
+            );
+        };
+        let tape = input.tape();
+        let mut sequences = vec![];
+        let mut final_states = vec![];
+
+        for (direction, weights) in context.parameters().chunks(3).enumerate() {
+            let [kernel, recurrent_kernel, bias] = *weights else {
+                unreachable!("recurrent layers have three parameters per direction");
+            };
+            // [batch, time, gates * units]
+            let x = input.matmul(kernel) + bias;
+
+            let zeros = tape.constant(ArrayD::zeros(vec![batch, self.units()]));
+            let mut states = vec![zeros; self.cell.n_states()];
+            let mut sequence = Vec::with_capacity(time);
+
+            for step in 0..time {
+                // the second direction runs over the reversed sequence
+                let t = if direction == 0 {
+                    step
+                } else {
+                    time - 1 - step
+                };
+                states = self
+                    .cell
+                    .step(x.index_axis(1, t), &states, recurrent_kernel);
+                sequence.push(states[0]);
+            }
+            if direction == 1 {
+                sequence.reverse();
+            }
+
+            sequences.push(sequence);
+            final_states.push(states);
+        }
+
+        let output = if self.return_sequences {
+            let sequences: Vec<_> = sequences
+                .iter()
+                .map(|sequence| Var::stack(1, sequence))
+                .collect();
+            Var::concatenate(2, &sequences)
+        } else {
+            let last: Vec<_> = final_states.iter().map(|states| states[0]).collect();
+            Var::concatenate(1, &last)
+        };
+        let states = (0..self.cell.n_states())
+            .map(|k| {
+                let state: Vec<_> = final_states.iter().map(|states| states[k]).collect();
+                Var::concatenate(1, &state)
+            })
+            .collect();
+
+        (output, states)
+    }
+
+    fn forward<'t>(&self, input: Var<'t, T>, context: &mut Context<'_, 't, T>) -> Var<'t, T>
+    where
+        T: Float + ScalarOperand + 'static,
+    {
+        let (output, states) = self.forward_with_state(input, context);
+
+        if !self.return_state {
+            return output;
+        }
+
+        let output = if self.return_sequences {
+            output
+        } else {
+            output.insert_axis(1)
+        };
+
+        Var::concatenate(1, &[output, Var::stack(1, &states)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{concatenate, s, Array, Array1, Array2, Array3, ArrayViewD, Axis};
+
+    use super::*;
+    use crate::{
+        autograd::check,
+        layers::{
+            tests::{deterministic, forward_with, values},
+            Mode,
+        },
+        training::Rng,
+    };
+
+    const CELLS: [Cell; 3] = [Cell::Simple, Cell::Lstm, Cell::Gru];
+
+    /// Layer with 2 input features, 3 units and deterministic weights.
+    fn recurrent(cell: Cell, bidirectional: bool) -> Recurrent<f64> {
+        let mut rnn = Recurrent::new(cell, 2, 3, bidirectional, Zeros).unwrap();
+        deterministic(rnn.parameters.iter_mut().collect());
+        rnn
+    }
+
+    fn sequences() -> Array3<f64> {
+        Array::from_shape_fn((2, 4, 2), |(n, t, f)| ((n * 8 + t * 2 + f) as f64).sin())
+    }
+
+    fn forward(rnn: &Recurrent<f64>, x: &Array3<f64>) -> ArrayD<f64> {
+        let input = x.clone().into_dyn();
+
+        forward_with(
+            rnn.parameters.iter().collect(),
+            &input,
+            Mode::Training,
+            |x, context| rnn.forward(x, context),
+        )
+        .output
+    }
+
+    fn assert_close(found: ArrayViewD<f64>, expected: ArrayViewD<f64>) {
+        assert_eq!(expected.shape(), found.shape());
+        assert!(
+            found
+                .iter()
+                .zip(&expected)
+                .all(|(a, b)| (a - b).abs() < 1e-12),
+            "{} != {}",
+            found,
+            expected
+        );
+    }
+
+    fn sigmoid(a: Array2<f64>) -> Array2<f64> {
+        a.mapv(|v| 1.0 / (1.0 + (-v).exp()))
+    }
+
+    /// Hidden states of a direction in the order of the time steps,
+    /// computed with loops over the time steps.
+    fn reference(
+        cell: Cell,
+        weights: &[ArrayD<f64>],
+        x: &Array3<f64>,
+        reverse: bool,
+    ) -> Vec<Array2<f64>> {
+        let w: Array2<f64> = weights[0].clone().into_dimensionality().unwrap();
+        let u: Array2<f64> = weights[1].clone().into_dimensionality().unwrap();
+        let b: Array1<f64> = weights[2].clone().into_dimensionality().unwrap();
+        let (batch, time, _) = x.dim();
+        let units = u.nrows();
+
+        let mut h = Array2::zeros((batch, units));
+        let mut c = Array2::<f64>::zeros((batch, units));
+        let mut outputs = vec![Array2::zeros((batch, units)); time];
+
+        for step in 0..time {
+            let t = if reverse { time - 1 - step } else { step };
+            let x = x.index_axis(Axis(1), t).dot(&w) + &b;
+
+            h = match cell {
+                Cell::Simple => (x + h.dot(&u)).mapv(f64::tanh),
+                Cell::Lstm => {
+                    let z = x + h.dot(&u);
+                    let gate = |k: usize| z.slice(s![.., k * units..(k + 1) * units]).to_owned();
+                    c = sigmoid(gate(1)) * &c + sigmoid(gate(0)) * gate(2).mapv(f64::tanh);
+                    sigmoid(gate(3)) * c.mapv(f64::tanh)
+                }
+                Cell::Gru => {
+                    let zr = sigmoid(
+                        x.slice(s![.., ..2 * units]).to_owned()
+                            + h.dot(&u.slice(s![.., ..2 * units])),
+                    );
+                    let z = zr.slice(s![.., ..units]).to_owned();
+                    let r = zr.slice(s![.., units..]).to_owned();
+                    let n = (x.slice(s![.., 2 * units..]).to_owned()
+                        + (r * &h).dot(&u.slice(s![.., 2 * units..])))
+                    .mapv(f64::tanh);
+                    &z * &h + (1.0 - z) * n
+                }
+            };
+            outputs[t] = h.clone();
+        }
+
+        outputs
+    }
+
+    #[test]
+    fn test_forward() {
+        let x = sequences();
+
+        for cell in CELLS {
+            let mut rnn = recurrent(cell, true);
+            let forwards = reference(cell, &rnn.parameters[..3], &x, false);
+            let backwards = reference(cell, &rnn.parameters[3..], &x, true);
+
+            let expected = concatenate![Axis(1), forwards[3], backwards[0]];
+            assert_close(forward(&rnn, &x).view(), expected.view().into_dyn());
+
+            rnn.return_sequences = true;
+            let output = forward(&rnn, &x);
+            for t in 0..4 {
+                let expected = concatenate![Axis(1), forwards[t], backwards[t]];
+                assert_close(output.index_axis(Axis(1), t), expected.view().into_dyn());
+            }
+        }
+    }
+
+    #[test]
+    fn test_return_state() {
+        let x = sequences();
+        let mut rnn = recurrent(Cell::Lstm, false);
+        rnn.return_state = true;
+        let hidden = reference(Cell::Lstm, &rnn.parameters, &x, false);
+
+        let output = forward(&rnn, &x);
+        assert_eq!(&[2, 3, 3], output.shape());
+        assert_close(output.index_axis(Axis(1), 0), hidden[3].view().into_dyn());
+        assert_eq!(output.index_axis(Axis(1), 0), output.index_axis(Axis(1), 1));
+
+        rnn.return_sequences = true;
+        let output = forward(&rnn, &x);
+        assert_eq!(&[2, 6, 3], output.shape());
+        assert_eq!(output.index_axis(Axis(1), 3), output.index_axis(Axis(1), 4));
+
+        let mut shapes = vec![];
+        let input = x.into_dyn();
+        let result = forward_with(
+            rnn.parameters.iter().collect(),
+            &input,
+            Mode::Training,
+            |x, context| {
+                let (output, states) = rnn.forward_with_state(x, context);
+                shapes = states.iter().map(|state| state.shape()).collect();
+                output
+            },
+        );
+
+        assert_eq!(&[2, 4, 3], result.output.shape());
+        assert_eq!(vec![vec![2, 3], vec![2, 3]], shapes);
+    }
+
+    #[test]
+    fn test_gradients() {
+        let x = sequences().slice(s![.., ..3, ..]).to_owned().into_dyn();
+        let weights = Array::linspace(-1.0, 1.0, 36)
+            .into_shape((2, 3, 6))
+            .unwrap();
+
+        for cell in CELLS {
+            let mut rnn = recurrent(cell, true);
+            rnn.return_sequences = true;
+            let mut inputs = vec![x.clone()];
+            inputs.extend(rnn.parameters.iter().cloned());
+
+            check(&inputs, |v| {
+                let tape = v[0].tape();
+                let mut rng = Rng::new(0);
+                let mut context = Context::new(Mode::Training, &v[1..], &mut rng);
+
+                rnn.forward(v[0], &mut context) * tape.constant(weights.clone())
+            });
+        }
+    }
+
+    #[test]
+    fn test_output_shape() {
+        let rnn: Lstm = Lstm::new(2, 3, Zeros).unwrap();
+        assert_eq!(vec![3], rnn.output_shape(&[5, 2]).unwrap());
+
+        let rnn = rnn.return_sequences(true);
+        assert_eq!(vec![5, 3], rnn.output_shape(&[5, 2]).unwrap());
+        assert_eq!(
+            vec![7, 3],
+            rnn.clone()
+                .return_state(true)
+                .output_shape(&[5, 2])
+                .unwrap()
+        );
+
+        let rnn: Gru = Gru::bidirectional(2, 3, Zeros).unwrap().return_state(true);
+        assert_eq!(vec![2, 6], rnn.output_shape(&[5, 2]).unwrap());
+
+        for input_shape in [&[5, 3][..], &[0, 2], &[2]] {
+            assert!(matches!(
+                rnn.output_shape(input_shape),
+                Err(Error::ShapeMismatch {
+                    name: "gru input",
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_invalid_hyperparameters() {
+        assert!(matches!(
+            SimpleRnn::<f64>::new(0, 3, Zeros),
+            Err(Error::InvalidHyperparameter {
+                name: "input_size",
+                ..
+            })
+        ));
+        assert!(matches!(
+            Lstm::<f64>::bidirectional(2, 0, Zeros),
+            Err(Error::InvalidHyperparameter { name: "units", .. })
+        ));
+    }
+
+    #[test]
+    fn test_from_config() {
+        let rnn: Lstm = Lstm::bidirectional(2, 3, Zeros)
+            .unwrap()
+            .return_sequences(true);
+        assert_eq!(vec![2.0, 3.0, 1.0, 1.0, 0.0], rnn.config());
+        assert_eq!(
+            Some(rnn.clone()),
+            Lstm::from_config(&rnn.config(), values(&rnn))
+        );
+        assert_eq!(
+            None,
+            Lstm::<f64>::from_config(&rnn.config(), values(&rnn) - 1)
+        );
+
+        let rnn: SimpleRnn = SimpleRnn::new(2, 3, Zeros).unwrap().return_state(true);
+        assert_eq!(
+            Some(rnn.clone()),
+            SimpleRnn::from_config(&rnn.config(), values(&rnn))
+        );
+        assert_eq!(
+            6,
+            Layer::<f64>::parameters(&Gru::bidirectional(2, 3, Zeros).unwrap()).len()
+        );
+        assert_eq!(
+            None,
+            Gru::<f64>::from_config(&[2.0, 3.0, 0.5, 0.0, 0.0], usize::MAX)
+        );
+        assert_eq!(
+            None,
+            Gru::<f64>::from_config(&[2.0, 3.0, 0.0, 0.0], usize::MAX)
+        );
+    }
+}
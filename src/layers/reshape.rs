@@ -0,0 +1,329 @@
+use ndarray::ScalarOperand;
+use num_traits::{Float, FromPrimitive};
+
+use crate::{autograd::Var, Error, Result};
+
+use super::{non_negative_integer, positive_integer, Context, Layer};
+
+/// Merges all axes of a sample into one, e.g. feature maps with the shape
+/// `[batch, channels, height, width]` into a matrix with the shape `[batch,
+/// channels * height * width]` for dense layers.
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::Array4;
+/// use robit::{
+///     initializers::RandomDistr,
+///     layers::{Conv2d, Flatten, MaxPool2d},
+///     Model,
+/// };
+///
+/// let mut model: Model = Model::new(0.01);
+/// model.add(Conv2d::new((1, 4), (3, 3), RandomDistr::normal()).unwrap());
+/// model.add(MaxPool2d::new((2, 2)));
+/// model.add(Flatten);
+/// model.add_layer((4 * 3 * 3, 10), RandomDistr::normal());
+///
+/// let images = Array4::zeros((2, 1, 8, 8));
+///
+/// assert_eq!(&[2, 10], model.predict(&images).shape());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Flatten;
+
+impl Flatten {
+    pub(crate) fn from_config(config: &[f64]) -> Option<Self> {
+        config.is_empty().then_some(Self)
+    }
+}
+
+impl<T> Layer<T> for Flatten
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "flatten"
+    }
+
+    fn config(&self) -> Vec<f64> {
+        vec![]
+    }
+
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        Ok(vec![input_shape.iter().product()])
+    }
+
+    fn forward<'t>(&self, input: Var<'t, T>, _: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        let shape = input.shape();
+
+        input.reshape(&[shape[0], shape[1..].iter().product()])
+    }
+}
+
+/// Changes the shape of every sample, keeping the order of the values.
+///
+/// # Examples
+///
+/// ```
+/// use robit::layers::{Layer, Reshape};
+///
+/// let reshape = Reshape::new(vec![1, 28, 28]);
+///
+/// assert_eq!(
+///     vec![1, 28, 28],
+///     Layer::<f64>::output_shape(&reshape, &[784]).unwrap()
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reshape {
+    shape: Vec<usize>,
+}
+
+impl Reshape {
+    /// Creates the layer with the shape of an output sample, without the
+    /// batch axis.
+    pub fn new(shape: Vec<usize>) -> Self {
+        Self { shape }
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub(crate) fn from_config(config: &[f64]) -> Option<Self> {
+        let shape = config
+            .iter()
+            .map(|&n| positive_integer(n))
+            .collect::<Option<_>>()?;
+
+        Some(Self::new(shape))
+    }
+}
+
+impl<T> Layer<T> for Reshape
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "reshape"
+    }
+
+    fn config(&self) -> Vec<f64> {
+        self.shape.iter().map(|&n| n as f64).collect()
+    }
+
+    /// Fails with an [Error::ShapeMismatch] if the number of values of a
+    /// sample differs from the target shape.
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        let n_values: usize = self.shape.iter().product();
+
+        if input_shape.iter().product::<usize>() != n_values {
+            return Err(Error::ShapeMismatch {
+                name: "reshape input",
+                expected: vec![n_values],
+                found: input_shape.to_vec(),
+            });
+        }
+
+        Ok(self.shape.clone())
+    }
+
+    fn forward<'t>(&self, input: Var<'t, T>, _: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        let mut shape = vec![input.shape()[0]];
+        shape.extend(&self.shape);
+
+        input.reshape(&shape)
+    }
+}
+
+/// Reorders the axes of every sample, axis `i` of an output sample is axis
+/// `axes[i]` of the input sample, without counting the batch axis.
+///
+/// # Examples
+///
+/// ```
+/// use robit::layers::{Layer, Permute};
+///
+/// // channels last to channels first
+/// let permute = Permute::new(vec![2, 0, 1]).unwrap();
+///
+/// assert_eq!(
+///     vec![3, 28, 28],
+///     Layer::<f64>::output_shape(&permute, &[28, 28, 3]).unwrap()
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Permute {
+    axes: Vec<usize>,
+}
+
+impl Permute {
+    /// Fails with an [Error::InvalidHyperparameter] if `axes` is not a
+    /// permutation of `0..axes.len()`.
+    pub fn new(axes: Vec<usize>) -> Result<Self> {
+        let mut sorted = axes.clone();
+        sorted.sort_unstable();
+
+        if !sorted.iter().copied().eq(0..axes.len()) {
+            return Err(Error::invalid_hyperparameter(
+                "axes",
+                format!("{:?} is not a permutation", axes),
+            ));
+        }
+
+        Ok(Self { axes })
+    }
+
+    pub fn axes(&self) -> &[usize] {
+        &self.axes
+    }
+
+    pub(crate) fn from_config(config: &[f64]) -> Option<Self> {
+        let axes = config
+            .iter()
+            .map(|&axis| non_negative_integer(axis))
+            .collect::<Option<_>>()?;
+
+        Self::new(axes).ok()
+    }
+}
+
+impl<T> Layer<T> for Permute
+where
+    T: Float + FromPrimitive + ScalarOperand + 'static,
+{
+    fn name(&self) -> &'static str {
+        "permute"
+    }
+
+    fn config(&self) -> Vec<f64> {
+        self.axes.iter().map(|&axis| axis as f64).collect()
+    }
+
+    /// Fails with an [Error::ShapeMismatch] if the number of axes of a sample
+    /// differs from the number of permuted axes.
+    fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+        if input_shape.len() != self.axes.len() {
+            let mut expected = input_shape.to_vec();
+            expected.resize(self.axes.len(), 1);
+
+            return Err(Error::ShapeMismatch {
+                name: "permute input",
+                expected,
+                found: input_shape.to_vec(),
+            });
+        }
+
+        Ok(self.axes.iter().map(|&axis| input_shape[axis]).collect())
+    }
+
+    fn forward<'t>(&self, input: Var<'t, T>, _: &mut Context<'_, 't, T>) -> Var<'t, T> {
+        let mut axes = vec![0];
+        axes.extend(self.axes.iter().map(|&axis| axis + 1));
+
+        input.permute(&axes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{Array, ArrayD};
+
+    use super::*;
+    use crate::{
+        autograd::check,
+        layers::{tests::forward, Mode},
+        training::Rng,
+    };
+
+    fn input() -> ArrayD<f64> {
+        Array::from_shape_fn((2, 3, 4, 5), |(n, c, y, x)| {
+            (n * 60 + c * 20 + y * 5 + x) as f64
+        })
+        .into_dyn()
+    }
+
+    #[test]
+    fn test_flatten() {
+        let y = forward(&Flatten, &input(), Mode::Training).output;
+
+        assert_eq!(&[2, 60], y.shape());
+        assert_eq!(Array::range(0.0, 120.0, 1.0).to_vec(), y.into_raw_vec());
+        assert_eq!(
+            vec![60],
+            Layer::<f64>::output_shape(&Flatten, &[3, 4, 5]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_reshape() {
+        let layer = Reshape::new(vec![12, 5]);
+        let y = forward(&layer, &input(), Mode::Training).output;
+
+        assert_eq!(&[2, 12, 5], y.shape());
+        assert_eq!(input().into_raw_vec(), y.into_raw_vec());
+        assert!(matches!(
+            Layer::<f64>::output_shape(&layer, &[4, 4]),
+            Err(Error::ShapeMismatch { expected, .. }) if expected == vec![60]
+        ));
+    }
+
+    #[test]
+    fn test_permute() {
+        let layer = Permute::new(vec![1, 2, 0]).unwrap();
+        let x = input();
+        let y = forward(&layer, &x, Mode::Training).output;
+
+        assert_eq!(&[2, 4, 5, 3], y.shape());
+        assert_eq!(x[[1, 2, 3, 4]], y[[1, 3, 4, 2]]);
+        assert_eq!(
+            vec![4, 5, 3],
+            Layer::<f64>::output_shape(&layer, &[3, 4, 5]).unwrap()
+        );
+        assert!(Layer::<f64>::output_shape(&layer, &[3, 4]).is_err());
+        assert!(matches!(
+            Permute::new(vec![0, 2]),
+            Err(Error::InvalidHyperparameter { name: "axes", .. })
+        ));
+        assert!(Permute::new(vec![1, 1]).is_err());
+    }
+
+    #[test]
+    fn test_gradients() {
+        let x = input().mapv(|v| (v * 0.1).sin());
+        let weights = Array::linspace(-1.0, 1.0, 120);
+
+        check(&[x], |v| {
+            let tape = v[0].tape();
+            let mut rng = Rng::new(0);
+            let mut context = Context::new(Mode::Training, &[], &mut rng);
+
+            let y = Permute::new(vec![2, 0, 1])
+                .unwrap()
+                .forward(v[0], &mut context);
+            let y = Reshape::new(vec![4, 15]).forward(y, &mut context);
+            let y = Flatten.forward(y, &mut context);
+
+            y * tape.constant(weights.clone().into_shape((2, 60)).unwrap())
+        });
+    }
+
+    #[test]
+    fn test_from_config() {
+        let reshape = Reshape::new(vec![1, 28, 28]);
+        let permute = Permute::new(vec![2, 0, 1]).unwrap();
+
+        assert_eq!(
+            Some(reshape.clone()),
+            Reshape::from_config(&Layer::<f64>::config(&reshape))
+        );
+        assert_eq!(
+            Some(permute.clone()),
+            Permute::from_config(&Layer::<f64>::config(&permute))
+        );
+        assert_eq!(Some(Flatten), Flatten::from_config(&[]));
+        assert_eq!(None, Reshape::from_config(&[0.0, 2.0]));
+        assert_eq!(None, Permute::from_config(&[0.0, 0.0]));
+    }
+}
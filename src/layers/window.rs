@@ -0,0 +1,165 @@
+use ndarray::ArrayD;
+use num_traits::Float;
+
+use crate::{autograd::Var, Error, Result};
+
+/// Kernel size, stride, padding and dilation of a window that slides over
+/// the height and width of images with the shape `[batch, channels, height,
+/// width]`, shared by convolution and pooling layers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct Window {
+    pub(super) kernel: (usize, usize),
+    pub(super) stride: (usize, usize),
+    pub(super) padding: (usize, usize),
+    pub(super) dilation: (usize, usize),
+}
+
+impl Window {
+    /// Fails with an [Error::InvalidHyperparameter] if the kernel size,
+    /// stride or dilation is zero.
+    pub(super) fn check(&self) -> Result<()> {
+        if self.kernel.0 == 0 || self.kernel.1 == 0 {
+            return Err(Error::invalid_hyperparameter(
+                "kernel_size",
+                "must be positive",
+            ));
+        }
+        if self.stride.0 == 0 || self.stride.1 == 0 {
+            return Err(Error::invalid_hyperparameter("stride", "must be positive"));
+        }
+        if self.dilation.0 == 0 || self.dilation.1 == 0 {
+            return Err(Error::invalid_hyperparameter(
+                "dilation",
+                "must be positive",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Height and width of the dilated kernel.
+    pub(super) fn extent(&self) -> (usize, usize) {
+        (
+            self.dilation.0 * (self.kernel.0 - 1) + 1,
+            self.dilation.1 * (self.kernel.1 - 1) + 1,
+        )
+    }
+
+    /// Smallest height and width of an image that contains a window.
+    pub(super) fn min_input_size(&self) -> (usize, usize) {
+        let (height, width) = self.extent();
+
+        (
+            height.saturating_sub(2 * self.padding.0).max(1),
+            width.saturating_sub(2 * self.padding.1).max(1),
+        )
+    }
+
+    /// Number of windows along the height and width of an image, `None` if
+    /// the padded image is smaller than the window.
+    pub(super) fn output_size(&self, (height, width): (usize, usize)) -> Option<(usize, usize)> {
+        let (kernel_height, kernel_width) = self.extent();
+        let height = (height + 2 * self.padding.0).checked_sub(kernel_height)?;
+        let width = (width + 2 * self.padding.1).checked_sub(kernel_width)?;
+
+        Some((height / self.stride.0 + 1, width / self.stride.1 + 1))
+    }
+
+    /// Calls `f` with the index of every value of the im2col matrix, see
+    /// [Window::im2col], and the index of the image value it is copied from,
+    /// skipping the padding.
+    fn for_each_index(&self, shape: &[usize], groups: usize, mut f: impl FnMut(usize, usize)) {
+        let [batch, channels, height, width] = *shape else {
+            unreachable!("images have four axes");
+        };
+        let (out_height, out_width) = self.output_size((height, width)).unwrap();
+        let group_channels = channels / groups;
+        let mut column = 0;
+
+        for g in 0..groups {
+            for n in 0..batch {
+                for i in 0..out_height {
+                    for j in 0..out_width {
+                        for c in g * group_channels..(g + 1) * group_channels {
+                            for ki in 0..self.kernel.0 {
+                                let y = (i * self.stride.0 + ki * self.dilation.0)
+                                    .checked_sub(self.padding.0)
+                                    .filter(|&y| y < height);
+
+                                for kj in 0..self.kernel.1 {
+                                    let x = (j * self.stride.1 + kj * self.dilation.1)
+                                        .checked_sub(self.padding.1)
+                                        .filter(|&x| x < width);
+
+                                    if let (Some(y), Some(x)) = (y, x) {
+                                        f(column, ((n * channels + c) * height + y) * width + x);
+                                    }
+
+                                    column += 1;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Records the im2col matrix of the images on the tape, see
+    /// [Window::im2col].
+    pub(super) fn columns<'t, T>(&self, images: Var<'t, T>, groups: usize, fill: T) -> Var<'t, T>
+    where
+        T: Float + 'static,
+    {
+        let window = *self;
+        let columns = images.with_value(|x| window.im2col(x, groups, fill));
+
+        images
+            .tape()
+            .op(&[images], columns, move |grad, inputs, _| {
+                vec![window.col2im(grad, inputs[0].shape(), groups)]
+            })
+    }
+
+    /// Copies every window of the images into a row of a matrix per group,
+    /// with the shape `[groups, batch * out_height * out_width,
+    /// channels / groups * kernel_height * kernel_width]`. The padding is
+    /// filled with `fill`.
+    fn im2col<T: Float>(&self, images: &ArrayD<T>, groups: usize, fill: T) -> ArrayD<T> {
+        let [batch, channels, height, width] = *images.shape() else {
+            unreachable!("images have four axes");
+        };
+        let (out_height, out_width) = self.output_size((height, width)).unwrap();
+        let shape = vec![
+            groups,
+            batch * out_height * out_width,
+            channels / groups * self.kernel.0 * self.kernel.1,
+        ];
+
+        let mut columns = ArrayD::from_elem(shape, fill);
+        let values = columns.as_slice_mut().unwrap();
+        let pixels = images.as_standard_layout();
+        let pixels = pixels.as_slice().unwrap();
+
+        self.for_each_index(images.shape(), groups, |column, pixel| {
+            values[column] = pixels[pixel];
+        });
+
+        columns
+    }
+
+    /// Sums the rows of im2col matrices back into images of the given shape,
+    /// the inverse of [Window::im2col] for overlapping windows.
+    fn col2im<T: Float>(&self, columns: &ArrayD<T>, shape: &[usize], groups: usize) -> ArrayD<T> {
+        let columns = columns.as_standard_layout();
+        let columns = columns.as_slice().unwrap();
+        let mut images = ArrayD::zeros(shape);
+        let values = images.as_slice_mut().unwrap();
+
+        self.for_each_index(shape, groups, |column, pixel| {
+            values[pixel] = values[pixel] + columns[column];
+        });
+
+        images
+    }
+}
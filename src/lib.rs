@@ -1,9 +1,21 @@
 pub mod activations;
+pub mod autograd;
+pub mod callbacks;
 pub mod initializers;
-// pub mod layers;
+pub mod layers;
 pub mod losses;
-// pub mod optimizers;
+pub mod metrics;
+pub mod optimizers;
+pub mod serialization;
 
+mod error;
+mod gradcheck;
 mod model;
+mod summary;
+mod training;
 
+pub use error::{Error, Result};
+pub use gradcheck::{gradcheck, GradCheck, LayerGradCheck};
 pub use model::Model;
+pub use summary::{LayerSummary, Summary};
+pub use training::{FitConfig, History};
@@ -1,8 +1,18 @@
 mod mse;
 
 pub use mse::MeanSquaredError;
-use ndarray::{Array1, Array2, ArrayBase, Ix1, Ix2, ViewRepr};
+use ndarray::{Array2, ArrayBase, Ix1, Ix2, ViewRepr};
+
+use crate::autograd::Var;
 
 pub trait Loss<T> {
-    fn get(&self, y_true: &Array2<T>, y_pred: &Array2<T>) -> Array1<T>;
+    /// Identifier of the loss function in saved models.
+    fn name(&self) -> &'static str;
+
+    /// Returns the loss of the predictions, averaged over the batch.
+    fn compute(&self, y_true: &Array2<T>, y_pred: &Array2<T>) -> T;
+
+    /// Records the loss on the tape of the predictions, like
+    /// [Loss::compute] but differentiable, see [autograd](crate::autograd).
+    fn forward<'t>(&self, y_true: Var<'t, T>, y_pred: Var<'t, T>) -> Var<'t, T>;
 }
@@ -1,25 +1,29 @@
-use std::{
-    iter::Sum,
-    ops::{Div, Mul, Sub}, fmt::Debug,
-};
+use std::iter::Sum;
 
-use ndarray::{Array, Array1, Array2, ArrayBase, Ix1, Ix2, ViewRepr, ScalarOperand};
-use num_traits::{FromPrimitive, One, Pow, Zero};
+use ndarray::{Array, Array2, ArrayBase, Ix1, Ix2, ScalarOperand, ViewRepr};
+use num_traits::{Float, FromPrimitive, One, Pow, Zero};
 
 use super::Loss;
+use crate::autograd::Var;
 
 pub struct MeanSquaredError;
 
 impl<T> Loss<T> for MeanSquaredError
 where
-     T: Zero + Clone + Copy + Div<Output = T> + Mul<T, Output = T> + Sub<Output = T> + From<u16> + FromPrimitive + ScalarOperand + Debug,
+    T: Float + FromPrimitive + ScalarOperand,
 {
-    #[inline]
-    fn get(&self, y_true: &Array2<T>, y_pred: &Array2<T>) -> Array1<T> {
+    fn name(&self) -> &'static str {
+        "mean_squared_error"
+    }
+
+    fn compute(&self, y_true: &Array2<T>, y_pred: &Array2<T>) -> T {
         let diff = y_pred - y_true;
 
-        // diff.mapv(|x| x * x).mean_axis(ndarray::Axis(0)).unwrap() / (T::from(y_pred.shape()[1].to_owned() as f64))
-        diff.sum_axis(ndarray::Axis(0)) / (T::from(y_pred.shape()[1].to_owned() as u16))
+        diff.mapv(|x| x * x).sum() / T::from_usize(diff.len()).unwrap()
+    }
+
+    fn forward<'t>(&self, y_true: Var<'t, T>, y_pred: Var<'t, T>) -> Var<'t, T> {
+        (y_pred - y_true).square().mean()
     }
 }
 
@@ -32,6 +36,7 @@ impl Default for MeanSquaredError {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::autograd::Tape;
     use rust_decimal_macros::dec;
 
     #[test]
@@ -63,4 +68,30 @@ mod tests {
 
         // assert_eq!(dec!(0.125), mse.get(&y_true, &y_pred));
     }
+
+    #[test]
+    fn test_compute() {
+        let y_true = ndarray::arr2(&[[0.6f64, 0.3], [1.0, 0.0]]);
+        let y_pred = ndarray::arr2(&[[0.3, 0.1], [1.0, 0.0]]);
+
+        let mse = MeanSquaredError::default();
+
+        assert!((0.0325 - mse.compute(&y_true, &y_pred)).abs() < 1e-12);
+        assert_eq!(0.0, mse.compute(&y_true, &y_true));
+    }
+
+    #[test]
+    fn test_forward() {
+        let tape = Tape::new();
+        let y_true = tape.constant(ndarray::arr2(&[[0.6f64, 0.3], [1.0, 0.0]]));
+        let y_pred = tape.var(ndarray::arr2(&[[0.3, 0.1], [1.0, 0.5]]));
+
+        let loss = MeanSquaredError.forward(y_true, y_pred);
+        let gradient = &tape.backward(loss)[y_pred];
+
+        assert!((0.095 - loss.value()[[]]).abs() < 1e-12);
+        assert!((gradient[[0, 0]] - -0.15).abs() < 1e-12);
+        assert!((gradient[[1, 1]] - 0.25).abs() < 1e-12);
+        assert_eq!(0.0, gradient[[1, 0]]);
+    }
 }
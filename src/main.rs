@@ -1,4 +1,4 @@
-use std::{marker::PhantomData, ops::SubAssign, time::Instant};
+use std::{error::Error, marker::PhantomData, ops::SubAssign, time::Instant};
 
 use itertools::Itertools;
 use mnist::*;
@@ -10,12 +10,14 @@ use num_traits::One;
 use rand_distr::{Normal, StandardNormal};
 use robit::{
     activations::Relu,
+    callbacks::{CallbackList, EarlyStopping, Logger},
     initializers::RandomDistr,
     losses::{Loss, MeanSquaredError},
-    Model,
+    metrics::{Accuracy, ConfusionMatrix},
+    FitConfig, Model,
 };
 
-fn main() {
+fn main() -> Result<(), Box<dyn Error>> {
     type T = f32;
 
     let Mnist {
@@ -42,12 +44,9 @@ fn main() {
             })
             .flatten()
             .collect::<Vec<T>>(),
-    )
-    .expect("Error converting labels to Array2 struct");
+    )?;
 
-    let train_data = Array2::from_shape_vec((50_000, 784), trn_img)
-        .expect("Error converting images to Array2 struct")
-        .map(|x| *x as T / 256.0);
+    let train_data = Array2::from_shape_vec((50_000, 784), trn_img)?.map(|x| *x as T / 256.0);
 
     let test_labels = Array2::from_shape_vec(
         (10_000, 10),
@@ -60,12 +59,9 @@ fn main() {
             })
             .flatten()
             .collect::<Vec<T>>(),
-    )
-    .expect("Error converting labels to Array2 struct");
+    )?;
     
-    let test_data = Array2::from_shape_vec((10_000, 784), tst_img)
-        .expect("Error converting images to Array2 struct")
-        .map(|x| *x as T / 256.0);
+    let test_data = Array2::from_shape_vec((10_000, 784), tst_img)?.map(|x| *x as T / 256.0);
 
     // println!("{:#.1?}\n",train_data.slice(s![image_num, .., ..]));
 
@@ -73,12 +69,14 @@ fn main() {
 
     type Init = RandomDistr<Normal<T>, T>;
 
-    let mut model: Model<Relu, MeanSquaredError, T> = Model::new(0.00015);
+    let mut model: Model<Relu, MeanSquaredError, T> = Model::new(0.05);
 
     // model.add_layer((784, 28), Init::default());
-    model.add_layer((784, 10), Init::normal_with(0.0, 0.05).unwrap());
+    model.try_add_layer((784, 10), Init::normal_with(0.0, 0.05)?)?;
     // model.add_layer((28, 10), Init::default());
 
+    model.summary();
+
     // let image_num = 0;
     // let x_example: Array2<f32> = train_data
     //     .slice(s![image_num..(image_num + 1), ..])
@@ -89,18 +87,29 @@ fn main() {
 
     let now = Instant::now();
 
-    let mse = MeanSquaredError::default();
+    let mut callbacks = CallbackList::new();
+    callbacks.push(Logger::new());
+    callbacks.push(EarlyStopping::new("val_loss", 10).restore_best_weights(true));
+
+    model.try_fit_with_callbacks(
+        &train_data,
+        &train_labels,
+        FitConfig {
+            epochs: 200,
+            validation_data: Some((test_data.clone(), test_labels.clone())),
+            metrics: vec![Box::new(Accuracy)],
+            ..Default::default()
+        },
+        &mut callbacks,
+    )?;
 
-    for epoch in 0..200 {
-        model.fit(&train_data, &train_labels);
+    println!("duration = {:?}", now.elapsed());
 
-        let test_predict = model.predict(&test_data);
-        let loss = mse.get(&test_labels, &test_predict);
+    let confusion_matrix =
+        ConfusionMatrix::from_predictions(&test_labels, &model.try_predict(&test_data)?);
 
-        println!("[Epoch:{}] loss = {:?}", epoch, loss.mean().unwrap());
-    }
+    println!("{}", confusion_matrix);
+    println!("{}", confusion_matrix.report());
 
-    println!("duration = {:?}", now.elapsed());
-
-    
+    Ok(())
 }
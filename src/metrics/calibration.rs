@@ -0,0 +1,203 @@
+use ndarray::Array2;
+use num_traits::{Float, FromPrimitive};
+
+use super::to_labels;
+
+/// Samples whose confidence falls into `[lower, upper)` of a reliability
+/// diagram. The last bin includes its upper edge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReliabilityBin<T = f64> {
+    pub lower: T,
+    pub upper: T,
+
+    /// Number of samples in the bin.
+    pub count: usize,
+
+    /// Mean predicted confidence of the samples, `0` for an empty bin.
+    pub confidence: T,
+
+    /// Fraction of the samples that were predicted correctly, `0` for an
+    /// empty bin.
+    pub accuracy: T,
+}
+
+/// Groups the predictions into `n_bins` equally wide bins by their
+/// confidence.
+///
+/// For a classifier with a single output, the confidence is the probability
+/// of the positive class and the accuracy is the fraction of positive
+/// samples. With multiple outputs the confidence is the highest predicted
+/// probability and the accuracy the fraction of samples whose class is
+/// predicted correctly.
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::arr2;
+/// use robit::metrics::reliability_bins;
+///
+/// let y_true = arr2(&[[1.0, 0.0], [0.0, 1.0]]);
+/// let y_pred = arr2(&[[0.9, 0.1], [0.7, 0.3]]);
+///
+/// let bins = reliability_bins(&y_true, &y_pred, 10);
+///
+/// assert_eq!(1, bins[7].count);
+/// assert_eq!(0.0, bins[7].accuracy);
+/// ```
+///
+/// # Panics
+///
+/// Panics if the shapes of `y_true` and `y_pred` differ or `n_bins` is zero.
+pub fn reliability_bins<T: Float + FromPrimitive>(
+    y_true: &Array2<T>,
+    y_pred: &Array2<T>,
+    n_bins: usize,
+) -> Vec<ReliabilityBin<T>> {
+    assert_eq!(y_true.dim(), y_pred.dim(), "shapes differ");
+    assert!(n_bins > 0, "n_bins must be positive");
+
+    let n = T::from_usize(n_bins).unwrap();
+    let mut bins: Vec<ReliabilityBin<T>> = (0..n_bins)
+        .map(|i| ReliabilityBin {
+            lower: T::from_usize(i).unwrap() / n,
+            upper: T::from_usize(i + 1).unwrap() / n,
+            count: 0,
+            confidence: T::zero(),
+            accuracy: T::zero(),
+        })
+        .collect();
+
+    for (confidence, correct) in confidences(y_true, y_pred) {
+        let i = (confidence * n)
+            .floor()
+            .to_usize()
+            .unwrap_or(0)
+            .min(n_bins - 1);
+        let bin = &mut bins[i];
+
+        bin.count += 1;
+        bin.confidence = bin.confidence + confidence;
+        bin.accuracy = bin.accuracy + correct;
+    }
+
+    for bin in bins.iter_mut().filter(|bin| bin.count > 0) {
+        let count = T::from_usize(bin.count).unwrap();
+
+        bin.confidence = bin.confidence / count;
+        bin.accuracy = bin.accuracy / count;
+    }
+
+    bins
+}
+
+/// Expected calibration error, `Σ (n_b / n) |accuracy_b - confidence_b|`
+/// over the [reliability_bins].
+///
+/// # Panics
+///
+/// Panics if the shapes of `y_true` and `y_pred` differ or `n_bins` is zero.
+pub fn expected_calibration_error<T: Float + FromPrimitive>(
+    y_true: &Array2<T>,
+    y_pred: &Array2<T>,
+    n_bins: usize,
+) -> T {
+    let n_samples = T::from_usize(y_true.nrows().max(1)).unwrap();
+
+    reliability_bins(y_true, y_pred, n_bins)
+        .into_iter()
+        .fold(T::zero(), |ece, bin| {
+            let weight = T::from_usize(bin.count).unwrap() / n_samples;
+
+            ece + weight * (bin.accuracy - bin.confidence).abs()
+        })
+}
+
+/// Returns the confidence of every prediction and whether it was correct, as
+/// `0` or `1`.
+fn confidences<T: Float>(y_true: &Array2<T>, y_pred: &Array2<T>) -> Vec<(T, T)> {
+    let as_float = |correct: bool| if correct { T::one() } else { T::zero() };
+
+    if y_pred.ncols() == 1 {
+        let threshold = T::from(0.5).unwrap();
+
+        return y_pred
+            .column(0)
+            .iter()
+            .zip(y_true.column(0))
+            .map(|(p, y)| (*p, as_float(*y >= threshold)))
+            .collect();
+    }
+
+    y_pred
+        .rows()
+        .into_iter()
+        .zip(to_labels(y_true).into_iter().zip(to_labels(y_pred)))
+        .map(|(row, (t, p))| (row[p], as_float(t == p)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::arr2;
+
+    use super::*;
+    use crate::metrics::tests::assert_close;
+
+    #[test]
+    fn test_reliability_bins_binary() {
+        let y_true = arr2(&[[1.0], [0.0], [1.0], [1.0]]);
+        let y_pred = arr2(&[[0.9], [0.8], [0.2], [1.0]]);
+
+        let bins = reliability_bins(&y_true, &y_pred, 2);
+
+        assert_eq!(1, bins[0].count);
+        assert_close(0.2, bins[0].confidence);
+        assert_close(1.0, bins[0].accuracy);
+        assert_eq!(3, bins[1].count);
+        assert_close(0.9, bins[1].confidence);
+        assert_close(2.0 / 3.0, bins[1].accuracy);
+    }
+
+    #[test]
+    fn test_expected_calibration_error() {
+        let y_true = arr2(&[[1.0], [0.0], [1.0], [1.0]]);
+        let y_pred = arr2(&[[0.9], [0.8], [0.2], [1.0]]);
+
+        // 1/4 * |1 - 0.2| + 3/4 * |2/3 - 0.9|
+        assert_close(
+            0.25 * 0.8 + 0.75 * (0.9 - 2.0 / 3.0),
+            expected_calibration_error(&y_true, &y_pred, 2),
+        );
+    }
+
+    #[test]
+    fn test_perfectly_calibrated() {
+        let y_true = arr2(&[[1.0, 0.0], [0.0, 1.0]]);
+
+        assert_close(0.0, expected_calibration_error(&y_true, &y_true, 10));
+    }
+
+    #[test]
+    fn test_top_label_confidence() {
+        let y_true = arr2(&[[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]);
+        let y_pred = arr2(&[[0.6, 0.3, 0.1], [0.7, 0.2, 0.1]]);
+
+        let bins = reliability_bins(&y_true, &y_pred, 5);
+
+        assert_eq!(2, bins[3].count);
+        assert_close(0.65, bins[3].confidence);
+        assert_close(0.5, bins[3].accuracy);
+    }
+
+    #[test]
+    #[should_panic(expected = "shapes differ")]
+    fn test_reliability_bins_shape_mismatch() {
+        reliability_bins(&arr2(&[[1.0], [0.0]]), &arr2(&[[0.9], [0.8], [0.2]]), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "shapes differ")]
+    fn test_expected_calibration_error_shape_mismatch() {
+        expected_calibration_error(&arr2(&[[1.0], [0.0], [1.0]]), &arr2(&[[0.9], [0.8]]), 10);
+    }
+}
@@ -0,0 +1,479 @@
+use std::cmp::Ordering;
+
+use ndarray::{Array2, ArrayView1};
+use num_traits::{Float, FromPrimitive};
+
+use super::{ratio, ConfusionMatrix, Metric};
+
+/// How the per-class scores of a multi-class metric are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Average {
+    /// Unweighted mean of the per-class scores.
+    #[default]
+    Macro,
+
+    /// Score of the summed true positives, false positives and false
+    /// negatives of all classes.
+    Micro,
+
+    /// Mean of the per-class scores weighted by the number of samples of each
+    /// class.
+    Weighted,
+}
+
+impl Average {
+    fn suffix(&self) -> &'static str {
+        match self {
+            Average::Macro => "macro",
+            Average::Micro => "micro",
+            Average::Weighted => "weighted",
+        }
+    }
+}
+
+/// Converts one-hot encoded labels or predicted probabilities to class
+/// indices.
+///
+/// Rows with multiple columns are mapped to the index of their maximum. A
+/// single column is treated as the probability of the positive class of a
+/// binary problem and thresholded at `0.5`.
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::arr2;
+/// use robit::metrics::to_labels;
+///
+/// assert_eq!(vec![1, 0], to_labels(&arr2(&[[0.1, 0.9], [0.7, 0.3]])));
+/// assert_eq!(vec![1, 0], to_labels(&arr2(&[[0.6], [0.2]])));
+/// ```
+pub fn to_labels<T: Float>(y: &Array2<T>) -> Vec<usize> {
+    if y.ncols() == 1 {
+        let threshold = T::from(0.5).unwrap();
+
+        return y
+            .column(0)
+            .iter()
+            .map(|p| (*p >= threshold) as usize)
+            .collect();
+    }
+
+    y.rows().into_iter().map(argmax).collect()
+}
+
+fn argmax<T: Float>(row: ArrayView1<T>) -> usize {
+    row.iter()
+        .enumerate()
+        .fold((0, T::neg_infinity()), |(best, max), (i, x)| {
+            match x.partial_cmp(&max) {
+                Some(Ordering::Greater) => (i, *x),
+                _ => (best, max),
+            }
+        })
+        .0
+}
+
+/// Fraction of samples whose predicted class matches the true class.
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::arr2;
+/// use robit::metrics::accuracy;
+///
+/// let y_true = arr2(&[[1.0, 0.0], [0.0, 1.0]]);
+/// let y_pred = arr2(&[[0.8, 0.2], [0.6, 0.4]]);
+///
+/// assert_eq!(0.5, accuracy(&y_true, &y_pred));
+/// ```
+///
+/// # Panics
+///
+/// Panics if the shapes of `y_true` and `y_pred` differ.
+pub fn accuracy<T: Float + FromPrimitive>(y_true: &Array2<T>, y_pred: &Array2<T>) -> T {
+    assert_eq!(y_true.dim(), y_pred.dim(), "shapes differ");
+
+    let correct = to_labels(y_true)
+        .into_iter()
+        .zip(to_labels(y_pred))
+        .filter(|(t, p)| t == p)
+        .count();
+
+    ratio(correct, y_true.nrows())
+}
+
+/// Fraction of samples whose true class is among the `k` classes with the
+/// highest predicted probability.
+///
+/// A single column is treated as the probability of the positive class of a
+/// binary problem, like in [to_labels].
+///
+/// # Panics
+///
+/// Panics if the shapes of `y_true` and `y_pred` differ.
+pub fn top_k_accuracy<T: Float + FromPrimitive>(
+    y_true: &Array2<T>,
+    y_pred: &Array2<T>,
+    k: usize,
+) -> T {
+    assert_eq!(y_true.dim(), y_pred.dim(), "shapes differ");
+
+    let correct = to_labels(y_true)
+        .into_iter()
+        .zip(y_pred.rows())
+        .filter(|(label, row)| {
+            let n_greater = if row.len() == 1 {
+                let p = row[0];
+                let (own, other) = if *label == 1 {
+                    (p, T::one() - p)
+                } else {
+                    (T::one() - p, p)
+                };
+
+                (other > own) as usize
+            } else {
+                let p = row[*label];
+
+                row.iter().filter(|x| **x > p).count()
+            };
+
+            n_greater < k
+        })
+        .count();
+
+    ratio(correct, y_true.nrows())
+}
+
+/// Precision, `tp / (tp + fp)`, of the predicted classes.
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::arr2;
+/// use robit::metrics::{precision, Average};
+///
+/// let y_true = arr2(&[[1.0, 0.0], [0.0, 1.0], [0.0, 1.0]]);
+/// let y_pred = arr2(&[[0.9, 0.1], [0.8, 0.2], [0.1, 0.9]]);
+///
+/// assert_eq!(0.75, precision(&y_true, &y_pred, Average::Macro));
+/// ```
+///
+/// # Panics
+///
+/// Panics if the shapes of `y_true` and `y_pred` differ.
+pub fn precision<T: Float + FromPrimitive>(
+    y_true: &Array2<T>,
+    y_pred: &Array2<T>,
+    average: Average,
+) -> T {
+    Counts::new(y_true, y_pred).average(average, |c| ratio(c.tp, c.tp + c.fp))
+}
+
+/// Recall, `tp / (tp + fn)`, of the predicted classes.
+///
+/// # Panics
+///
+/// Panics if the shapes of `y_true` and `y_pred` differ.
+pub fn recall<T: Float + FromPrimitive>(
+    y_true: &Array2<T>,
+    y_pred: &Array2<T>,
+    average: Average,
+) -> T {
+    Counts::new(y_true, y_pred).average(average, |c| ratio(c.tp, c.tp + c.fn_))
+}
+
+/// Harmonic mean of precision and recall, `2tp / (2tp + fp + fn)`.
+///
+/// # Panics
+///
+/// Panics if the shapes of `y_true` and `y_pred` differ.
+pub fn f1_score<T: Float + FromPrimitive>(
+    y_true: &Array2<T>,
+    y_pred: &Array2<T>,
+    average: Average,
+) -> T {
+    Counts::new(y_true, y_pred).average(average, |c| ratio(2 * c.tp, 2 * c.tp + c.fp + c.fn_))
+}
+
+/// True positives, false positives and false negatives of a class.
+#[derive(Debug, Clone, Copy, Default)]
+struct ClassCounts {
+    tp: usize,
+    fp: usize,
+    fn_: usize,
+}
+
+impl ClassCounts {
+    fn support(&self) -> usize {
+        self.tp + self.fn_
+    }
+}
+
+struct Counts(Vec<ClassCounts>);
+
+impl Counts {
+    fn new<T: Float>(y_true: &Array2<T>, y_pred: &Array2<T>) -> Self {
+        let matrix = ConfusionMatrix::from_predictions(y_true, y_pred);
+
+        Self(
+            (0..matrix.n_classes())
+                .map(|c| ClassCounts {
+                    tp: matrix.true_positives(c),
+                    fp: matrix.false_positives(c),
+                    fn_: matrix.false_negatives(c),
+                })
+                .collect(),
+        )
+    }
+
+    fn average<T, F>(&self, average: Average, score: F) -> T
+    where
+        T: Float + FromPrimitive,
+        F: Fn(&ClassCounts) -> T,
+    {
+        match average {
+            Average::Macro => {
+                let sum = self.0.iter().map(&score).fold(T::zero(), |a, b| a + b);
+
+                sum / T::from_usize(self.0.len()).unwrap()
+            }
+            Average::Micro => {
+                let total = self
+                    .0
+                    .iter()
+                    .fold(ClassCounts::default(), |a, c| ClassCounts {
+                        tp: a.tp + c.tp,
+                        fp: a.fp + c.fp,
+                        fn_: a.fn_ + c.fn_,
+                    });
+
+                score(&total)
+            }
+            Average::Weighted => {
+                let n_samples: usize = self.0.iter().map(ClassCounts::support).sum();
+                let sum = self.0.iter().fold(T::zero(), |a, c| {
+                    a + score(c) * T::from_usize(c.support()).unwrap()
+                });
+
+                if n_samples == 0 {
+                    return T::zero();
+                }
+
+                sum / T::from_usize(n_samples).unwrap()
+            }
+        }
+    }
+}
+
+/// Tracks the [accuracy] during training.
+pub struct Accuracy;
+
+impl<T: Float + FromPrimitive> Metric<T> for Accuracy {
+    fn name(&self) -> String {
+        "accuracy".to_owned()
+    }
+
+    fn compute(&self, y_true: &Array2<T>, y_pred: &Array2<T>) -> T {
+        accuracy(y_true, y_pred)
+    }
+}
+
+/// Tracks the [top_k_accuracy] during training.
+pub struct TopKAccuracy {
+    pub k: usize,
+}
+
+impl<T: Float + FromPrimitive> Metric<T> for TopKAccuracy {
+    fn name(&self) -> String {
+        format!("top_{}_accuracy", self.k)
+    }
+
+    fn compute(&self, y_true: &Array2<T>, y_pred: &Array2<T>) -> T {
+        top_k_accuracy(y_true, y_pred, self.k)
+    }
+}
+
+/// Tracks the [precision] during training.
+pub struct Precision(pub Average);
+
+impl<T: Float + FromPrimitive> Metric<T> for Precision {
+    fn name(&self) -> String {
+        format!("precision_{}", self.0.suffix())
+    }
+
+    fn compute(&self, y_true: &Array2<T>, y_pred: &Array2<T>) -> T {
+        precision(y_true, y_pred, self.0)
+    }
+}
+
+/// Tracks the [recall] during training.
+pub struct Recall(pub Average);
+
+impl<T: Float + FromPrimitive> Metric<T> for Recall {
+    fn name(&self) -> String {
+        format!("recall_{}", self.0.suffix())
+    }
+
+    fn compute(&self, y_true: &Array2<T>, y_pred: &Array2<T>) -> T {
+        recall(y_true, y_pred, self.0)
+    }
+}
+
+/// Tracks the [f1_score] during training.
+pub struct F1Score(pub Average);
+
+impl<T: Float + FromPrimitive> Metric<T> for F1Score {
+    fn name(&self) -> String {
+        format!("f1_{}", self.0.suffix())
+    }
+
+    fn compute(&self, y_true: &Array2<T>, y_pred: &Array2<T>) -> T {
+        f1_score(y_true, y_pred, self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, s};
+
+    use super::*;
+    use crate::metrics::tests::assert_close;
+
+    /// Labels `[0, 0, 1, 1, 2, 2]`, predictions `[0, 1, 1, 1, 2, 0]`.
+    fn data() -> (Array2<f64>, Array2<f64>) {
+        let y_true = arr2(&[
+            [1.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [0.0, 0.0, 1.0],
+        ]);
+        let y_pred = arr2(&[
+            [0.7, 0.2, 0.1],
+            [0.3, 0.6, 0.1],
+            [0.1, 0.8, 0.1],
+            [0.2, 0.5, 0.3],
+            [0.1, 0.1, 0.8],
+            [0.5, 0.1, 0.4],
+        ]);
+
+        (y_true, y_pred)
+    }
+
+    #[test]
+    fn test_to_labels() {
+        let (y_true, y_pred) = data();
+
+        assert_eq!(vec![0, 0, 1, 1, 2, 2], to_labels(&y_true));
+        assert_eq!(vec![0, 1, 1, 1, 2, 0], to_labels(&y_pred));
+    }
+
+    #[test]
+    fn test_accuracy() {
+        let (y_true, y_pred) = data();
+
+        assert_close(4.0 / 6.0, accuracy(&y_true, &y_pred));
+        assert_close(1.0, accuracy(&y_true, &y_true));
+    }
+
+    #[test]
+    fn test_top_k_accuracy() {
+        let (y_true, y_pred) = data();
+
+        assert_close(4.0 / 6.0, top_k_accuracy(&y_true, &y_pred, 1));
+        assert_close(1.0, top_k_accuracy(&y_true, &y_pred, 2));
+
+        let y_true = arr2(&[[1.0], [0.0], [1.0]]);
+        let y_pred = arr2(&[[0.8], [0.3], [0.4]]);
+        assert_close(2.0 / 3.0, top_k_accuracy(&y_true, &y_pred, 1));
+        assert_close(1.0, top_k_accuracy(&y_true, &y_pred, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "shapes differ")]
+    fn test_top_k_accuracy_shape_mismatch() {
+        let (y_true, y_pred) = data();
+
+        top_k_accuracy(&y_true, &y_pred.slice(s![.., ..2]).to_owned(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "shapes differ")]
+    fn test_accuracy_shape_mismatch() {
+        let (y_true, y_pred) = data();
+
+        accuracy(&y_true.slice(s![.., ..2]).to_owned(), &y_pred);
+    }
+
+    #[test]
+    #[should_panic(expected = "shapes differ")]
+    fn test_precision_shape_mismatch() {
+        let (y_true, y_pred) = data();
+
+        precision(
+            &y_true.slice(s![.., ..2]).to_owned(),
+            &y_pred,
+            Average::Macro,
+        );
+    }
+
+    #[test]
+    fn test_precision() {
+        let (y_true, y_pred) = data();
+
+        // class 0: 1 / 2, class 1: 2 / 3, class 2: 1 / 1
+        assert_close(
+            (0.5 + 2.0 / 3.0 + 1.0) / 3.0,
+            precision(&y_true, &y_pred, Average::Macro),
+        );
+        assert_close(4.0 / 6.0, precision(&y_true, &y_pred, Average::Micro));
+        assert_close(
+            (0.5 + 2.0 / 3.0 + 1.0) / 3.0,
+            precision(&y_true, &y_pred, Average::Weighted),
+        );
+    }
+
+    #[test]
+    fn test_recall() {
+        let (y_true, y_pred) = data();
+
+        // class 0: 1 / 2, class 1: 2 / 2, class 2: 1 / 2
+        assert_close(2.0 / 3.0, recall(&y_true, &y_pred, Average::Macro));
+        assert_close(4.0 / 6.0, recall(&y_true, &y_pred, Average::Micro));
+    }
+
+    #[test]
+    fn test_f1_score() {
+        let (y_true, y_pred) = data();
+
+        // class 0: 2 / 4, class 1: 4 / 5, class 2: 2 / 3
+        assert_close(
+            (0.5 + 0.8 + 2.0 / 3.0) / 3.0,
+            f1_score(&y_true, &y_pred, Average::Macro),
+        );
+        assert_close(4.0 / 6.0, f1_score(&y_true, &y_pred, Average::Micro));
+    }
+
+    #[test]
+    fn test_binary() {
+        let y_true = arr2(&[[1.0], [0.0], [1.0], [0.0]]);
+        let y_pred = arr2(&[[0.9], [0.6], [0.2], [0.1]]);
+
+        assert_close(0.5, accuracy(&y_true, &y_pred));
+        assert_close(0.5, precision(&y_true, &y_pred, Average::Macro));
+    }
+
+    #[test]
+    fn test_metric_names() {
+        assert_eq!("accuracy", Metric::<f64>::name(&Accuracy));
+        assert_eq!(
+            "top_5_accuracy",
+            Metric::<f64>::name(&TopKAccuracy { k: 5 })
+        );
+        assert_eq!(
+            "f1_weighted",
+            Metric::<f64>::name(&F1Score(Average::Weighted))
+        );
+    }
+}
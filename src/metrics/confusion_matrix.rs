@@ -0,0 +1,313 @@
+use std::fmt::{self, Display};
+
+use ndarray::Array2;
+use num_traits::Float;
+
+use super::{ratio, to_labels};
+
+/// Counts of the predicted classes for every true class.
+///
+/// Rows correspond to the true classes and columns to the predicted classes.
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::arr2;
+/// use robit::metrics::ConfusionMatrix;
+///
+/// let y_true = arr2(&[[1.0, 0.0], [0.0, 1.0], [0.0, 1.0]]);
+/// let y_pred = arr2(&[[0.9, 0.1], [0.8, 0.2], [0.1, 0.9]]);
+///
+/// let matrix = ConfusionMatrix::from_predictions(&y_true, &y_pred);
+///
+/// assert_eq!(1, matrix.get(1, 0));
+/// println!("{}", matrix);
+/// println!("{}", matrix.report());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfusionMatrix {
+    counts: Array2<usize>,
+}
+
+impl ConfusionMatrix {
+    /// Builds the matrix from class indices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slices differ in length or contain a class that is not
+    /// below `n_classes`.
+    pub fn from_labels(y_true: &[usize], y_pred: &[usize], n_classes: usize) -> Self {
+        assert_eq!(y_true.len(), y_pred.len(), "label counts differ");
+
+        let mut counts = Array2::zeros((n_classes, n_classes));
+
+        for (t, p) in y_true.iter().zip(y_pred) {
+            counts[(*t, *p)] += 1;
+        }
+
+        Self { counts }
+    }
+
+    /// Builds the matrix from one-hot encoded labels and the output of
+    /// [Model::predict], see [to_labels].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shapes of `y_true` and `y_pred` differ.
+    ///
+    /// [Model::predict]: crate::Model::predict
+    pub fn from_predictions<T: Float>(y_true: &Array2<T>, y_pred: &Array2<T>) -> Self {
+        assert_eq!(y_true.dim(), y_pred.dim(), "shapes differ");
+
+        Self::from_labels(
+            &to_labels(y_true),
+            &to_labels(y_pred),
+            y_true.ncols().max(2),
+        )
+    }
+
+    pub fn n_classes(&self) -> usize {
+        self.counts.nrows()
+    }
+
+    /// Number of samples of class `actual` that were predicted as
+    /// `predicted`.
+    pub fn get(&self, actual: usize, predicted: usize) -> usize {
+        self.counts[(actual, predicted)]
+    }
+
+    pub fn counts(&self) -> &Array2<usize> {
+        &self.counts
+    }
+
+    pub fn true_positives(&self, class: usize) -> usize {
+        self.counts[(class, class)]
+    }
+
+    pub fn false_positives(&self, class: usize) -> usize {
+        self.counts.column(class).sum() - self.true_positives(class)
+    }
+
+    pub fn false_negatives(&self, class: usize) -> usize {
+        self.counts.row(class).sum() - self.true_positives(class)
+    }
+
+    /// Number of samples whose true class is `class`.
+    pub fn support(&self, class: usize) -> usize {
+        self.counts.row(class).sum()
+    }
+
+    pub fn n_samples(&self) -> usize {
+        self.counts.sum()
+    }
+
+    /// Fraction of samples on the diagonal.
+    pub fn accuracy(&self) -> f64 {
+        let correct: usize = (0..self.n_classes()).map(|c| self.true_positives(c)).sum();
+
+        ratio(correct, self.n_samples())
+    }
+
+    /// Per-class precision, recall, F1 score and support.
+    pub fn report(&self) -> ClassificationReport {
+        let classes: Vec<ClassScores> = (0..self.n_classes())
+            .map(|c| {
+                let tp = self.true_positives(c);
+                let fp = self.false_positives(c);
+                let fn_ = self.false_negatives(c);
+
+                ClassScores {
+                    precision: ratio(tp, tp + fp),
+                    recall: ratio(tp, tp + fn_),
+                    f1_score: ratio(2 * tp, 2 * tp + fp + fn_),
+                    support: tp + fn_,
+                }
+            })
+            .collect();
+
+        let n_classes = classes.len() as f64;
+        let n_samples = self.n_samples();
+
+        let macro_avg = ClassScores {
+            precision: classes.iter().map(|c| c.precision).sum::<f64>() / n_classes,
+            recall: classes.iter().map(|c| c.recall).sum::<f64>() / n_classes,
+            f1_score: classes.iter().map(|c| c.f1_score).sum::<f64>() / n_classes,
+            support: n_samples,
+        };
+
+        let weighted = |score: fn(&ClassScores) -> f64| {
+            if n_samples == 0 {
+                return 0.0;
+            }
+
+            classes
+                .iter()
+                .map(|c| score(c) * c.support as f64)
+                .sum::<f64>()
+                / n_samples as f64
+        };
+
+        let weighted_avg = ClassScores {
+            precision: weighted(|c| c.precision),
+            recall: weighted(|c| c.recall),
+            f1_score: weighted(|c| c.f1_score),
+            support: n_samples,
+        };
+
+        ClassificationReport {
+            accuracy: self.accuracy(),
+            classes,
+            macro_avg,
+            weighted_avg,
+        }
+    }
+}
+
+impl Display for ConfusionMatrix {
+    /// Renders the matrix as a table with the true classes as rows and the
+    /// predicted classes as columns.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header = "true\\pred";
+        let width = self
+            .counts
+            .iter()
+            .map(|c| c.to_string().len())
+            .chain([self.n_classes().saturating_sub(1).to_string().len()])
+            .max()
+            .unwrap_or(1);
+
+        write!(f, "{}", header)?;
+        for c in 0..self.n_classes() {
+            write!(f, " {:>width$}", c, width = width)?;
+        }
+        writeln!(f)?;
+
+        for (c, row) in self.counts.rows().into_iter().enumerate() {
+            write!(f, "{:>width$}", c, width = header.len())?;
+            for count in row {
+                write!(f, " {:>width$}", count, width = width)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Scores of a single class, or their average.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClassScores {
+    pub precision: f64,
+    pub recall: f64,
+    pub f1_score: f64,
+
+    /// Number of samples the scores are computed from.
+    pub support: usize,
+}
+
+/// Per-class scores of a classifier, created by [ConfusionMatrix::report].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassificationReport {
+    /// Scores of every class, indexed by the class.
+    pub classes: Vec<ClassScores>,
+    pub accuracy: f64,
+    pub macro_avg: ClassScores,
+
+    /// Average of the class scores weighted by their support.
+    pub weighted_avg: ClassScores,
+}
+
+impl Display for ClassificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let row = |f: &mut fmt::Formatter<'_>, name: &str, scores: &ClassScores| {
+            writeln!(
+                f,
+                "{:>12} {:>9.2} {:>9.2} {:>9.2} {:>9}",
+                name, scores.precision, scores.recall, scores.f1_score, scores.support
+            )
+        };
+
+        writeln!(
+            f,
+            "{:>12} {:>9} {:>9} {:>9} {:>9}",
+            "", "precision", "recall", "f1-score", "support"
+        )?;
+        writeln!(f)?;
+
+        for (c, scores) in self.classes.iter().enumerate() {
+            row(f, &c.to_string(), scores)?;
+        }
+
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:>12} {:>9} {:>9} {:>9.2} {:>9}",
+            "accuracy", "", "", self.accuracy, self.macro_avg.support
+        )?;
+        row(f, "macro avg", &self.macro_avg)?;
+        row(f, "weighted avg", &self.weighted_avg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::tests::assert_close;
+
+    fn matrix() -> ConfusionMatrix {
+        ConfusionMatrix::from_labels(&[0, 0, 1, 1, 2, 2], &[0, 1, 1, 1, 2, 0], 3)
+    }
+
+    #[test]
+    fn test_counts() {
+        let matrix = matrix();
+
+        assert_eq!(
+            ndarray::arr2(&[[1, 1, 0], [0, 2, 0], [1, 0, 1]]),
+            matrix.counts()
+        );
+        assert_eq!(1, matrix.false_positives(0));
+        assert_eq!(1, matrix.false_negatives(0));
+        assert_eq!(2, matrix.support(2));
+        assert_close(4.0 / 6.0, matrix.accuracy());
+    }
+
+    #[test]
+    fn test_report() {
+        let report = matrix().report();
+
+        assert_close(2.0 / 3.0, report.classes[1].precision);
+        assert_close(1.0, report.classes[1].recall);
+        assert_close(0.8, report.classes[1].f1_score);
+        assert_eq!(2, report.classes[1].support);
+        assert_close((0.5 + 0.8 + 2.0 / 3.0) / 3.0, report.macro_avg.f1_score);
+        assert_eq!(6, report.weighted_avg.support);
+    }
+
+    #[test]
+    fn test_display() {
+        let expected = "\
+true\\pred 0 1 2
+        0 1 1 0
+        1 0 2 0
+        2 1 0 1
+";
+
+        assert_eq!(expected, matrix().to_string());
+    }
+
+    #[test]
+    fn test_report_display() {
+        let report = matrix().report().to_string();
+        let lines: Vec<&str> = report.lines().collect();
+
+        assert_eq!(
+            "           0      0.50      0.50      0.50         2",
+            lines[2]
+        );
+        assert_eq!(
+            "    accuracy                          0.67         6",
+            lines[6]
+        );
+    }
+}
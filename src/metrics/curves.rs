@@ -0,0 +1,423 @@
+use ndarray::Array2;
+use num_traits::{Float, FromPrimitive};
+
+use super::{ratio, Average};
+
+/// Receiver operating characteristic of a binary or one-vs-rest classifier,
+/// created by [roc_curve].
+///
+/// The points are ordered by decreasing threshold. The first point is always
+/// `(0, 0)` at an infinite threshold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RocCurve<T = f64> {
+    /// False positive rate of every threshold.
+    pub fpr: Vec<T>,
+
+    /// True positive rate of every threshold.
+    pub tpr: Vec<T>,
+
+    /// Scores at or above which a sample is predicted as positive.
+    pub thresholds: Vec<T>,
+}
+
+impl<T: Float> RocCurve<T> {
+    /// Area under the curve.
+    pub fn auc(&self) -> T {
+        auc(&self.fpr, &self.tpr)
+    }
+}
+
+/// Precision and recall of a binary or one-vs-rest classifier at every
+/// threshold, created by [precision_recall_curve].
+///
+/// The points are ordered by decreasing threshold. The first point is always
+/// a precision of `1` at a recall of `0` at an infinite threshold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrecisionRecallCurve<T = f64> {
+    pub precision: Vec<T>,
+    pub recall: Vec<T>,
+    pub thresholds: Vec<T>,
+}
+
+impl<T: Float> PrecisionRecallCurve<T> {
+    /// Average precision, `Σ (R_n - R_{n-1}) P_n`, the precision at each
+    /// threshold weighted by the increase of the recall.
+    pub fn average_precision(&self) -> T {
+        self.recall
+            .windows(2)
+            .zip(self.precision.iter().skip(1))
+            .fold(T::zero(), |sum, (r, p)| sum + (r[1] - r[0]) * *p)
+    }
+}
+
+/// Cumulative true and false positives at every distinct score, ordered by
+/// decreasing score.
+struct BinaryCounts<T> {
+    tps: Vec<usize>,
+    fps: Vec<usize>,
+    thresholds: Vec<T>,
+}
+
+impl<T: Float> BinaryCounts<T> {
+    /// Treats `class` as the positive class. A sample is positive if its
+    /// target in the column of the class is at least `0.5`.
+    ///
+    /// Panics if the shapes of `y_true` and `y_pred` differ or a score is
+    /// NaN, since the scores could not be ordered.
+    fn new(y_true: &Array2<T>, y_pred: &Array2<T>, class: usize) -> Self {
+        assert_eq!(y_true.dim(), y_pred.dim(), "shapes differ");
+
+        let threshold = T::from(0.5).unwrap();
+        let labels = y_true.column(class);
+        let scores = y_pred.column(class);
+
+        assert!(
+            !scores.iter().any(|score| score.is_nan()),
+            "predicted scores of class {} contain NaN",
+            class
+        );
+
+        let mut indices: Vec<usize> = (0..scores.len()).collect();
+        indices.sort_by(|a, b| scores[*b].partial_cmp(&scores[*a]).unwrap());
+
+        let mut counts = Self {
+            tps: vec![],
+            fps: vec![],
+            thresholds: vec![],
+        };
+        let (mut tp, mut fp) = (0, 0);
+
+        for (n, i) in indices.iter().enumerate() {
+            if labels[*i] >= threshold {
+                tp += 1;
+            } else {
+                fp += 1;
+            }
+
+            let is_last = match indices.get(n + 1) {
+                Some(next) => scores[*next] != scores[*i],
+                None => true,
+            };
+
+            if is_last {
+                counts.tps.push(tp);
+                counts.fps.push(fp);
+                counts.thresholds.push(scores[*i]);
+            }
+        }
+
+        counts
+    }
+
+    fn positives(&self) -> usize {
+        self.tps.last().copied().unwrap_or(0)
+    }
+
+    fn negatives(&self) -> usize {
+        self.fps.last().copied().unwrap_or(0)
+    }
+}
+
+/// Computes the receiver operating characteristic of the probabilities
+/// predicted for `class`, which is `0` for a binary classifier with a single
+/// output.
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::arr2;
+/// use robit::metrics::roc_curve;
+///
+/// let y_true = arr2(&[[0.0], [0.0], [1.0], [1.0]]);
+/// let y_pred = arr2(&[[0.1], [0.4], [0.35], [0.8]]);
+///
+/// let roc = roc_curve(&y_true, &y_pred, 0);
+///
+/// assert_eq!(vec![0.0, 0.0, 0.5, 0.5, 1.0], roc.fpr);
+/// assert_eq!(vec![0.0, 0.5, 0.5, 1.0, 1.0], roc.tpr);
+/// assert_eq!(0.75, roc.auc());
+/// ```
+///
+/// # Panics
+///
+/// Panics if the shapes of `y_true` and `y_pred` differ or a probability
+/// predicted for `class` is NaN.
+pub fn roc_curve<T: Float + FromPrimitive>(
+    y_true: &Array2<T>,
+    y_pred: &Array2<T>,
+    class: usize,
+) -> RocCurve<T> {
+    let counts = BinaryCounts::new(y_true, y_pred, class);
+    let (positives, negatives) = (counts.positives(), counts.negatives());
+
+    RocCurve {
+        fpr: [T::zero()]
+            .into_iter()
+            .chain(counts.fps.iter().map(|fp| ratio(*fp, negatives)))
+            .collect(),
+        tpr: [T::zero()]
+            .into_iter()
+            .chain(counts.tps.iter().map(|tp| ratio(*tp, positives)))
+            .collect(),
+        thresholds: [T::infinity()]
+            .into_iter()
+            .chain(counts.thresholds)
+            .collect(),
+    }
+}
+
+/// Computes precision and recall of the probabilities predicted for `class`
+/// at every threshold, which is `0` for a binary classifier with a single
+/// output.
+///
+/// # Panics
+///
+/// Panics if the shapes of `y_true` and `y_pred` differ or a probability
+/// predicted for `class` is NaN.
+pub fn precision_recall_curve<T: Float + FromPrimitive>(
+    y_true: &Array2<T>,
+    y_pred: &Array2<T>,
+    class: usize,
+) -> PrecisionRecallCurve<T> {
+    let counts = BinaryCounts::new(y_true, y_pred, class);
+    let positives = counts.positives();
+
+    PrecisionRecallCurve {
+        precision: [T::one()]
+            .into_iter()
+            .chain(
+                counts
+                    .tps
+                    .iter()
+                    .zip(&counts.fps)
+                    .map(|(tp, fp)| ratio(*tp, tp + fp)),
+            )
+            .collect(),
+        recall: [T::zero()]
+            .into_iter()
+            .chain(counts.tps.iter().map(|tp| ratio(*tp, positives)))
+            .collect(),
+        thresholds: [T::infinity()]
+            .into_iter()
+            .chain(counts.thresholds)
+            .collect(),
+    }
+}
+
+/// Area under a curve computed with the trapezoidal rule. The `x` values
+/// must be monotonic.
+///
+/// # Examples
+///
+/// ```
+/// use robit::metrics::auc;
+///
+/// assert_eq!(0.5, auc(&[0.0, 1.0], &[0.0, 1.0]));
+/// ```
+pub fn auc<T: Float>(x: &[T], y: &[T]) -> T {
+    let two = T::one() + T::one();
+
+    x.windows(2)
+        .zip(y.windows(2))
+        .fold(T::zero(), |area, (x, y)| {
+            area + (x[1] - x[0]) * (y[1] + y[0]) / two
+        })
+        .abs()
+}
+
+/// Area under the ROC curve.
+///
+/// A classifier with a single output is scored as binary classifier. With
+/// multiple outputs every class is scored against the rest and the scores are
+/// combined by `average`, where [Average::Micro] scores all outputs as a
+/// single binary problem.
+///
+/// # Panics
+///
+/// Panics if the shapes of `y_true` and `y_pred` differ or a predicted
+/// probability is NaN.
+pub fn roc_auc_score<T: Float + FromPrimitive>(
+    y_true: &Array2<T>,
+    y_pred: &Array2<T>,
+    average: Average,
+) -> T {
+    one_vs_rest(y_true, y_pred, average, |y_true, y_pred, class| {
+        roc_curve(y_true, y_pred, class).auc()
+    })
+}
+
+/// Average precision of the probabilities, combined over the classes like
+/// [roc_auc_score].
+///
+/// # Panics
+///
+/// Panics if the shapes of `y_true` and `y_pred` differ or a predicted
+/// probability is NaN.
+pub fn average_precision_score<T: Float + FromPrimitive>(
+    y_true: &Array2<T>,
+    y_pred: &Array2<T>,
+    average: Average,
+) -> T {
+    one_vs_rest(y_true, y_pred, average, |y_true, y_pred, class| {
+        precision_recall_curve(y_true, y_pred, class).average_precision()
+    })
+}
+
+fn one_vs_rest<T, F>(y_true: &Array2<T>, y_pred: &Array2<T>, average: Average, score: F) -> T
+where
+    T: Float + FromPrimitive,
+    F: Fn(&Array2<T>, &Array2<T>, usize) -> T,
+{
+    assert_eq!(y_true.dim(), y_pred.dim(), "shapes differ");
+
+    if y_true.ncols() == 1 {
+        return score(y_true, y_pred, 0);
+    }
+
+    match average {
+        Average::Micro => {
+            let n = y_true.len();
+            let y_true = y_true.to_shape((n, 1)).unwrap().to_owned();
+            let y_pred = y_pred.to_shape((n, 1)).unwrap().to_owned();
+
+            score(&y_true, &y_pred, 0)
+        }
+        Average::Macro => {
+            let sum = (0..y_true.ncols())
+                .fold(T::zero(), |sum, class| sum + score(y_true, y_pred, class));
+
+            sum / T::from_usize(y_true.ncols()).unwrap()
+        }
+        Average::Weighted => {
+            let threshold = T::from(0.5).unwrap();
+            let supports: Vec<usize> = y_true
+                .columns()
+                .into_iter()
+                .map(|c| c.iter().filter(|y| **y >= threshold).count())
+                .collect();
+            let n_positives: usize = supports.iter().sum();
+
+            let sum = supports
+                .iter()
+                .enumerate()
+                .fold(T::zero(), |sum, (class, support)| {
+                    sum + score(y_true, y_pred, class) * T::from_usize(*support).unwrap()
+                });
+
+            sum / T::from_usize(n_positives.max(1)).unwrap()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::arr2;
+
+    use super::*;
+    use crate::metrics::tests::assert_close;
+
+    fn binary() -> (Array2<f64>, Array2<f64>) {
+        (
+            arr2(&[[0.0], [0.0], [1.0], [1.0]]),
+            arr2(&[[0.1], [0.4], [0.35], [0.8]]),
+        )
+    }
+
+    #[test]
+    fn test_roc_curve() {
+        let (y_true, y_pred) = binary();
+        let roc = roc_curve(&y_true, &y_pred, 0);
+
+        assert_eq!(vec![0.0, 0.0, 0.5, 0.5, 1.0], roc.fpr);
+        assert_eq!(vec![0.0, 0.5, 0.5, 1.0, 1.0], roc.tpr);
+        assert_eq!(vec![f64::INFINITY, 0.8, 0.4, 0.35, 0.1], roc.thresholds);
+        assert_close(0.75, roc.auc());
+    }
+
+    #[test]
+    fn test_roc_curve_ties() {
+        let y_true = arr2(&[[0.0], [1.0], [1.0]]);
+        let y_pred = arr2(&[[0.5], [0.5], [0.9]]);
+        let roc = roc_curve(&y_true, &y_pred, 0);
+
+        assert_eq!(vec![0.0, 0.0, 1.0], roc.fpr);
+        assert_eq!(vec![0.0, 0.5, 1.0], roc.tpr);
+    }
+
+    #[test]
+    fn test_precision_recall_curve() {
+        let (y_true, y_pred) = binary();
+        let curve = precision_recall_curve(&y_true, &y_pred, 0);
+
+        assert_eq!(vec![0.0, 0.5, 0.5, 1.0, 1.0], curve.recall);
+        assert_close(2.0 / 3.0, curve.precision[3]);
+        assert_close(0.8333333333333333, curve.average_precision());
+    }
+
+    #[test]
+    fn test_one_vs_rest() {
+        let y_true = arr2(&[[1.0, 0.0], [1.0, 0.0], [0.0, 1.0], [0.0, 1.0]]);
+        let y_pred = arr2(&[[0.9, 0.1], [0.6, 0.4], [0.65, 0.35], [0.2, 0.8]]);
+
+        assert_close(0.75, roc_auc_score(&y_true, &y_pred, Average::Macro));
+        assert_close(0.75, roc_auc_score(&y_true, &y_pred, Average::Weighted));
+        assert_close(1.0, roc_auc_score(&y_true, &y_true, Average::Micro));
+    }
+
+    #[test]
+    #[should_panic(expected = "contain NaN")]
+    fn test_nan_scores() {
+        let y_true = Array2::from_shape_fn((40, 1), |(i, _)| (i % 2) as f64);
+        let y_pred = Array2::from_shape_fn((40, 1), |(i, _)| {
+            if i % 7 == 0 {
+                f64::NAN
+            } else {
+                i as f64 / 40.0
+            }
+        });
+
+        roc_curve(&y_true, &y_pred, 0).auc();
+    }
+
+    #[test]
+    #[should_panic(expected = "shapes differ")]
+    fn test_roc_curve_shape_mismatch() {
+        let y_true = arr2(&[[0.0], [1.0]]);
+        let y_pred = arr2(&[[0.1], [0.4], [0.35]]);
+
+        roc_curve(&y_true, &y_pred, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "shapes differ")]
+    fn test_precision_recall_curve_shape_mismatch() {
+        let y_true = arr2(&[[0.0], [1.0], [1.0]]);
+        let y_pred = arr2(&[[0.1], [0.4]]);
+
+        precision_recall_curve(&y_true, &y_pred, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "shapes differ")]
+    fn test_roc_auc_score_shape_mismatch() {
+        let y_true = arr2(&[[1.0, 0.0, 0.0, 1.0]]);
+        let y_pred = arr2(&[[0.9, 0.1], [0.2, 0.8]]);
+
+        roc_auc_score(&y_true, &y_pred, Average::Micro);
+    }
+
+    #[test]
+    #[should_panic(expected = "shapes differ")]
+    fn test_average_precision_score_shape_mismatch() {
+        let y_true = arr2(&[[1.0, 0.0], [0.0, 1.0]]);
+        let y_pred = arr2(&[[0.9, 0.1], [0.2, 0.8], [0.6, 0.4]]);
+
+        average_precision_score(&y_true, &y_pred, Average::Macro);
+    }
+
+    #[test]
+    fn test_auc() {
+        assert_close(0.5, auc(&[0.0, 1.0], &[0.0, 1.0]));
+        assert_close(1.0, auc(&[1.0, 0.0], &[1.0, 1.0]));
+    }
+}
@@ -0,0 +1,68 @@
+mod calibration;
+mod classification;
+mod confusion_matrix;
+mod curves;
+mod regression;
+
+use ndarray::Array2;
+use num_traits::{Float, FromPrimitive};
+
+pub use calibration::{expected_calibration_error, reliability_bins, ReliabilityBin};
+pub use classification::{
+    accuracy, f1_score, precision, recall, to_labels, top_k_accuracy, Accuracy, Average, F1Score,
+    Precision, Recall, TopKAccuracy,
+};
+pub use confusion_matrix::{ClassScores, ClassificationReport, ConfusionMatrix};
+pub use curves::{
+    auc, average_precision_score, precision_recall_curve, roc_auc_score, roc_curve,
+    PrecisionRecallCurve, RocCurve,
+};
+pub use regression::{
+    mean_absolute_error, r2_score, root_mean_squared_error, MeanAbsoluteError, R2Score,
+    RootMeanSquaredError,
+};
+
+/// A metric that is tracked during training.
+///
+/// The value is computed at the end of every epoch on the training data and,
+/// if available, on the validation data, where it is logged with a `val_`
+/// prefix.
+///
+/// # Examples
+///
+/// ```
+/// use robit::{metrics::{Accuracy, Metric}, FitConfig};
+///
+/// let config: FitConfig = FitConfig {
+///     epochs: 10,
+///     metrics: vec![Box::new(Accuracy)],
+///     ..Default::default()
+/// };
+/// ```
+pub trait Metric<T = f64> {
+    /// Name under which the value is logged, e.g. `accuracy`.
+    fn name(&self) -> String;
+
+    fn compute(&self, y_true: &Array2<T>, y_pred: &Array2<T>) -> T;
+}
+
+/// Divides two counts, returning zero if the denominator is zero.
+fn ratio<T: Float + FromPrimitive>(numerator: usize, denominator: usize) -> T {
+    if denominator == 0 {
+        return T::zero();
+    }
+
+    T::from_usize(numerator).unwrap() / T::from_usize(denominator).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    pub(super) fn assert_close(expected: f64, actual: f64) {
+        assert!(
+            (expected - actual).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use ndarray::{Array2, Axis};
+use num_traits::{Float, FromPrimitive};
+
+use super::Metric;
+
+/// Mean of the absolute differences between the predictions and the targets.
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::arr2;
+/// use robit::metrics::mean_absolute_error;
+///
+/// let y_true = arr2(&[[1.0], [2.0]]);
+/// let y_pred = arr2(&[[1.5], [1.0]]);
+///
+/// assert_eq!(0.75, mean_absolute_error(&y_true, &y_pred));
+/// ```
+///
+/// # Panics
+///
+/// Panics if the shapes of `y_true` and `y_pred` differ.
+pub fn mean_absolute_error<T: Float + FromPrimitive>(y_true: &Array2<T>, y_pred: &Array2<T>) -> T {
+    assert_eq!(y_true.dim(), y_pred.dim(), "shapes differ");
+
+    (y_pred - y_true)
+        .mapv(T::abs)
+        .mean()
+        .unwrap_or_else(T::zero)
+}
+
+/// Square root of the mean squared difference between the predictions and
+/// the targets.
+///
+/// # Panics
+///
+/// Panics if the shapes of `y_true` and `y_pred` differ.
+pub fn root_mean_squared_error<T: Float + FromPrimitive>(
+    y_true: &Array2<T>,
+    y_pred: &Array2<T>,
+) -> T {
+    assert_eq!(y_true.dim(), y_pred.dim(), "shapes differ");
+
+    (y_pred - y_true)
+        .mapv(|x| x * x)
+        .mean()
+        .unwrap_or_else(T::zero)
+        .sqrt()
+}
+
+/// Coefficient of determination, `1 - SS_res / SS_tot`, averaged over the
+/// output columns.
+///
+/// A column whose targets are constant scores `1.0` when it is predicted
+/// perfectly and `0.0` otherwise.
+///
+/// # Panics
+///
+/// Panics if the shapes of `y_true` and `y_pred` differ.
+pub fn r2_score<T: Float + FromPrimitive>(y_true: &Array2<T>, y_pred: &Array2<T>) -> T {
+    assert_eq!(y_true.dim(), y_pred.dim(), "shapes differ");
+
+    let mean = match y_true.mean_axis(Axis(0)) {
+        Some(mean) => mean,
+        None => return T::zero(),
+    };
+
+    let ss_res = (y_true - y_pred).mapv(|x| x * x).sum_axis(Axis(0));
+    let ss_tot = (y_true - &mean).mapv(|x| x * x).sum_axis(Axis(0));
+
+    let scores = ss_res.iter().zip(ss_tot.iter()).map(|(res, tot)| {
+        if *tot == T::zero() {
+            if *res == T::zero() {
+                T::one()
+            } else {
+                T::zero()
+            }
+        } else {
+            T::one() - *res / *tot
+        }
+    });
+
+    scores.fold(T::zero(), |a, b| a + b) / T::from_usize(y_true.ncols()).unwrap()
+}
+
+/// Tracks the [mean_absolute_error] during training.
+pub struct MeanAbsoluteError;
+
+impl<T: Float + FromPrimitive> Metric<T> for MeanAbsoluteError {
+    fn name(&self) -> String {
+        "mae".to_owned()
+    }
+
+    fn compute(&self, y_true: &Array2<T>, y_pred: &Array2<T>) -> T {
+        mean_absolute_error(y_true, y_pred)
+    }
+}
+
+/// Tracks the [root_mean_squared_error] during training.
+pub struct RootMeanSquaredError;
+
+impl<T: Float + FromPrimitive> Metric<T> for RootMeanSquaredError {
+    fn name(&self) -> String {
+        "rmse".to_owned()
+    }
+
+    fn compute(&self, y_true: &Array2<T>, y_pred: &Array2<T>) -> T {
+        root_mean_squared_error(y_true, y_pred)
+    }
+}
+
+/// Tracks the [r2_score] during training.
+pub struct R2Score;
+
+impl<T: Float + FromPrimitive> Metric<T> for R2Score {
+    fn name(&self) -> String {
+        "r2".to_owned()
+    }
+
+    fn compute(&self, y_true: &Array2<T>, y_pred: &Array2<T>) -> T {
+        r2_score(y_true, y_pred)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::arr2;
+
+    use super::*;
+    use crate::metrics::tests::assert_close;
+
+    #[test]
+    fn test_mean_absolute_error() {
+        let y_true = arr2(&[[3.0, -0.5], [2.0, 7.0]]);
+        let y_pred = arr2(&[[2.5, 0.0], [2.0, 8.0]]);
+
+        assert_close(0.5, mean_absolute_error(&y_true, &y_pred));
+    }
+
+    #[test]
+    fn test_root_mean_squared_error() {
+        let y_true = arr2(&[[3.0, -0.5], [2.0, 7.0]]);
+        let y_pred = arr2(&[[2.5, 0.0], [2.0, 8.0]]);
+
+        assert_close(
+            (1.5f64 / 4.0).sqrt(),
+            root_mean_squared_error(&y_true, &y_pred),
+        );
+    }
+
+    #[test]
+    fn test_r2_score() {
+        let y_true = arr2(&[[3.0], [-0.5], [2.0], [7.0]]);
+        let y_pred = arr2(&[[2.5], [0.0], [2.0], [8.0]]);
+
+        assert_close(0.9486081370449679, r2_score(&y_true, &y_pred));
+        assert_close(1.0, r2_score(&y_true, &y_true));
+    }
+
+    #[test]
+    fn test_r2_score_constant_target() {
+        let y_true = arr2(&[[1.0], [1.0]]);
+
+        assert_close(1.0, r2_score(&y_true, &y_true));
+        assert_close(0.0, r2_score(&y_true, &arr2(&[[0.0], [1.0]])));
+    }
+
+    #[test]
+    #[should_panic(expected = "shapes differ")]
+    fn test_mean_absolute_error_shape_mismatch() {
+        mean_absolute_error(&arr2(&[[1.0], [2.0]]), &arr2(&[[1.0, 0.0], [2.0, 0.0]]));
+    }
+
+    #[test]
+    #[should_panic(expected = "shapes differ")]
+    fn test_root_mean_squared_error_shape_mismatch() {
+        root_mean_squared_error(&arr2(&[[1.0, 2.0]]), &arr2(&[[1.0, 2.0], [3.0, 4.0]]));
+    }
+
+    #[test]
+    #[should_panic(expected = "shapes differ")]
+    fn test_r2_score_shape_mismatch() {
+        r2_score(&arr2(&[[1.0], [2.0]]), &arr2(&[[1.0, 0.0], [2.0, 0.0]]));
+    }
+}
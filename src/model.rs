@@ -1,22 +1,84 @@
 use std::{
-    fmt::Debug,
+    fmt::{Debug, Display},
+    fs::File,
+    io::{BufReader, BufWriter},
     iter::Sum,
-    ops::{Div, Mul, Sub, SubAssign},
+    mem,
+    ops::{Div, Sub, SubAssign},
+    path::Path,
     process::Output,
+    str::FromStr,
 };
 
 use ndarray::{
-    s, Array1, Array2, Axis, Ix1, Ix2, LinalgScalar,
-    ScalarOperand
+    s, Array, Array1, Array2, ArrayD, Axis, Dimension, Ix1, Ix2, RemoveAxis,
+    ScalarOperand, Slice,
 };
-use num_traits::{FromPrimitive};
+use num_traits::{Float, FromPrimitive};
 
 use crate::{
     activations::{Activation, Relu},
+    autograd::{Tape, Var},
+    callbacks::{Callback, CallbackList, Logs},
     initializers::Initializer,
+    layers::{Context, Layer, Mode, SparseRows},
     losses::{Loss, MeanSquaredError},
+    optimizers::{Optimizer, Rows, Scheduler},
+    serialization::{
+        read_checkpoint, read_model, write_checkpoint, write_model, write_onnx,
+        write_training_checkpoint, Element, SafeTensors,
+    },
+    summary::{LayerSummary, Summary},
+    training::{FitConfig, History, Rng},
+    Error, Result,
 };
 
+/// Weights and biases of every layer.
+pub(crate) type Parameters<T> = (Vec<Array2<T>>, Vec<Array1<T>>);
+
+/// Position of a layer in the model, in the order of [Model::nodes].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Node {
+    /// Index of the dense layer in [Model::weights] and [Model::biases].
+    Dense(usize),
+
+    /// Index of a layer added with [Model::add].
+    Layer(usize),
+}
+
+/// Loss of a batch and its gradients, returned by [Model::gradients].
+pub(crate) struct BatchGradients<T> {
+    pub(crate) loss: T,
+    pub(crate) weights: Vec<Array2<T>>,
+    pub(crate) biases: Vec<Array1<T>>,
+
+    /// Gradients of the [Layer::parameters] of every layer added with
+    /// [Model::add].
+    pub(crate) layers: Vec<Vec<ArrayD<T>>>,
+
+    /// New [Layer::state] of every layer added with [Model::add].
+    pub(crate) states: States<T>,
+
+    /// Rows of the parameters of every layer added with [Model::add] that
+    /// received gradients, if not all, see [Context::set_sparse_rows].
+    pub(crate) sparse_rows: Vec<SparseRows>,
+}
+
+/// Layers added with [Model::add] and the number of dense layers before
+/// them.
+pub(crate) type Layers<T> = Vec<(usize, Box<dyn Layer<T>>)>;
+
+/// New state of every layer added with [Model::add] after a forward pass,
+/// `None` if it did not change.
+type States<T> = Vec<Option<Vec<ArrayD<T>>>>;
+
+/// Parameters of every layer recorded on a tape.
+struct Recorded<'t, T> {
+    weights: Vec<Var<'t, T>>,
+    biases: Vec<Var<'t, T>>,
+    layers: Vec<Vec<Var<'t, T>>>,
+}
+
 pub struct Model<A = Relu, L = MeanSquaredError, T = f64>
 where
     A: Activation<T>,
@@ -24,10 +86,17 @@ where
 {
     weights: Vec<Array2<T>>,
     biases: Vec<Array1<T>>,
+
+    layers: Layers<T>,
     activation: A,
     loss: L,
     batch_size: usize,
     learning_rate: T,
+    optimizer: Option<Box<dyn Optimizer<T>>>,
+    scheduler: Option<Box<dyn Scheduler<T>>>,
+    epoch: usize,
+    rng: Rng,
+    stop_training: bool,
 }
 
 impl<A, L, T> Model<A, L, T>
@@ -40,23 +109,581 @@ where
         Self {
             weights: vec![],
             biases: vec![],
+            layers: vec![],
             activation: A::default(),
             loss: L::default(),
             batch_size: 32,
             learning_rate,
+            optimizer: None,
+            scheduler: None,
+            epoch: 0,
+            rng: Rng::new(0),
+            stop_training: false,
         }
     }
 }
 
+impl<A, L, T> Model<A, L, T>
+where
+    A: Activation<T> + Default,
+    L: Loss<T> + Default,
+    T: FromStr + Float + FromPrimitive + ScalarOperand,
+{
+    /// Loads a model that was stored with [Model::save].
+    ///
+    /// See [serialization](crate::serialization) for a description of the
+    /// format. Fails with [Error::Io] if the file can not be opened and with
+    /// [Error::Text] if its content is malformed.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(read_model(BufReader::new(File::open(path)?))?)
+    }
+}
+
+impl<A, L, T> Model<A, L, T>
+where
+    A: Activation<T> + Default,
+    L: Loss<T> + Default,
+    T: Element + Float + FromPrimitive + ScalarOperand,
+{
+    /// Loads a model that was stored with [Model::save_checkpoint] or
+    /// [Model::save_training_checkpoint], including the training state if it
+    /// was stored.
+    ///
+    /// Fails with an [Error::Checkpoint] if the file is corrupted, was
+    /// written by an unsupported format version or does not match the model
+    /// type.
+    pub fn load_checkpoint<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(read_checkpoint(BufReader::new(File::open(path)?))?)
+    }
+}
+
 impl<A, L, T> Model<A, L, T>
 where
     A: Activation<T>,
     L: Loss<T>,
-    T: Debug,
 {
-    pub fn add_layer<I: Initializer<T>>(&mut self, shape: (usize, usize), mut init: I) {
+    /// Adds a dense layer with `shape.0` inputs and `shape.1` outputs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shape is invalid, see [Model::try_add_layer].
+    pub fn add_layer<I: Initializer<T>>(&mut self, shape: (usize, usize), init: I) {
+        if let Err(err) = self.try_add_layer(shape, init) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Adds a dense layer with `shape.0` inputs and `shape.1` outputs.
+    ///
+    /// Fails with an [Error::ShapeMismatch] if a dimension is zero or the
+    /// number of inputs differs from the number of outputs of the previous
+    /// layer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use robit::{initializers::RandomDistr, Error, Model};
+    ///
+    /// let mut model: Model = Model::new(0.01);
+    /// model.try_add_layer((4, 3), RandomDistr::normal()).unwrap();
+    ///
+    /// assert!(matches!(
+    ///     model.try_add_layer((2, 1), RandomDistr::normal()),
+    ///     Err(Error::ShapeMismatch { .. })
+    /// ));
+    /// ```
+    pub fn try_add_layer<I: Initializer<T>>(
+        &mut self,
+        shape: (usize, usize),
+        init: I,
+    ) -> Result<()> {
+        let n_inputs = self.output_shape().and_then(|shape| shape.last().copied());
+
+        if shape.0 == 0 || shape.1 == 0 || n_inputs.is_some_and(|n| n != shape.0) {
+            return Err(Error::ShapeMismatch {
+                name: "layer",
+                expected: vec![n_inputs.unwrap_or(shape.0).max(1), shape.1.max(1)],
+                found: vec![shape.0, shape.1],
+            });
+        }
+
         self.weights.push(init.gen(shape));
         self.biases.push(init.gen(shape.1));
+
+        Ok(())
+    }
+
+    /// Adds a layer after the previously added layers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layer can not process the output of the previous
+    /// layer, see [Model::try_add].
+    pub fn add(&mut self, layer: impl Layer<T> + 'static) {
+        if let Err(err) = self.try_add(layer) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Adds a layer after the previously added layers.
+    ///
+    /// Fails like [Layer::output_shape] if the layer can not process the
+    /// output of the previous layer. The check is skipped if the shape of
+    /// the output is not known yet, because the model does not start with a
+    /// dense layer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use robit::{initializers::RandomDistr, layers::Dropout, Model};
+    ///
+    /// let mut model: Model = Model::new(0.01);
+    /// model.add_layer((4, 3), RandomDistr::normal());
+    /// model.try_add(Dropout::new(0.5).unwrap()).unwrap();
+    ///
+    /// assert_eq!(1, model.layers().count());
+    /// ```
+    pub fn try_add(&mut self, layer: impl Layer<T> + 'static) -> Result<()> {
+        if let Some(shape) = self.output_shape() {
+            layer.output_shape(&shape)?;
+        }
+
+        self.layers.push((self.weights.len(), Box::new(layer)));
+
+        Ok(())
+    }
+
+    /// Returns the layers added with [Model::add].
+    pub fn layers(&self) -> impl Iterator<Item = &dyn Layer<T>> {
+        self.layers.iter().map(|(_, layer)| layer.as_ref())
+    }
+
+    /// Replaces the layers added with [Model::add], together with the number
+    /// of dense layers before them.
+    pub(crate) fn set_layers(&mut self, layers: Layers<T>) {
+        self.layers = layers;
+    }
+
+    /// Returns a name for every layer added with [Model::add] that is unique
+    /// within the model, e.g. `dropout_0` and `dropout_1`.
+    pub(crate) fn layer_names(&self) -> Vec<String> {
+        let mut counts: Vec<(&str, usize)> = vec![];
+
+        self.layers()
+            .map(|layer| {
+                let n = match counts.iter_mut().find(|(name, _)| *name == layer.name()) {
+                    Some((_, count)) => {
+                        *count += 1;
+                        *count - 1
+                    }
+                    None => {
+                        counts.push((layer.name(), 1));
+                        0
+                    }
+                };
+
+                format!("{}_{}", layer.name(), n)
+            })
+            .collect()
+    }
+
+    /// Returns copies of the parameters and state of every layer added with
+    /// [Model::add], in the order of [Layer::parameters] and [Layer::state].
+    pub(crate) fn layer_tensors(&self) -> Vec<ArrayD<T>>
+    where
+        T: Clone,
+    {
+        self.layers()
+            .flat_map(|layer| layer.parameters().into_iter().chain(layer.state()))
+            .cloned()
+            .collect()
+    }
+
+    /// Replaces the values returned by [Model::layer_tensors].
+    pub(crate) fn set_layer_tensors(&mut self, tensors: Vec<ArrayD<T>>) {
+        let mut tensors = tensors.into_iter();
+
+        for (_, layer) in self.layers.iter_mut() {
+            for value in layer.parameters_mut() {
+                *value = tensors.next().unwrap();
+            }
+
+            for value in layer.state_mut() {
+                *value = tensors.next().unwrap();
+            }
+        }
+    }
+
+    pub(crate) fn layer_mut(&mut self, k: usize) -> &mut dyn Layer<T> {
+        self.layers[k].1.as_mut()
+    }
+
+    /// Returns all layers in the order they are applied.
+    pub(crate) fn nodes(&self) -> Vec<Node> {
+        let mut nodes = Vec::with_capacity(self.weights.len() + self.layers.len());
+        let mut layers = self
+            .layers
+            .iter()
+            .map(|(position, _)| *position)
+            .enumerate()
+            .peekable();
+
+        for i in 0..=self.weights.len() {
+            while let Some((k, _)) = layers.next_if(|(_, position)| *position == i) {
+                nodes.push(Node::Layer(k));
+            }
+
+            if i < self.weights.len() {
+                nodes.push(Node::Dense(i));
+            }
+        }
+
+        nodes
+    }
+
+    /// Returns the shape of the output for an input of the given shape, both
+    /// with the batch axis.
+    ///
+    /// Fails with [Error::EmptyModel] if the model has no layers, with an
+    /// [Error::ShapeMismatch] named `name` if the input has no batch axis or
+    /// a dense layer does not match its input and like [Layer::output_shape]
+    /// otherwise.
+    fn infer_shape(&self, name: &'static str, input_shape: &[usize]) -> Result<Vec<usize>> {
+        if self.weights.is_empty() && self.layers.is_empty() {
+            return Err(Error::EmptyModel);
+        }
+
+        if input_shape.is_empty() {
+            let mut expected = vec![1];
+            expected.extend(self.input_shape().unwrap_or_default());
+
+            return Err(Error::ShapeMismatch {
+                name,
+                expected,
+                found: vec![],
+            });
+        }
+
+        let mut shape = input_shape.to_vec();
+
+        for node in self.nodes() {
+            shape = match node {
+                Node::Dense(i) => {
+                    let (n_inputs, n_outputs) = self.weights[i].dim();
+
+                    if shape.len() < 2 || shape[shape.len() - 1] != n_inputs {
+                        let mut expected = shape.clone();
+                        expected.truncate(expected.len().max(2) - 1);
+                        expected.push(n_inputs);
+
+                        return Err(Error::ShapeMismatch {
+                            name,
+                            expected,
+                            found: shape,
+                        });
+                    }
+
+                    let mut output = shape;
+                    *output.last_mut().unwrap() = n_outputs;
+                    output
+                }
+                Node::Layer(k) => {
+                    let mut output = vec![shape[0]];
+                    output.extend(self.layers[k].1.output_shape(&shape[1..])?);
+                    output
+                }
+            };
+        }
+
+        Ok(shape)
+    }
+
+    /// Checks that every layer can process the output of the previous layer,
+    /// if the shape of the input is known, e.g. after loading a model.
+    pub(crate) fn check_shapes(&self) -> Result<()> {
+        if let Some(shape) = self.input_shape() {
+            let mut shape_with_batch = vec![1];
+            shape_with_batch.extend(shape);
+            self.infer_shape("input", &shape_with_batch)?;
+        }
+
+        Ok(())
+    }
+
+    /// Shape of an input sample, known if the model starts with a dense
+    /// layer.
+    fn input_shape(&self) -> Option<Vec<usize>> {
+        match self.nodes().first() {
+            Some(Node::Dense(i)) => Some(vec![self.weights[*i].nrows()]),
+            _ => None,
+        }
+    }
+
+    /// Shape of an output sample, if the shape of the input is known.
+    fn output_shape(&self) -> Option<Vec<usize>> {
+        let mut shape = vec![1];
+        shape.extend(self.input_shape()?);
+
+        Some(self.infer_shape("input", &shape).ok()?[1..].to_vec())
+    }
+
+    pub fn activation(&self) -> &A {
+        &self.activation
+    }
+
+    pub fn loss(&self) -> &L {
+        &self.loss
+    }
+
+    pub fn learning_rate(&self) -> T
+    where
+        T: Copy,
+    {
+        self.learning_rate
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Stores the architecture, hyperparameters and parameters of the model
+    /// in a human-readable file that can be loaded with [Model::load].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use robit::{initializers::RandomDistr, Model};
+    ///
+    /// let mut model: Model = Model::new(0.01);
+    /// model.add_layer((2, 1), RandomDistr::normal());
+    /// model.save("model.txt").unwrap();
+    ///
+    /// let loaded: Model = Model::load("model.txt").unwrap();
+    /// ```
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()>
+    where
+        T: Display + Copy,
+    {
+        Ok(write_model(self, BufWriter::new(File::create(path)?))?)
+    }
+
+    /// Stores the model in the binary checkpoint format, which is smaller and
+    /// faster to read and write than [Model::save].
+    pub fn save_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<()>
+    where
+        T: Element,
+    {
+        Ok(write_checkpoint(self, BufWriter::new(File::create(path)?))?)
+    }
+
+    /// Stores the model like [Model::save_checkpoint] together with the
+    /// optimizer, scheduler, epoch and random number generator state, so
+    /// that training of the loaded model continues exactly where it stopped.
+    pub fn save_training_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<()>
+    where
+        T: Element,
+    {
+        Ok(write_training_checkpoint(self, BufWriter::new(File::create(path)?))?)
+    }
+
+    /// Stores the parameters in the safetensors format, see
+    /// [SafeTensors::from_model] for the tensor names and shapes.
+    pub fn export_safetensors<P: AsRef<Path>>(&self, path: P) -> Result<()>
+    where
+        T: Element,
+    {
+        Ok(SafeTensors::from_model(self).write(BufWriter::new(File::create(path)?))?)
+    }
+
+    /// Stores the model as ONNX graph, see [write_onnx] for its structure.
+    pub fn export_onnx<P: AsRef<Path>>(&self, path: P) -> Result<()>
+    where
+        T: Element,
+    {
+        Ok(write_onnx(self, BufWriter::new(File::create(path)?))?)
+    }
+
+    /// Replaces the parameters with the tensors of a safetensors file, e.g.
+    /// one written by [Model::export_safetensors].
+    ///
+    /// The layers of the model must already be added, the shapes of the
+    /// tensors are validated against them.
+    pub fn import_safetensors<P: AsRef<Path>>(&mut self, path: P) -> Result<()>
+    where
+        T: Element,
+    {
+        let tensors = SafeTensors::read(BufReader::new(File::open(path)?))?;
+
+        Ok(tensors.load_parameters(self)?)
+    }
+
+    /// Returns an overview of the layers and parameters of the model and
+    /// prints it to stdout.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use robit::{initializers::RandomDistr, Model};
+    ///
+    /// let mut model: Model = Model::new(0.01);
+    /// model.add_layer((784, 32), RandomDistr::normal());
+    /// model.add_layer((32, 10), RandomDistr::normal());
+    ///
+    /// let summary = model.summary();
+    ///
+    /// assert_eq!(25450, summary.total_params());
+    /// assert_eq!(25450 * 8, summary.memory());
+    /// ```
+    pub fn summary(&self) -> Summary {
+        // shapes are unknown if the model does not start with a dense layer
+        let mut shape = self.input_shape();
+        let names = self.layer_names();
+        let mut layers = vec![];
+
+        for node in self.nodes() {
+            let input_shape = shape.clone();
+
+            layers.push(match node {
+                Node::Dense(i) => {
+                    let (w, b) = (&self.weights[i], &self.biases[i]);
+                    shape = Some(vec![w.ncols()]);
+
+                    LayerSummary {
+                        name: format!("dense_{}", i),
+                        kind: "Dense".to_owned(),
+                        input_shape: input_shape.unwrap_or_default(),
+                        output_shape: vec![w.ncols()],
+                        activation: Some(self.activation.name().to_owned()),
+                        trainable_params: w.len() + b.len(),
+                        non_trainable_params: 0,
+                    }
+                }
+                Node::Layer(k) => {
+                    let layer = self.layers[k].1.as_ref();
+                    shape = shape.and_then(|shape| layer.output_shape(&shape).ok());
+
+                    LayerSummary {
+                        name: names[k].clone(),
+                        kind: layer.kind(),
+                        input_shape: input_shape.unwrap_or_default(),
+                        output_shape: shape.clone().unwrap_or_default(),
+                        activation: None,
+                        trainable_params: layer.parameters().iter().map(|p| p.len()).sum(),
+                        non_trainable_params: layer.state().iter().map(|s| s.len()).sum(),
+                    }
+                }
+            });
+        }
+
+        let summary = Summary {
+            layers,
+            value_size: mem::size_of::<T>(),
+        };
+
+        println!("{}", summary);
+
+        summary
+    }
+
+    /// Returns the weight matrix of every layer.
+    pub fn weights(&self) -> &[Array2<T>] {
+        &self.weights
+    }
+
+    /// Returns the bias vector of every layer.
+    pub fn biases(&self) -> &[Array1<T>] {
+        &self.biases
+    }
+
+    /// Replaces the parameters of all layers, e.g. with a snapshot taken from
+    /// [Model::weights] and [Model::biases].
+    pub(crate) fn set_parameters(&mut self, weights: Vec<Array2<T>>, biases: Vec<Array1<T>>)
+    where
+        T: Clone,
+    {
+        // optimizers update the parameters as row-major slices
+        self.weights = weights
+            .into_iter()
+            .map(|w| w.as_standard_layout().into_owned())
+            .collect();
+        self.biases = biases;
+    }
+
+    pub(crate) fn parameters_mut(&mut self) -> (&mut [Array2<T>], &mut [Array1<T>]) {
+        (&mut self.weights, &mut self.biases)
+    }
+
+    /// Replaces plain gradient descent with the optimizer.
+    pub fn set_optimizer<O: Optimizer<T> + 'static>(&mut self, optimizer: O) {
+        self.optimizer = Some(Box::new(optimizer));
+    }
+
+    pub(crate) fn set_boxed_optimizer(&mut self, optimizer: Option<Box<dyn Optimizer<T>>>) {
+        self.optimizer = optimizer;
+    }
+
+    /// Returns the optimizer, `None` when training with plain gradient
+    /// descent.
+    pub fn optimizer(&self) -> Option<&dyn Optimizer<T>> {
+        self.optimizer.as_deref()
+    }
+
+    /// Adjusts the learning rate of every epoch with the scheduler.
+    pub fn set_scheduler<S: Scheduler<T> + 'static>(&mut self, scheduler: S) {
+        self.scheduler = Some(Box::new(scheduler));
+    }
+
+    pub(crate) fn set_boxed_scheduler(&mut self, scheduler: Option<Box<dyn Scheduler<T>>>) {
+        self.scheduler = scheduler;
+    }
+
+    pub fn scheduler(&self) -> Option<&dyn Scheduler<T>> {
+        self.scheduler.as_deref()
+    }
+
+    /// Number of epochs trained so far, continued by every call to
+    /// [Model::fit_epochs].
+    pub fn epoch(&self) -> usize {
+        self.epoch
+    }
+
+    pub(crate) fn set_epoch(&mut self, epoch: usize) {
+        self.epoch = epoch;
+    }
+
+    /// Seeds the random number generator that shuffles the training data and
+    /// draws random values during training, e.g. dropout masks. Passing the
+    /// value of [Model::rng_state] restores the generator.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// State of the random number generator used during training.
+    pub fn rng_state(&self) -> u64 {
+        self.rng.state()
+    }
+
+    /// Learning rate of the current epoch, after applying the scheduler.
+    pub fn current_learning_rate(&self) -> T
+    where
+        T: Copy,
+    {
+        match &self.scheduler {
+            Some(scheduler) => scheduler.learning_rate(self.learning_rate, self.epoch),
+            None => self.learning_rate,
+        }
+    }
+
+    /// Sets the number of samples per gradient update.
+    pub fn set_batch_size(&mut self, batch_size: usize) {
+        self.batch_size = batch_size;
+    }
+
+    /// Ends the running training after the current batch, or before the
+    /// first epoch if called from [Callback::on_train_begin]. Meant to be
+    /// called from a [Callback].
+    pub fn stop_training(&mut self) {
+        self.stop_training = true;
     }
 }
 
@@ -64,71 +691,629 @@ impl<A, T, L> Model<A, L, T>
 where
     A: Activation<T>,
     L: Loss<T>,
-    T: LinalgScalar + PartialOrd + FromPrimitive + ScalarOperand + Mul<T> + Debug + SubAssign<T>,
+    T: Float + FromPrimitive + ScalarOperand + Debug + SubAssign<T>,
 {
-    pub fn predict(&self, input: &Array2<T>) -> Array2<T> {
-        let mut a = self
-            .activation
-            .call(&(input.dot(&self.weights[0]) + &self.biases[0]));
+    /// Returns the output of the model for every sample of the input.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the model has no layers or the input does not match the
+    /// model, see [Model::try_predict].
+    pub fn predict<D: Dimension>(&self, input: &Array<T, D>) -> Array2<T> {
+        self.try_predict(input).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Returns the output of the model for every sample of the input.
+    ///
+    /// The first axis of the input is the batch axis, e.g. a matrix with a
+    /// row per sample for dense layers or images with the shape `[batch,
+    /// channels, height, width]` for [Conv2d](crate::layers::Conv2d). The
+    /// output must be a matrix with a row per sample, e.g. by adding a
+    /// [Flatten](crate::layers::Flatten) layer before the last dense layer.
+    ///
+    /// Fails with [Error::EmptyModel] if the model has no layers and with an
+    /// [Error::ShapeMismatch] if a layer can not process the output of the
+    /// previous layer, e.g. the number of columns of the input differs from
+    /// the number of inputs of the model, or the output is not a matrix. Fails
+    /// with an [Error::InvalidInput] if the first layer can not process the
+    /// values of the input, e.g. tokens outside of the vocabulary of an
+    /// [Embedding](crate::layers::Embedding).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ndarray::arr2;
+    /// use robit::{initializers::RandomDistr, Error, Model};
+    ///
+    /// let mut model: Model = Model::new(0.01);
+    ///
+    /// assert!(matches!(
+    ///     model.try_predict(&arr2(&[[0.0, 1.0]])),
+    ///     Err(Error::EmptyModel)
+    /// ));
+    ///
+    /// model.add_layer((2, 1), RandomDistr::normal());
+    ///
+    /// assert_eq!(1, model.try_predict(&arr2(&[[0.0, 1.0]])).unwrap().len());
+    /// ```
+    pub fn try_predict<D: Dimension>(&self, input: &Array<T, D>) -> Result<Array2<T>> {
+        self.check_input("input", input)?;
+
+        let tape = Tape::new();
+        let parameters = self.record(&tape, false);
+        let (output, ..) = self.forward(
+            tape.constant(input.clone()),
+            &parameters,
+            Mode::Inference,
+            &mut Rng::new(0),
+        );
+
+        Ok(output.value().into_dimensionality().unwrap())
+    }
+
+    /// Checks that the model has layers, every layer can process the output
+    /// of the previous layer, the output is a matrix and the first layer
+    /// accepts the values of the input, see [Layer::check_input].
+    pub(crate) fn check_input<D>(&self, name: &'static str, x: &Array<T, D>) -> Result<()>
+    where
+        D: Dimension,
+    {
+        let shape = self.infer_shape(name, x.shape())?;
 
-        for (w, b) in self.weights[1..].iter().zip(self.biases[1..].iter()) {
-            a = self.activation.call(&(a.dot(w) + b));
+        if shape.len() != 2 {
+            return Err(Error::ShapeMismatch {
+                name: "output",
+                expected: vec![shape[0], shape[1..].iter().product()],
+                found: shape,
+            });
         }
 
-        a
+        if let Some(&Node::Layer(k)) = self.nodes().first() {
+            self.layers[k].1.check_input(x.view().into_dyn())?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the targets have a row for every row of the input and a
+    /// column for every output of the model.
+    pub(crate) fn check_targets<D: Dimension>(
+        &self,
+        name: &'static str,
+        x: &Array<T, D>,
+        y: &Array2<T>,
+    ) -> Result<()> {
+        let expected = self.infer_shape("input", x.shape())?;
+
+        if y.shape() != expected {
+            return Err(Error::ShapeMismatch {
+                name,
+                expected,
+                found: y.shape().to_vec(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn check_data<D: Dimension>(&self, x: &Array<T, D>, y: &Array2<T>) -> Result<()> {
+        self.check_input("input", x)?;
+        self.check_targets("targets", x, y)?;
+
+        if self.batch_size == 0 {
+            return Err(Error::invalid_hyperparameter(
+                "batch_size",
+                "must be positive",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the loss of the model on the given data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the data is invalid, see [Model::try_evaluate].
+    pub fn evaluate<D: Dimension>(&self, x: &Array<T, D>, y: &Array2<T>) -> T {
+        self.try_evaluate(x, y).unwrap_or_else(|err| panic!("{}", err))
     }
 
-    pub fn fit(&mut self, X: &Array2<T>, Y: &Array2<T>) {
-        let n_samples = X.shape()[0];
-        let n_features = X.shape()[1];
-        
+    /// Returns the loss of the model on the given data.
+    ///
+    /// Fails with [Error::EmptyModel] if the model has no layers and with an
+    /// [Error::ShapeMismatch] if the data does not match the inputs and
+    /// outputs of the model.
+    pub fn try_evaluate<D: Dimension>(&self, x: &Array<T, D>, y: &Array2<T>) -> Result<T> {
+        self.check_input("input", x)?;
+        self.check_targets("targets", x, y)?;
+
+        Ok(self.loss.compute(y, &self.try_predict(x)?))
+    }
+
+    /// Trains the model for the configured number of epochs and records the
+    /// training and validation loss of every epoch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ndarray::arr2;
+    /// use robit::{initializers::RandomDistr, FitConfig, Model};
+    ///
+    /// let mut model: Model = Model::new(0.01);
+    /// model.add_layer((2, 1), RandomDistr::normal());
+    ///
+    /// let x = arr2(&[[0.0, 1.0], [1.0, 0.0]]);
+    /// let y = arr2(&[[1.0], [0.0]]);
+    ///
+    /// let history = model.fit_epochs(&x, &y, FitConfig {
+    ///     epochs: 10,
+    ///     validation_data: Some((x.clone(), y.clone())),
+    ///     ..Default::default()
+    /// });
+    ///
+    /// assert_eq!(10, history.epochs());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the data or configuration is invalid, see
+    /// [Model::try_fit_epochs].
+    pub fn fit_epochs<D: RemoveAxis>(
+        &mut self,
+        x: &Array<T, D>,
+        y: &Array2<T>,
+        config: FitConfig<T, D>,
+    ) -> History<T> {
+        self.fit_with_callbacks(x, y, config, &mut CallbackList::new())
+    }
+
+    /// Trains the model like [Model::fit_epochs].
+    ///
+    /// Fails with [Error::EmptyModel] if the model has no layers, with an
+    /// [Error::ShapeMismatch] if the training or validation data does not
+    /// match the inputs and outputs of the model and with an
+    /// [Error::InvalidHyperparameter] if the batch size is zero or the
+    /// validation split is outside of `[0, 1)`.
+    pub fn try_fit_epochs<D: RemoveAxis>(
+        &mut self,
+        x: &Array<T, D>,
+        y: &Array2<T>,
+        config: FitConfig<T, D>,
+    ) -> Result<History<T>> {
+        self.try_fit_with_callbacks(x, y, config, &mut CallbackList::new())
+    }
+
+    /// Trains the model like [Model::fit_epochs] and notifies the callbacks
+    /// about the training progress.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ndarray::arr2;
+    /// use robit::{
+    ///     callbacks::{CallbackList, Logger},
+    ///     initializers::RandomDistr,
+    ///     FitConfig, Model,
+    /// };
+    ///
+    /// let mut model: Model = Model::new(0.01);
+    /// model.add_layer((2, 1), RandomDistr::normal());
+    ///
+    /// let x = arr2(&[[0.0, 1.0], [1.0, 0.0]]);
+    /// let y = arr2(&[[1.0], [0.0]]);
+    ///
+    /// let mut callbacks = CallbackList::new();
+    /// callbacks.push(Logger::new());
+    ///
+    /// model.fit_with_callbacks(&x, &y, FitConfig::default(), &mut callbacks);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the data or configuration is invalid, see
+    /// [Model::try_fit_epochs].
+    pub fn fit_with_callbacks<D, C>(
+        &mut self,
+        x: &Array<T, D>,
+        y: &Array2<T>,
+        config: FitConfig<T, D>,
+        callbacks: &mut C,
+    ) -> History<T>
+    where
+        D: RemoveAxis,
+        C: Callback<T, A, L>,
+    {
+        self.try_fit_with_callbacks(x, y, config, callbacks)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Trains the model like [Model::fit_with_callbacks], failing like
+    /// [Model::try_fit_epochs] before the training starts if the data or
+    /// configuration is invalid.
+    pub fn try_fit_with_callbacks<D, C>(
+        &mut self,
+        x: &Array<T, D>,
+        y: &Array2<T>,
+        config: FitConfig<T, D>,
+        callbacks: &mut C,
+    ) -> Result<History<T>>
+    where
+        D: RemoveAxis,
+        C: Callback<T, A, L>,
+    {
+        self.check_data(x, y)?;
+
+        let (x_train, y_train, validation) = config.split(x, y)?;
+
+        if let Some((x_val, y_val)) = &validation {
+            self.check_input("validation input", x_val)?;
+            self.check_targets("validation targets", x_val, y_val)?;
+        }
+
+        let mut history = History::new();
+        let mut logs = Logs::new();
+
+        self.stop_training = false;
+        callbacks.on_train_begin(self, &logs);
+
+        for _ in 0..config.epochs {
+            if self.stop_training {
+                break;
+            }
+
+            let epoch = self.epoch;
+            callbacks.on_epoch_begin(epoch, self, &Logs::new());
+
+            logs = Logs::new();
+
+            let shuffled;
+            let (x_epoch, y_epoch) = if config.shuffle {
+                let indices = self.rng.permutation(x_train.len_of(Axis(0)));
+                shuffled = (
+                    x_train.select(Axis(0), &indices),
+                    y_train.select(Axis(0), &indices),
+                );
+                (&shuffled.0, &shuffled.1)
+            } else {
+                (&x_train, &y_train)
+            };
+
+            let learning_rate = self.current_learning_rate();
+            let loss = self.fit_epoch(x_epoch, y_epoch, learning_rate, callbacks);
+            self.epoch += 1;
+            history.loss.push(loss);
+            logs.insert("loss", loss);
+
+            if !config.metrics.is_empty() {
+                let y_pred = self.predict(&x_train);
+
+                for metric in config.metrics.iter() {
+                    let name = metric.name();
+                    let value = metric.compute(&y_train, &y_pred);
+                    history.push_metric(&name, value);
+                    logs.insert(&name, value);
+                }
+            }
+
+            if let Some((x_val, y_val)) = &validation {
+                let y_pred = self.predict(x_val);
+                let val_loss = self.loss.compute(y_val, &y_pred);
+                history.val_loss.push(val_loss);
+                logs.insert("val_loss", val_loss);
+
+                for metric in config.metrics.iter() {
+                    let name = format!("val_{}", metric.name());
+                    let value = metric.compute(y_val, &y_pred);
+                    history.push_metric(&name, value);
+                    logs.insert(&name, value);
+                }
+            }
+
+            callbacks.on_epoch_end(epoch, self, &logs);
+        }
+
+        callbacks.on_train_end(self, &logs);
+
+        Ok(history)
+    }
+
+    /// Runs a single pass over the training data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the data is invalid, see [Model::try_fit].
+    pub fn fit<D: Dimension>(&mut self, X: &Array<T, D>, Y: &Array2<T>) {
+        if let Err(err) = self.try_fit(X, Y) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Runs a single pass over the training data.
+    ///
+    /// Fails with [Error::EmptyModel] if the model has no layers, with an
+    /// [Error::ShapeMismatch] or [Error::InvalidInput] if the data does not
+    /// match the inputs and outputs of the model, see [Model::try_predict],
+    /// and with an [Error::InvalidHyperparameter] if the batch size is zero.
+    pub fn try_fit<D: Dimension>(&mut self, x: &Array<T, D>, y: &Array2<T>) -> Result<()> {
+        self.check_data(x, y)?;
+        self.fit_epoch(x, y, self.learning_rate, &mut CallbackList::new());
+
+        Ok(())
+    }
+
+    /// Runs a single pass over the training data and returns the mean loss of
+    /// the batches.
+    fn fit_epoch<D, C>(
+        &mut self,
+        x: &Array<T, D>,
+        y: &Array2<T>,
+        learning_rate: T,
+        callbacks: &mut C,
+    ) -> T
+    where
+        D: Dimension,
+        C: Callback<T, A, L>,
+    {
+        let n_samples = x.shape()[0];
+        let mut total_loss = T::zero();
+        let mut n_batches = 0;
+
         for i in (0..n_samples).step_by(self.batch_size) {
-            if (i + self.batch_size) >= n_samples {
-                continue;
+            let end = (i + self.batch_size).min(n_samples);
+
+            callbacks.on_batch_begin(n_batches, self, &Logs::new());
+
+            let x_batch = x.slice_axis(Axis(0), Slice::from(i..end)).to_owned();
+            let y_batch = y.slice(s![i..end, ..]).to_owned();
+
+            let mut rng = self.rng.clone();
+            let gradients = self.gradients(&x_batch, &y_batch, &mut rng);
+            self.rng = rng;
+
+            let loss = gradients.loss;
+            self.update(gradients, learning_rate);
+
+            let mut logs = Logs::new();
+            logs.insert("loss", loss);
+            callbacks.on_batch_end(n_batches, self, &logs);
+
+            total_loss = total_loss + loss;
+            n_batches += 1;
+
+            if self.stop_training {
+                break;
+            }
+        }
+
+        if n_batches == 0 {
+            return T::zero();
+        }
+
+        total_loss / T::from_usize(n_batches).unwrap()
+    }
+
+    /// Records the parameters on the tape, as values with gradient if
+    /// `trainable` is set.
+    fn record<'t>(&self, tape: &'t Tape<T>, trainable: bool) -> Recorded<'t, T> {
+        let record = |value: ArrayD<T>| {
+            if trainable {
+                tape.var(value)
+            } else {
+                tape.constant(value)
             }
+        };
+
+        Recorded {
+            weights: self
+                .weights
+                .iter()
+                .map(|w| record(w.clone().into_dyn()))
+                .collect(),
+            biases: self
+                .biases
+                .iter()
+                .map(|b| record(b.clone().into_dyn()))
+                .collect(),
+            layers: self
+                .layers
+                .iter()
+                .map(|(_, layer)| {
+                    layer
+                        .parameters()
+                        .into_iter()
+                        .cloned()
+                        .map(record)
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// Records the output of the model for the input `x` and returns it
+    /// together with the new state and the sparse rows of every layer added
+    /// with [Model::add]. Padding masks are passed on to the following
+    /// layers, see [Context::padding_mask].
+    fn forward<'t>(
+        &self,
+        x: Var<'t, T>,
+        parameters: &Recorded<'t, T>,
+        mode: Mode,
+        rng: &mut Rng,
+    ) -> (Var<'t, T>, States<T>, Vec<SparseRows>) {
+        let mut a = x;
+        let mut states = Vec::with_capacity(self.layers.len());
+        let mut sparse_rows = Vec::with_capacity(self.layers.len());
+        let mut padding_mask = None;
+
+        for node in self.nodes() {
+            a = match node {
+                // dense layers only change the last axis and keep the mask
+                Node::Dense(i) => self
+                    .activation
+                    .forward(a.matmul(parameters.weights[i]) + parameters.biases[i]),
+                Node::Layer(k) => {
+                    let layer = &self.layers[k].1;
+                    let mut context = Context::new(mode, &parameters.layers[k], rng)
+                        .with_padding_mask(padding_mask.take());
+                    let output = layer.forward(a, &mut context);
+                    sparse_rows.push(context.take_sparse_rows());
+                    padding_mask = context.take_padding_mask(layer.keeps_time_axis());
+                    states.push(context.into_state());
+                    output
+                }
+            };
+        }
+
+        (a, states, sparse_rows)
+    }
+
+    /// Returns the loss on the batch in training mode, e.g. with dropout.
+    pub(crate) fn training_loss<D: Dimension>(
+        &self,
+        x: &Array<T, D>,
+        y: &Array2<T>,
+        rng: &mut Rng,
+    ) -> T {
+        let tape = Tape::new();
+        let parameters = self.record(&tape, false);
+        let (output, ..) = self.forward(tape.constant(x.clone()), &parameters, Mode::Training, rng);
 
-            let x_batch = X.slice(s![i..i + self.batch_size, ..]);
-            let y_batch = Y.slice(s![i..i + self.batch_size, ..]);
-            let y_pred = self.predict(&x_batch.to_owned());
-            let error = self.loss.get(&y_batch.to_owned(), &y_pred);
+        self.loss.forward(tape.constant(y.clone()), output).value()[[]]
+    }
 
-            self.backpropagate(x_batch.to_owned(), error)
+    /// Returns the loss on the batch in training mode and its gradients with
+    /// respect to the parameters of every layer.
+    pub(crate) fn gradients<D: Dimension>(
+        &self,
+        x: &Array<T, D>,
+        y: &Array2<T>,
+        rng: &mut Rng,
+    ) -> BatchGradients<T> {
+        let tape = Tape::new();
+        let parameters = self.record(&tape, true);
+        let (output, states, sparse_rows) =
+            self.forward(tape.constant(x.clone()), &parameters, Mode::Training, rng);
 
-            // let y_pred = self.predict(x);
-            // let error = y_pred - &y_batch;
+        let loss = self.loss.forward(tape.constant(y.clone()), output);
+        let mut gradients = tape.backward(loss);
+        let mut take = |var| gradients.take(var).unwrap();
 
-            // self.backpropagate(&x, &error, 0.005);
+        BatchGradients {
+            loss: loss.value()[[]],
+            weights: parameters
+                .weights
+                .into_iter()
+                .map(|w| take(w).into_dimensionality().unwrap())
+                .collect(),
+            biases: parameters
+                .biases
+                .into_iter()
+                .map(|b| take(b).into_dimensionality().unwrap())
+                .collect(),
+            layers: parameters
+                .layers
+                .into_iter()
+                .map(|layer| layer.into_iter().map(&mut take).collect())
+                .collect(),
+            states,
+            sparse_rows,
         }
     }
 
-    fn backpropagate(&mut self, X: Array2<T>, error: Array1<T>) {
-        let mut activations = vec![X];
-        let mut zs = vec![];
+    /// Updates the parameters with the gradients, using the optimizer if
+    /// one is set, and the state of the layers.
+    ///
+    /// Only the sparse rows of a parameter are updated if it has any.
+    fn update(&mut self, gradients: BatchGradients<T>, learning_rate: T) {
+        let sparse_rows = gradients.sparse_rows;
 
-        // forward pass
-        for i in 0..self.weights.len() {
-            let z = activations[i].dot(&self.weights[i]) + &self.biases[i];
-            activations.push(self.activation.call(&z));
-            zs.push(z);
+        for ((_, layer), state) in self.layers.iter_mut().zip(gradients.states) {
+            if let Some(state) = state {
+                for (value, new) in layer.state_mut().into_iter().zip(state) {
+                    *value = new;
+                }
+            }
         }
 
-        // println!("{:?}", error.sum());
-        // println!("{:?}", error);
+        match &mut self.optimizer {
+            Some(optimizer) => {
+                // index of the first parameter of every layer in the order of
+                // the optimizer, after the weights and biases
+                let mut offset = self.weights.len() + self.biases.len();
+                let mut sparse = vec![];
+                for ((_, layer), rows) in self.layers.iter().zip(&sparse_rows) {
+                    let parameters = layer.parameters();
 
-        // backward pass
-        let mut delta = error * self.activation.call_deriv(&zs[zs.len() - 1]);
+                    for (j, rows) in rows {
+                        let parameter = parameters[*j];
+                        sparse.push(Rows {
+                            parameter: offset + j,
+                            len: parameter.len() / parameter.len_of(Axis(0)),
+                            rows,
+                        });
+                    }
+                    offset += parameters.len();
+                }
 
-        for i in (0..self.weights.len()).rev() {
-            let weights = self.weights[i].clone();
+                let mut parameters: Vec<&mut [T]> = self
+                    .weights
+                    .iter_mut()
+                    .map(|w| w.as_slice_mut().unwrap())
+                    .chain(self.biases.iter_mut().map(|b| b.as_slice_mut().unwrap()))
+                    .chain(
+                        self.layers
+                            .iter_mut()
+                            .flat_map(|(_, layer)| layer.parameters_mut())
+                            .map(|p| p.as_slice_mut().unwrap()),
+                    )
+                    .collect();
+                // the transposed products are not necessarily row-major
+                let delta_weights: Vec<_> = gradients
+                    .weights
+                    .iter()
+                    .map(|w| w.as_standard_layout())
+                    .collect();
+                let delta_layers: Vec<_> = gradients
+                    .layers
+                    .iter()
+                    .flatten()
+                    .map(|p| p.as_standard_layout())
+                    .collect();
+                let gradients: Vec<&[T]> = delta_weights
+                    .iter()
+                    .map(|w| w.as_slice().unwrap())
+                    .chain(gradients.biases.iter().map(|b| b.as_slice().unwrap()))
+                    .chain(delta_layers.iter().map(|p| p.as_slice().unwrap()))
+                    .collect();
+
+                optimizer.sparse_step(&mut parameters, &gradients, &sparse, learning_rate);
+            }
+            None => {
+                for i in 0..self.weights.len() {
+                    self.weights[i] -= &(&gradients.weights[i] * learning_rate);
+                    self.biases[i] -= &(&gradients.biases[i] * learning_rate);
+                }
 
-            let delta_bias = delta.mean_axis(Axis(0)).unwrap();
-            let delta_weight = activations[i].t().dot(&delta);
+                let layers = self.layers.iter_mut().zip(&gradients.layers);
 
-            self.weights[i] -= &(delta_weight * self.learning_rate);
-            self.biases[i] -= &(delta_bias * self.learning_rate);
+                for (((_, layer), gradients), sparse_rows) in layers.zip(&sparse_rows) {
+                    let parameters = layer.parameters_mut().into_iter().zip(gradients);
 
-            if i != 0 {
-                delta = delta.dot(&weights.t()) * self.activation.call_deriv(&zs[i - 1]);
+                    for (j, (p, g)) in parameters.enumerate() {
+                        match sparse_rows.iter().find(|(parameter, _)| *parameter == j) {
+                            Some((_, rows)) => {
+                                for &row in rows {
+                                    p.index_axis_mut(Axis(0), row)
+                                        .zip_mut_with(&g.index_axis(Axis(0), row), |p, &g| {
+                                            *p -= g * learning_rate
+                                        });
+                                }
+                            }
+                            None => *p -= &(g * learning_rate),
+                        }
+                    }
+                }
             }
         }
     }
@@ -136,6 +1321,861 @@ where
 
 #[cfg(test)]
 mod tests {
+    use ndarray::Array;
+
+    use super::*;
+    use crate::{
+        initializers::{Ones, RandomDistr},
+        layers::{
+            AlphaDropout, BatchNorm1d, Conv2d, Dropout, Embedding, Flatten, Gru,
+            LearnedPositionalEncoding, Lstm, MaxPool2d, MultiHeadAttention,
+            SinusoidalPositionalEncoding, TransformerEncoderBlock,
+        },
+        metrics::{MeanAbsoluteError, R2Score},
+        optimizers::{Adam, Sgd, StepDecay},
+    };
+
+    fn data() -> (Array2<f64>, Array2<f64>) {
+        let x = Array::linspace(0.0, 1.0, 128).into_shape((64, 2)).unwrap();
+        let y = x.sum_axis(Axis(1)).insert_axis(Axis(1)) * 0.5;
+
+        (x, y)
+    }
+
+    #[test]
+    fn test_fit_epochs() {
+        let (x, y) = data();
+        let mut model: Model = Model::new(0.01);
+        model.add_layer((2, 1), RandomDistr::normal());
+        model.set_batch_size(8);
+
+        let history = model.fit_epochs(
+            &x,
+            &y,
+            FitConfig {
+                epochs: 5,
+                validation_split: Some(0.25),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(5, history.epochs());
+        assert_eq!(5, history.val_loss.len());
+        assert!(history.loss.iter().all(|l| l.is_finite()));
+    }
+
+    #[test]
+    fn test_try_predict() {
+        let (x, _) = data();
+        let mut model: Model = Model::new(0.01);
+
+        assert!(matches!(model.try_predict(&x), Err(Error::EmptyModel)));
+
+        model.add_layer((3, 1), RandomDistr::normal());
+
+        assert!(matches!(
+            model.try_predict(&x),
+            Err(Error::ShapeMismatch { name: "input", .. })
+        ));
+    }
+
+    #[test]
+    fn test_try_evaluate() {
+        let (x, y) = data();
+        let mut model: Model = Model::new(0.01);
+
+        assert!(matches!(model.try_evaluate(&x, &y), Err(Error::EmptyModel)));
+
+        model.add_layer((2, 1), RandomDistr::normal());
+
+        assert_eq!(model.evaluate(&x, &y), model.try_evaluate(&x, &y).unwrap());
+        assert!(matches!(
+            model.try_evaluate(&x, &y.slice(s![..2, ..]).to_owned()),
+            Err(Error::ShapeMismatch {
+                name: "targets",
+                ..
+            })
+        ));
+        assert!(matches!(
+            model.try_evaluate(&x, &Array2::zeros((x.nrows(), 3))),
+            Err(Error::ShapeMismatch {
+                name: "targets",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_scalar_input() {
+        let x = ndarray::arr0(1.0);
+        let y = Array2::zeros((1, 2));
+
+        for dense in [false, true] {
+            let mut model: Model = Model::new(0.01);
+            if dense {
+                model.add_layer((1, 2), RandomDistr::normal());
+            } else {
+                model.add(Flatten);
+            }
+
+            assert!(matches!(
+                model.try_predict(&x),
+                Err(Error::ShapeMismatch { name: "input", found, .. }) if found.is_empty()
+            ));
+            assert!(matches!(
+                model.try_fit(&x, &y),
+                Err(Error::ShapeMismatch { name: "input", .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_try_add_layer() {
+        let mut model: Model = Model::new(0.01);
+
+        assert!(model.try_add_layer((0, 3), RandomDistr::normal()).is_err());
+
+        model.try_add_layer((2, 3), RandomDistr::normal()).unwrap();
+
+        assert!(matches!(
+            model.try_add_layer((4, 1), RandomDistr::normal()),
+            Err(Error::ShapeMismatch {
+                name: "layer",
+                ..
+            })
+        ));
+        assert_eq!(1, model.weights().len());
+    }
+
+    #[test]
+    fn test_try_add() {
+        let mut model: Model = Model::new(0.01);
+        model.try_add(Dropout::new(0.5).unwrap()).unwrap();
+        model.add_layer((2, 3), RandomDistr::normal());
+        model.add(AlphaDropout::new(0.1).unwrap());
+        model.add_layer((3, 1), RandomDistr::normal());
+
+        assert_eq!(
+            vec![
+                Node::Layer(0),
+                Node::Dense(0),
+                Node::Layer(1),
+                Node::Dense(1)
+            ],
+            model.nodes()
+        );
+        // the input shape is only known if the model starts with a dense layer
+        assert_eq!(None, model.output_shape());
+
+        let summary = model.summary();
+        let names: Vec<&str> = summary.layers.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(
+            vec!["dropout_0", "dense_0", "alpha_dropout_0", "dense_1"],
+            names
+        );
+        assert_eq!("AlphaDropout", summary.layers[2].kind);
+        assert_eq!(vec![3], summary.layers[2].output_shape);
+    }
+
+    #[test]
+    fn test_predict_with_dropout() {
+        let (x, y) = data();
+        let mut model: Model = Model::new(0.01);
+        model.add_layer((2, 8), Ones);
+        model.add(Dropout::new(0.5).unwrap());
+        model.add_layer((8, 1), Ones);
+
+        let mut without_dropout: Model = Model::new(0.01);
+        without_dropout.set_parameters(model.weights().to_vec(), model.biases().to_vec());
+
+        // dropout only applies during training
+        assert_eq!(without_dropout.predict(&x), model.predict(&x));
+        assert_eq!(model.predict(&x), model.predict(&x));
+
+        let mut rng = Rng::new(0);
+        assert_ne!(
+            model.training_loss(&x, &y, &mut rng),
+            model.training_loss(&x, &y, &mut rng)
+        );
+    }
+
+    #[test]
+    fn test_fit_with_dropout() {
+        let (x, y) = data();
+        let fit = || {
+            let mut model: Model = Model::new(0.001);
+            model.add_layer((2, 8), Ones);
+            model.add(Dropout::new(0.5).unwrap());
+            model.add_layer((8, 1), Ones);
+            model.set_seed(3);
+            model.fit_epochs(&x, &y, FitConfig::default());
+            model
+        };
+
+        let (first, second) = (fit(), fit());
+
+        assert_eq!(first.weights(), second.weights());
+        // the hidden units only differ because of the dropout masks
+        let w = &first.weights()[1];
+        assert!(w.iter().all(|v| v.is_finite()));
+        assert!(w.iter().any(|&v| v != w[(0, 0)]));
+    }
+
+    #[test]
+    fn test_try_fit() {
+        let (x, y) = data();
+        let mut model: Model = Model::new(0.01);
+        model.add_layer((2, 1), RandomDistr::normal());
+
+        let y_short = y.slice(s![..10, ..]).to_owned();
+        assert!(matches!(
+            model.try_fit(&x, &y_short),
+            Err(Error::ShapeMismatch {
+                name: "targets",
+                ..
+            })
+        ));
+
+        let config = FitConfig {
+            validation_data: Some((y.clone(), y.clone())),
+            ..Default::default()
+        };
+        assert!(matches!(
+            model.try_fit_epochs(&x, &y, config),
+            Err(Error::ShapeMismatch {
+                name: "validation input",
+                ..
+            })
+        ));
+
+        model.set_batch_size(0);
+        assert!(matches!(
+            model.try_fit(&x, &y),
+            Err(Error::InvalidHyperparameter {
+                name: "batch_size",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_load_malformed_file() {
+        let path = std::env::temp_dir().join("robit_test_malformed.txt");
+        std::fs::write(&path, "robit-model 1\nactivation\n").unwrap();
+        let result = Model::<Relu, MeanSquaredError, f64>::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(Error::Text(_))));
+        assert!(matches!(
+            Model::<Relu, MeanSquaredError, f64>::load(&path),
+            Err(Error::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let path = std::env::temp_dir().join("robit_test_missing.bin");
+
+        assert!(matches!(
+            Model::<Relu, MeanSquaredError, f64>::load_checkpoint(&path),
+            Err(Error::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_fit_epochs_without_validation() {
+        let (x, y) = data();
+        let mut model: Model = Model::new(0.01);
+        model.add_layer((2, 1), RandomDistr::normal());
+
+        let history = model.fit_epochs(&x, &y, FitConfig::default());
+
+        assert_eq!(1, history.epochs());
+        assert!(history.val_loss.is_empty());
+    }
+
+    #[test]
+    fn test_fit_epochs_with_metrics() {
+        let (x, y) = data();
+        let mut model: Model = Model::new(0.01);
+        model.add_layer((2, 1), RandomDistr::normal());
+
+        let history = model.fit_epochs(
+            &x,
+            &y,
+            FitConfig {
+                epochs: 3,
+                validation_split: Some(0.25),
+                metrics: vec![Box::new(MeanAbsoluteError), Box::new(R2Score)],
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(3, history.metric("mae").unwrap().len());
+        assert_eq!(3, history.metric("val_mae").unwrap().len());
+        assert_eq!(3, history.metric("val_r2").unwrap().len());
+        assert_eq!(None, history.metric("accuracy"));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let (x, _) = data();
+        let mut model: Model = Model::new(0.01);
+        model.add_layer((2, 3), RandomDistr::normal());
+        model.add_layer((3, 1), RandomDistr::normal());
+
+        let path = std::env::temp_dir().join("robit_test_save_and_load.txt");
+        model.save(&path).unwrap();
+        let loaded: Model = Model::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(model.predict(&x), loaded.predict(&x));
+    }
+
+    #[test]
+    fn test_save_and_load_checkpoint() {
+        let (x, _) = data();
+        let mut model: Model = Model::new(0.01);
+        model.add_layer((2, 3), RandomDistr::normal());
+        model.add_layer((3, 1), RandomDistr::normal());
+
+        let path = std::env::temp_dir().join("robit_test_save_and_load_checkpoint.bin");
+        model.save_checkpoint(&path).unwrap();
+        let loaded: Model = Model::load_checkpoint(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(model.predict(&x), loaded.predict(&x));
+    }
+
+    #[test]
+    fn test_batch_norm() {
+        let (x, y) = data();
+        let mut model: Model = Model::new(0.01);
+        model.add_layer((2, 4), RandomDistr::normal());
+        model.add(BatchNorm1d::new(4));
+        model.add_layer((4, 1), RandomDistr::normal());
+        model.set_batch_size(16);
+        model.fit_epochs(&x, &y, FitConfig::default());
+
+        let state = model.layers().next().unwrap().state();
+        assert!(state[1].iter().all(|&v| v != 1.0));
+
+        // the running statistics make predictions independent of the batch
+        let single = model.predict(&x.slice(s![..1, ..]).to_owned());
+        assert_eq!(model.predict(&x).row(0), single.row(0));
+
+        let path = std::env::temp_dir().join("robit_test_batch_norm.txt");
+        model.save(&path).unwrap();
+        let loaded: Model = Model::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(model.layer_tensors(), loaded.layer_tensors());
+        assert_eq!(model.predict(&x), loaded.predict(&x));
+
+        let path = std::env::temp_dir().join("robit_test_batch_norm.bin");
+        model.save_checkpoint(&path).unwrap();
+        let loaded: Model = Model::load_checkpoint(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(model.layer_tensors(), loaded.layer_tensors());
+    }
+
+    fn images() -> (Array<f64, ndarray::Ix4>, Array2<f64>) {
+        let x = Array::from_shape_fn((32, 1, 4, 4), |(n, _, i, j)| {
+            ((n * 16 + i * 4 + j) as f64 * 0.7).sin()
+        });
+        let y = x
+            .sum_axis(Axis(3))
+            .sum_axis(Axis(2))
+            .mapv(|v| 0.1 * v.abs());
+
+        (x, y)
+    }
+
+    fn conv_model() -> Model {
+        let mut model: Model = Model::new(0.001);
+        model.add(Conv2d::new((1, 2), (3, 3), Ones).unwrap().padding((1, 1)));
+        model.add(MaxPool2d::new((2, 2)));
+        model.add(Flatten);
+        model.add_layer((8, 1), Ones);
+        model.set_batch_size(8);
+        model
+    }
+
+    #[test]
+    fn test_conv() {
+        let (x, y) = images();
+        let mut model = conv_model();
+
+        assert_eq!(&[32, 1], model.predict(&x).shape());
+
+        let loss = model.evaluate(&x, &y);
+        let history = model.fit_epochs(
+            &x,
+            &y,
+            FitConfig {
+                epochs: 5,
+                validation_split: Some(0.25),
+                shuffle: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(5, history.val_loss.len());
+        assert!(model.evaluate(&x, &y) < loss);
+
+        let path = std::env::temp_dir().join("robit_test_conv.txt");
+        model.save(&path).unwrap();
+        let loaded: Model = Model::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
 
-    // todo ...
+        assert_eq!(model.predict(&x), loaded.predict(&x));
+    }
+
+    #[test]
+    fn test_output_not_a_matrix() {
+        let (x, y) = images();
+        let mut model: Model = Model::new(0.01);
+        model.add(Conv2d::new((1, 2), (3, 3), Ones).unwrap());
+
+        assert!(matches!(
+            model.try_predict(&x),
+            Err(Error::ShapeMismatch { name: "output", expected, .. }) if expected == vec![32, 8]
+        ));
+        assert!(model.try_fit(&x, &y).is_err());
+
+        let model = conv_model();
+        assert!(matches!(
+            model.try_predict(&x.index_axis(Axis(1), 0).to_owned()),
+            Err(Error::ShapeMismatch {
+                name: "conv_2d input",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_embedding() {
+        let x = Array::from_shape_fn((32, 3), |(i, j)| ((i + j) % 4) as f64);
+        let y = x.sum_axis(Axis(1)).insert_axis(Axis(1)) * 0.1;
+
+        for adam in [false, true] {
+            let mut model: Model = Model::new(0.01);
+            model.add(Embedding::new(6, 2, Ones).unwrap().padding_idx(0));
+            model.add(Flatten);
+            model.add_layer((6, 1), Ones);
+            model.set_batch_size(8);
+            if adam {
+                model.set_optimizer(Adam::new());
+            }
+
+            let loss = model.evaluate(&x, &y);
+            model.fit(&x, &y);
+            assert!(model.evaluate(&x, &y) < loss);
+
+            // only the looked up rows are updated, the padding row stays zero
+            let weight = model.layers().next().unwrap().parameters()[0]
+                .clone()
+                .into_dimensionality::<Ix2>()
+                .unwrap();
+            assert_eq!(&[0.0, 0.0], weight.row(0).as_slice().unwrap());
+            for row in 1..4 {
+                assert_ne!(&[1.0, 1.0], weight.row(row).as_slice().unwrap());
+            }
+            for row in 4..6 {
+                assert_eq!(&[1.0, 1.0], weight.row(row).as_slice().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_tokens() {
+        let mut model: Model = Model::new(0.01);
+        model.add(Embedding::new(6, 2, Ones).unwrap());
+        model.add(Flatten);
+        model.add_layer((6, 1), Ones);
+
+        let y = Array2::zeros((2, 1));
+        for token in [6.0, 1.5, -1.0, f64::NAN] {
+            let x = ndarray::arr2(&[[1.0, 2.0, 3.0], [4.0, 5.0, token]]);
+
+            assert!(matches!(
+                model.try_predict(&x),
+                Err(Error::InvalidInput {
+                    name: "embedding input",
+                    ..
+                })
+            ));
+            assert!(matches!(
+                model.try_fit(&x, &y),
+                Err(Error::InvalidInput { .. })
+            ));
+            assert!(matches!(
+                model.try_evaluate(&x, &y),
+                Err(Error::InvalidInput { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_recurrent() {
+        // the target is the mean of the sequence
+        let x = Array::from_shape_fn((32, 5, 1), |(n, t, _)| ((n * 5 + t) as f64 * 0.7).sin());
+        let y = x.mean_axis(Axis(1)).unwrap();
+
+        let mut model: Model = Model::new(0.05);
+        model.add(Lstm::new(1, 4, Ones).unwrap().return_sequences(true));
+        model.add(Gru::bidirectional(4, 2, Ones).unwrap());
+        model.add_layer((4, 1), Ones);
+        model.set_batch_size(8);
+
+        let loss = model.evaluate(&x, &y);
+        model.fit_epochs(
+            &x,
+            &y,
+            FitConfig {
+                epochs: 5,
+                ..Default::default()
+            },
+        );
+        assert!(model.evaluate(&x, &y) < loss);
+
+        let path = std::env::temp_dir().join("robit_test_recurrent.txt");
+        model.save(&path).unwrap();
+        let loaded: Model = Model::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(model.predict(&x), loaded.predict(&x));
+    }
+
+    #[test]
+    fn test_transformer() {
+        // the target is the first token
+        let x = Array::from_shape_fn((32, 4), |(n, t)| ((n * 3 + t * 5) % 7) as f64);
+        let y = x.slice(s![.., ..1]).mapv(|token| token * 0.1);
+
+        let mut model: Model = Model::new(0.01);
+        model.add(Embedding::new(7, 4, RandomDistr::normal()).unwrap());
+        model.add(SinusoidalPositionalEncoding);
+        model.add(LearnedPositionalEncoding::new(8, 4, Ones).unwrap());
+        model.add(
+            TransformerEncoderBlock::new(4, 2, 8, RandomDistr::normal())
+                .unwrap()
+                .causal(true)
+                .dropout(Dropout::new(0.1).unwrap()),
+        );
+        model.add(Flatten);
+        model.add_layer((16, 1), Ones);
+        model.set_batch_size(8);
+        model.set_optimizer(Adam::new());
+
+        let loss = model.evaluate(&x, &y);
+        model.fit_epochs(
+            &x,
+            &y,
+            FitConfig {
+                epochs: 5,
+                ..Default::default()
+            },
+        );
+        assert!(model.evaluate(&x, &y) < loss);
+
+        let path = std::env::temp_dir().join("robit_test_transformer.txt");
+        model.save(&path).unwrap();
+        let loaded: Model = Model::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(model.predict(&x), loaded.predict(&x));
+    }
+
+    #[test]
+    fn test_padding_mask() {
+        let mut model: Model = Model::new(0.01);
+        model.add(
+            Embedding::new(7, 4, RandomDistr::normal())
+                .unwrap()
+                .padding_idx(0),
+        );
+        model.add(MultiHeadAttention::new(4, 2, RandomDistr::normal()).unwrap());
+        model.add(TransformerEncoderBlock::new(4, 2, 8, RandomDistr::normal()).unwrap());
+        model.add(Flatten);
+
+        // the padding is ignored, so the outputs of the tokens do not depend
+        // on the length of the padding
+        let output = model.predict(&ndarray::arr2(&[[3.0, 5.0]]));
+        for padded in [vec![3.0, 5.0, 0.0], vec![3.0, 5.0, 0.0, 0.0]] {
+            let padded = Array2::from_shape_vec((1, padded.len()), padded).unwrap();
+            let padded_output = model.predict(&padded);
+
+            for (a, b) in output.iter().zip(padded_output.slice(s![.., ..8])) {
+                assert!((a - b).abs() < 1e-12);
+            }
+        }
+    }
+
+    /// Checks whether the padding mask reaches the layer.
+    struct MaskProbe {
+        masked: bool,
+    }
+
+    impl Layer for MaskProbe {
+        fn name(&self) -> &'static str {
+            "mask_probe"
+        }
+
+        fn config(&self) -> Vec<f64> {
+            vec![]
+        }
+
+        fn output_shape(&self, input_shape: &[usize]) -> Result<Vec<usize>> {
+            Ok(input_shape.to_vec())
+        }
+
+        fn keeps_time_axis(&self) -> bool {
+            true
+        }
+
+        fn forward<'t>(
+            &self,
+            input: Var<'t, f64>,
+            context: &mut Context<'_, 't, f64>,
+        ) -> Var<'t, f64> {
+            assert_eq!(context.padding_mask().is_some(), self.masked);
+            input
+        }
+    }
+
+    #[test]
+    fn test_padding_mask_without_time_axis() {
+        for return_sequences in [false, true] {
+            let mut model: Model = Model::new(0.01);
+            model.add(
+                Embedding::new(7, 4, RandomDistr::normal())
+                    .unwrap()
+                    .padding_idx(0),
+            );
+            model.add(MaskProbe { masked: true });
+            // without sequences the output [batch, units] has the shape of
+            // the mask [batch, time], but the time axis is gone
+            model.add(
+                Lstm::new(4, 3, RandomDistr::normal())
+                    .unwrap()
+                    .return_sequences(return_sequences),
+            );
+            model.add(Dropout::new(0.5).unwrap());
+            model.add(MaskProbe {
+                masked: return_sequences,
+            });
+            model.add(Flatten);
+
+            model.predict(&ndarray::arr2(&[[3.0, 5.0, 0.0]]));
+        }
+    }
+
+    #[test]
+    fn test_export_and_import_safetensors() {
+        let (x, _) = data();
+        let mut model: Model = Model::new(0.01);
+        model.add_layer((2, 3), RandomDistr::normal());
+        model.add_layer((3, 1), RandomDistr::normal());
+
+        let path = std::env::temp_dir().join("robit_test_safetensors.safetensors");
+        model.export_safetensors(&path).unwrap();
+
+        let mut imported: Model = Model::new(0.01);
+        imported.add_layer((2, 3), RandomDistr::normal());
+        imported.add_layer((3, 1), RandomDistr::normal());
+        imported.import_safetensors(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(model.predict(&x), imported.predict(&x));
+    }
+
+    fn resumable_model() -> Model {
+        let mut model: Model = Model::new(0.01);
+        model.add_layer((2, 3), RandomDistr::normal());
+        model.add_layer((3, 1), RandomDistr::normal());
+        model.set_batch_size(8);
+        model.set_optimizer(Adam::new());
+        model.set_scheduler(StepDecay::new(2, 0.5));
+        model.set_seed(7);
+        model
+    }
+
+    fn shuffled(epochs: usize) -> FitConfig {
+        FitConfig {
+            epochs,
+            shuffle: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_resume_training() {
+        let (x, y) = data();
+        let path = std::env::temp_dir().join("robit_test_resume_training.bin");
+
+        resumable_model().save_training_checkpoint(&path).unwrap();
+        let mut uninterrupted: Model = Model::load_checkpoint(&path).unwrap();
+        let mut resumed: Model = Model::load_checkpoint(&path).unwrap();
+
+        let history = uninterrupted.fit_epochs(&x, &y, shuffled(4));
+
+        resumed.fit_epochs(&x, &y, shuffled(2));
+        resumed.save_training_checkpoint(&path).unwrap();
+        let mut resumed: Model = Model::load_checkpoint(&path).unwrap();
+        let resumed_history = resumed.fit_epochs(&x, &y, shuffled(2));
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(4, resumed.epoch());
+        assert_eq!(history.loss[2..], resumed_history.loss[..]);
+        assert_eq!(uninterrupted.weights(), resumed.weights());
+        assert_eq!(uninterrupted.biases(), resumed.biases());
+    }
+
+    #[test]
+    fn test_plain_gradient_descent_matches_sgd() {
+        let (x, y) = data();
+        let mut plain: Model = Model::new(0.01);
+        plain.add_layer((2, 1), RandomDistr::normal());
+
+        let mut sgd: Model = Model::new(0.01);
+        sgd.set_parameters(plain.weights().to_vec(), plain.biases().to_vec());
+        sgd.set_optimizer(Sgd::new());
+
+        plain.fit_epochs(&x, &y, FitConfig::default());
+        sgd.fit_epochs(&x, &y, FitConfig::default());
+
+        assert_eq!(plain.weights(), sgd.weights());
+    }
+
+    #[test]
+    fn test_gradients() {
+        let (x, y) = data();
+        let mut model: Model = Model::new(0.01);
+        model.add_layer((2, 4), RandomDistr::normal_with(0.5, 0.2).unwrap());
+        model.add_layer((4, 3), RandomDistr::normal_with(0.5, 0.2).unwrap());
+        model.add_layer((3, 1), RandomDistr::normal_with(0.5, 0.2).unwrap());
+
+        let check = crate::gradcheck(&mut model, &x, &y, 1e-6).unwrap();
+
+        assert!(check.max_error() < 1e-6, "{:?}", check);
+    }
+
+    #[test]
+    fn test_export_onnx() {
+        let mut model: Model = Model::new(0.01);
+        model.add_layer((2, 3), RandomDistr::normal());
+        model.add_layer((3, 1), RandomDistr::normal());
+
+        let path = std::env::temp_dir().join("robit_test_export.onnx");
+        model.export_onnx(&path).unwrap();
+
+        let mut buffer = vec![];
+        write_onnx(&model, &mut buffer).unwrap();
+        let exported = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(buffer, exported);
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+        stop_after: Option<usize>,
+    }
+
+    impl Callback for Recorder {
+        fn on_train_begin(&mut self, _model: &mut Model, _logs: &Logs) {
+            self.events.push("train_begin".to_owned());
+        }
+
+        fn on_train_end(&mut self, _model: &mut Model, _logs: &Logs) {
+            self.events.push("train_end".to_owned());
+        }
+
+        fn on_epoch_begin(&mut self, epoch: usize, _model: &mut Model, _logs: &Logs) {
+            self.events.push(format!("epoch_begin {}", epoch));
+        }
+
+        fn on_epoch_end(&mut self, epoch: usize, model: &mut Model, logs: &Logs) {
+            assert!(logs.get("loss").is_some());
+            assert!(logs.get("val_loss").is_some());
+
+            self.events.push(format!("epoch_end {}", epoch));
+
+            if Some(epoch) == self.stop_after {
+                model.stop_training();
+            }
+        }
+
+        fn on_batch_begin(&mut self, batch: usize, _model: &mut Model, _logs: &Logs) {
+            self.events.push(format!("batch_begin {}", batch));
+        }
+
+        fn on_batch_end(&mut self, batch: usize, _model: &mut Model, logs: &Logs) {
+            assert!(logs.get("loss").is_some());
+
+            self.events.push(format!("batch_end {}", batch));
+        }
+    }
+
+    #[test]
+    fn test_fit_with_callbacks() {
+        let (x, y) = data();
+        let mut model: Model = Model::new(0.01);
+        model.add_layer((2, 1), RandomDistr::normal());
+        model.set_batch_size(16);
+
+        let mut recorder = Recorder::default();
+        model.fit_with_callbacks(
+            &x,
+            &y,
+            FitConfig {
+                epochs: 1,
+                validation_split: Some(0.25),
+                ..Default::default()
+            },
+            &mut recorder,
+        );
+
+        assert_eq!(
+            vec![
+                "train_begin",
+                "epoch_begin 0",
+                "batch_begin 0",
+                "batch_end 0",
+                "batch_begin 1",
+                "batch_end 1",
+                "batch_begin 2",
+                "batch_end 2",
+                "epoch_end 0",
+                "train_end"
+            ],
+            recorder.events
+        );
+    }
+
+    #[test]
+    fn test_stop_training() {
+        let (x, y) = data();
+        let mut model: Model = Model::new(0.01);
+        model.add_layer((2, 1), RandomDistr::normal());
+
+        let mut recorder = Recorder {
+            stop_after: Some(2),
+            ..Default::default()
+        };
+        let history = model.fit_with_callbacks(
+            &x,
+            &y,
+            FitConfig {
+                epochs: 10,
+                validation_split: Some(0.25),
+                ..Default::default()
+            },
+            &mut recorder,
+        );
+
+        assert_eq!(3, history.epochs());
+    }
 }
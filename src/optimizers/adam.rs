@@ -0,0 +1,211 @@
+use num_traits::Float;
+
+use super::{indices, zeros, Optimizer, OptimizerState, Rows};
+
+/// Adam optimizer, see [Kingma & Ba (2014)](https://arxiv.org/abs/1412.6980).
+///
+/// Keeps exponential moving averages of the gradients and the squared
+/// gradients, corrects their bias towards zero and scales the update of every
+/// value by the inverse root of its second moment.
+///
+/// # Examples
+///
+/// ```
+/// use robit::optimizers::Adam;
+///
+/// let optimizer: Adam = Adam::new().betas(0.9, 0.99).epsilon(1e-7);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adam<T = f64> {
+    beta1: T,
+    beta2: T,
+    epsilon: T,
+    steps: u64,
+    first_moments: Vec<Vec<T>>,
+    second_moments: Vec<Vec<T>>,
+}
+
+impl<T: Float> Adam<T> {
+    /// Creates the optimizer with `beta1 = 0.9`, `beta2 = 0.999` and
+    /// `epsilon = 1e-8`.
+    pub fn new() -> Self {
+        Self {
+            beta1: T::from(0.9).unwrap(),
+            beta2: T::from(0.999).unwrap(),
+            epsilon: T::from(1e-8).unwrap(),
+            steps: 0,
+            first_moments: vec![],
+            second_moments: vec![],
+        }
+    }
+
+    /// Sets the decay rates of the first and second moment estimates.
+    pub fn betas(mut self, beta1: T, beta2: T) -> Self {
+        self.beta1 = beta1;
+        self.beta2 = beta2;
+        self
+    }
+
+    /// Sets the term added to the denominator for numerical stability.
+    pub fn epsilon(mut self, epsilon: T) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    /// Returns `None` unless the state has no moments or the first and
+    /// second moments of every parameter.
+    pub(crate) fn from_state(mut state: OptimizerState<T>, n_parameters: usize) -> Option<Self> {
+        let [beta1, beta2, epsilon] = state.hyperparameters[..] else {
+            return None;
+        };
+
+        if !state.buffers.is_empty() && state.buffers.len() != 2 * n_parameters {
+            return None;
+        }
+
+        let second_moments = state.buffers.split_off(state.buffers.len() / 2);
+
+        Some(Self {
+            beta1,
+            beta2,
+            epsilon,
+            steps: state.steps,
+            first_moments: state.buffers,
+            second_moments,
+        })
+    }
+}
+
+impl<T: Float> Default for Adam<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> Optimizer<T> for Adam<T> {
+    fn name(&self) -> &'static str {
+        "adam"
+    }
+
+    fn step(&mut self, parameters: &mut [&mut [T]], gradients: &[&[T]], learning_rate: T) {
+        self.sparse_step(parameters, gradients, &[], learning_rate);
+    }
+
+    /// Only updates the given rows and their moments, the bias correction
+    /// uses the number of steps of the optimizer.
+    fn sparse_step(
+        &mut self,
+        parameters: &mut [&mut [T]],
+        gradients: &[&[T]],
+        sparse: &[Rows<'_>],
+        learning_rate: T,
+    ) {
+        if self.first_moments.is_empty() {
+            self.first_moments = zeros(parameters);
+            self.second_moments = zeros(parameters);
+        }
+
+        self.steps += 1;
+
+        let steps = T::from(self.steps).unwrap();
+        let correction1 = T::one() - self.beta1.powf(steps);
+        let correction2 = T::one() - self.beta2.powf(steps);
+
+        for (i, (p, g)) in parameters.iter_mut().zip(gradients).enumerate() {
+            let m = &mut self.first_moments[i];
+            let v = &mut self.second_moments[i];
+
+            for j in indices(sparse, i, p.len()) {
+                let g = g[j];
+                m[j] = self.beta1 * m[j] + (T::one() - self.beta1) * g;
+                v[j] = self.beta2 * v[j] + (T::one() - self.beta2) * g * g;
+
+                let m_hat = m[j] / correction1;
+                let v_hat = v[j] / correction2;
+
+                p[j] = p[j] - learning_rate * m_hat / (v_hat.sqrt() + self.epsilon);
+            }
+        }
+    }
+
+    fn state(&self) -> OptimizerState<T> {
+        OptimizerState {
+            hyperparameters: vec![self.beta1, self.beta2, self.epsilon],
+            steps: self.steps,
+            buffers: self
+                .first_moments
+                .iter()
+                .chain(self.second_moments.iter())
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_step() {
+        let mut optimizer = Adam::new();
+        let mut p = [1.0, 1.0];
+
+        optimizer.step(&mut [&mut p], &[&[2.0, -0.5]], 0.1);
+
+        // the bias-corrected first step moves every value by the learning rate
+        assert!((p[0] - 0.9).abs() < 1e-6);
+        assert!((p[1] - 1.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_sparse_step() {
+        let mut sparse = Adam::new();
+        let mut dense = Adam::new();
+        let (mut p, mut q) = ([1.0; 6], [1.0; 6]);
+        let rows = Rows {
+            parameter: 0,
+            len: 2,
+            rows: &[0, 2],
+        };
+
+        for optimizer in [&mut sparse, &mut dense] {
+            optimizer.step(&mut [&mut [0.0; 6]], &[&[1.0; 6]], 0.1);
+        }
+        let g = [0.5, -0.5, 0.0, 0.0, 2.0, 1.0];
+        sparse.sparse_step(&mut [&mut p], &[&g], &[rows], 0.1);
+        dense.step(&mut [&mut q], &[&g], 0.1);
+
+        // the updated rows match a dense step, the middle row and its
+        // moments are unchanged
+        assert_eq!([q[0], q[1], 1.0, 1.0, q[4], q[5]], p);
+        let (sparse, dense) = (sparse.state().buffers, dense.state().buffers);
+        assert_eq!(sparse[0][..2], dense[0][..2]);
+        assert_eq!([1.0 - 0.9; 2], sparse[0][2..4]);
+        assert_ne!(sparse[0][2..4], dense[0][2..4]);
+    }
+
+    #[test]
+    fn test_state() {
+        let mut optimizer = Adam::new();
+        optimizer.step(
+            &mut [&mut [1.0], &mut [2.0, 3.0]],
+            &[&[1.0], &[1.0, 2.0]],
+            0.1,
+        );
+
+        let state = optimizer.state();
+
+        assert_eq!(1, state.steps);
+        assert_eq!(4, state.buffers.len());
+        assert_eq!(2, state.buffers[1].len());
+        assert_eq!(2, state.buffers[3].len());
+        assert_eq!(optimizer, Adam::from_state(state.clone(), 2).unwrap());
+        assert_eq!(None, Adam::from_state(state.clone(), 1));
+        assert_eq!(None, Adam::from_state(state, 4));
+        assert_eq!(
+            Adam::<f64>::new(),
+            Adam::from_state(Adam::new().state(), 2).unwrap()
+        );
+    }
+}
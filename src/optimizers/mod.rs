@@ -1,148 +1,218 @@
+//! Optimizers that update the parameters of a [Model](crate::Model) from
+//! their gradients, and schedules for the learning rate.
+//!
+//! # Examples
+//!
+//! ```
+//! use robit::{
+//!     initializers::RandomDistr,
+//!     optimizers::{Adam, StepDecay},
+//!     Model,
+//! };
+//!
+//! let mut model: Model = Model::new(0.01);
+//! model.add_layer((2, 1), RandomDistr::normal());
+//! model.set_optimizer(Adam::new());
+//! model.set_scheduler(StepDecay::new(10, 0.5));
+//! ```
+
+mod adam;
+mod schedulers;
 mod sgd;
 
-pub use sgd::SGD;
+pub use adam::Adam;
+pub use schedulers::{ExponentialDecay, Scheduler, StepDecay};
+pub use sgd::Sgd;
 
-use crate::{layers::Layer, losses::Loss, Model};
+use std::ops::Range;
 
+use num_traits::Float;
+
+/// Updates parameters from their gradients.
+///
+/// The parameters are passed as flat slices in the same order on every step:
+/// the weights (row-major) of every dense layer, the biases of every dense
+/// layer and the [parameters](crate::layers::Layer::parameters) of the other
+/// layers (row-major).
 pub trait Optimizer<T = f64> {
-    fn minimize<const M: usize, const N: usize, L>(
-        &self,
-        weights: &mut Vec<T>,
-        biases: &mut Vec<T>,
-        training_data: &Vec<([T; M], [T; N])>,
-        layer: &dyn Layer<M, N, T>,
-        loss: &L,
+    /// Name that identifies the optimizer in checkpoints.
+    fn name(&self) -> &'static str;
+
+    /// Applies a single update for a batch.
+    fn step(&mut self, parameters: &mut [&mut [T]], gradients: &[&[T]], learning_rate: T);
+
+    /// Applies a single update like [Optimizer::step] where the parameters
+    /// listed in `sparse` only received gradients in some rows, e.g. the
+    /// looked up rows of an embedding.
+    ///
+    /// The other rows of these parameters must keep their values. [Sgd] and
+    /// [Adam] only update the given rows and their buffers, like lazy
+    /// optimizers, so the momentum of the other rows does not decay. The
+    /// default implementation applies [Optimizer::step] and restores the
+    /// other rows afterwards, the buffers of the optimizer stay dense.
+    fn sparse_step(
+        &mut self,
+        parameters: &mut [&mut [T]],
+        gradients: &[&[T]],
+        sparse: &[Rows<'_>],
+        learning_rate: T,
     ) where
-        L: Loss<T>;
+        T: Copy,
+    {
+        let previous: Vec<Vec<T>> = sparse
+            .iter()
+            .map(|rows| parameters[rows.parameter].to_vec())
+            .collect();
+
+        self.step(parameters, gradients, learning_rate);
+
+        for (rows, mut previous) in sparse.iter().zip(previous) {
+            let parameter = &mut *parameters[rows.parameter];
+
+            for range in rows.ranges() {
+                previous[range.clone()].copy_from_slice(&parameter[range]);
+            }
+            parameter.copy_from_slice(&previous);
+        }
+    }
+
+    /// Returns the hyperparameters and the accumulated state, e.g. to store
+    /// them in a checkpoint.
+    fn state(&self) -> OptimizerState<T>;
+}
+
+/// Rows of a parameter that received gradients in a step, see
+/// [Optimizer::sparse_step].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rows<'a> {
+    /// Index of the parameter in the order of [Optimizer::step].
+    pub parameter: usize,
+
+    /// Number of values of a row.
+    pub len: usize,
+
+    /// Indices of the rows.
+    pub rows: &'a [usize],
+}
+
+impl Rows<'_> {
+    /// Ranges of the values of the rows in the flat parameter.
+    pub fn ranges(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        self.rows
+            .iter()
+            .map(|row| row * self.len..(row + 1) * self.len)
+    }
+}
+
+/// Hyperparameters and accumulated state of an [Optimizer].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptimizerState<T = f64> {
+    pub hyperparameters: Vec<T>,
+
+    /// Number of steps taken.
+    pub steps: u64,
+
+    /// Buffers of the optimizer, one group per kind of buffer with one buffer
+    /// per parameter in the order of [Optimizer::step], e.g. the first and
+    /// second moments of [Adam]. Empty before the first step.
+    pub buffers: Vec<Vec<T>>,
+}
+
+/// Recreates the optimizer with the given name from its state, returns `None`
+/// if the name is unknown or the state does not belong to the optimizer,
+/// e.g. because it does not have the buffers of `n_parameters` parameters.
+pub(crate) fn optimizer_from_state<T>(
+    name: &str,
+    state: OptimizerState<T>,
+    n_parameters: usize,
+) -> Option<Box<dyn Optimizer<T>>>
+where
+    T: Float + 'static,
+{
+    match name {
+        "sgd" => Some(Box::new(Sgd::from_state(state, n_parameters)?)),
+        "adam" => Some(Box::new(Adam::from_state(state, n_parameters)?)),
+        _ => None,
+    }
+}
+
+/// Recreates the scheduler with the given name from its parameters, returns
+/// `None` if the name is unknown or the parameters do not belong to the
+/// scheduler.
+pub(crate) fn scheduler_from_parameters<T>(
+    name: &str,
+    parameters: &[T],
+) -> Option<Box<dyn Scheduler<T>>>
+where
+    T: Float + 'static,
+{
+    match name {
+        "step_decay" => Some(Box::new(StepDecay::from_parameters(parameters)?)),
+        "exponential_decay" => Some(Box::new(ExponentialDecay::from_parameters(parameters)?)),
+        _ => None,
+    }
 }
 
-// pub struct SGD<R, T> {
-//     learning_rate: f64,
-//     mini_batch_size: usize,
-//     rng: R,
-//     _phantom: PhantomData<T>,
-// }
-
-// impl<R, T> SGD<R, T> {
-//     pub fn new(rng: R, learning_rate: f64, mini_batch_size: usize) -> Self {
-//         Self {
-//             learning_rate,
-//             mini_batch_size,
-//             rng,
-//             _phantom: PhantomData,
-//         }
-//     }
-// }
-
-// impl<R, T, const N: usize> OptIter for SGD<R, T, N> {
-//     type Iter = SGDIterator<R, T, N>;
-
-//     fn into_iter(self, training_data: [T; N]) -> SGDIterator<R, T, N> {
-//         SGDIterator::new(self.learning_rate, self.rng, training_data)
-//     }
-// }
-
-// pub struct SGDIterator<R, T, const N: usize> {
-//     rng: R,
-//     training_data: [T; N],
-//     epoch: usize,
-//     learning_rate: f64,
-//     mini_batch_size: usize,
-// }
-
-// impl<R: Rng, T, const N: usize> SGDIterator<R, T, N> {
-//     pub fn new(rng: R, training_data: [T; N], learning_rate: f64, mini_batch_size: usize) -> Self {
-//         Self {
-//             training_data,
-//             rng,
-//             learning_rate,
-//             mini_batch_size,
-//             epoch: 0,
-//         }
-//     }
-
-//     fn update_mini_batch(&mut self, mini_batch: &[T]) {
-//         let nabla_b = [0; 10];
-//         let nabla_w = [0; 10];
-
-//         for t in mini_batch {
-//             let (delta_nabla_b, delta_nabla_w) = self.backprop(t);
-//         }
-//     }
-
-//     fn backprop(&mut self, training_data: &T) {
-//         let biases = [0; 10];
-//         let weights = [0; 10];
-
-//         let nabla_b = [0; 10];
-//         let nabla_w = [0; 10];
-
-//         let activation = training_data;
-//         let activations = [training_data];
-//         let mut zs = [0; N];
-
-//         let activations = biases
-//             .zip(weights)
-//             .map(|(bias, weights)| {
-//                 let z = weights * activation + bias;
-//                 zs.push(z);
-//                 z
-//             })
-//             .map(|z| z) // activation e.g.: sigmoid(z)
-//             .collect();
-
-//         let delta = self.cost_deriviative(activations, y);
-//     }
-// }
-
-// impl<R: Rng, T, const N: usize> Iterator for SGDIterator<R, T, N> {
-//     type Item = ();
-
-//     fn next(&mut self) -> Option<()> {
-//         self.epoch += 1;
-
-//         self.training_data.shuffle(&mut self.rng);
-
-//         for mini_batch in self.training_data.chunks(self.mini_batch_size) {
-//             self.update_mini_batch(mini_batch);
-//         }
-
-//         None
-//     }
-// }
-
-// impl<R: Rng, U> Optimizer for SGD<R, U> {
-//     fn run<T, const N: usize>(&mut self, mut training_data: [T; N], test_data: Option<DVector<T>>) {
-//         let epochs = 5;
-//         let mini_batch_size = 10;
-//         let n_train = training_data.len();
-//         // let n =
-
-//         for j in 0..epochs {
-//             println!("Epoch {}: Done", j);
-//         }
-//     }
-// }
-
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-//     use rand::thread_rng;
-//     use rust_decimal_macros::dec;
-
-//     #[test]
-//     fn test_add() {
-//         let y_true = DVector::from_vec(vec![dec!(0.5), dec!(0.4)]);
-//         let y_pred = DVector::from_vec(vec![dec!(0.6), dec!(0.4)]);
-
-//         let training_data = [0.5, 0.2];
-
-//         let mut optimizer = SGD::new(0.001, thread_rng(), 3);
-
-//         let foo = optimizer.run(training_data, None);
-
-//         dbg!(foo);
-
-//         assert_eq!(true, false);
-//     }
-// }
+/// Indices of the values of parameter `i` that are updated in a step, the
+/// values of the listed rows if the parameter is sparse and all otherwise.
+fn indices<'a>(
+    sparse: &'a [Rows<'_>],
+    i: usize,
+    len: usize,
+) -> Box<dyn Iterator<Item = usize> + 'a> {
+    match sparse.iter().find(|rows| rows.parameter == i) {
+        Some(rows) => Box::new(rows.ranges().flatten()),
+        None => Box::new(0..len),
+    }
+}
+
+/// Allocates one zeroed buffer per parameter.
+fn zeros<T: Float>(parameters: &[&mut [T]]) -> Vec<Vec<T>> {
+    parameters
+        .iter()
+        .map(|p| vec![T::zero(); p.len()])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Optimizer that moves every value by one, like a large momentum.
+    struct Drift;
+
+    impl Optimizer for Drift {
+        fn name(&self) -> &'static str {
+            "drift"
+        }
+
+        fn step(&mut self, parameters: &mut [&mut [f64]], _: &[&[f64]], _: f64) {
+            parameters
+                .iter_mut()
+                .for_each(|p| p.iter_mut().for_each(|v| *v += 1.0));
+        }
+
+        fn state(&self) -> OptimizerState {
+            OptimizerState {
+                hyperparameters: vec![],
+                steps: 0,
+                buffers: vec![],
+            }
+        }
+    }
+
+    #[test]
+    fn test_default_sparse_step() {
+        let (mut p, mut q) = ([0.0; 6], [0.0; 2]);
+        let rows = Rows {
+            parameter: 0,
+            len: 2,
+            rows: &[1],
+        };
+
+        Drift.sparse_step(&mut [&mut p, &mut q], &[&[0.0; 6], &[0.0; 2]], &[rows], 0.1);
+
+        assert_eq!([0.0, 0.0, 1.0, 1.0, 0.0, 0.0], p);
+        assert_eq!([1.0, 1.0], q);
+    }
+}
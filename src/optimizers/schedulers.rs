@@ -0,0 +1,135 @@
+use num_traits::Float;
+
+/// Schedule of the learning rate over the epochs of a training run.
+pub trait Scheduler<T = f64> {
+    /// Name that identifies the scheduler in checkpoints.
+    fn name(&self) -> &'static str;
+
+    /// Returns the learning rate of the epoch, counted from zero.
+    fn learning_rate(&self, base: T, epoch: usize) -> T;
+
+    /// Returns the parameters of the schedule, e.g. to store them in a
+    /// checkpoint.
+    fn parameters(&self) -> Vec<T>;
+}
+
+/// Multiplies the learning rate by `gamma` every `step_size` epochs.
+///
+/// # Examples
+///
+/// ```
+/// use robit::optimizers::{Scheduler, StepDecay};
+///
+/// let scheduler = StepDecay::new(2, 0.5);
+///
+/// assert_eq!(0.1, scheduler.learning_rate(0.1, 1));
+/// assert_eq!(0.05, scheduler.learning_rate(0.1, 2));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepDecay<T = f64> {
+    step_size: usize,
+    gamma: T,
+}
+
+impl<T: Float> StepDecay<T> {
+    pub fn new(step_size: usize, gamma: T) -> Self {
+        assert!(step_size > 0, "step_size must be positive");
+
+        Self { step_size, gamma }
+    }
+
+    pub(crate) fn from_parameters(parameters: &[T]) -> Option<Self> {
+        match parameters {
+            [step_size, gamma] => Some(Self {
+                step_size: step_size.to_usize().filter(|s| *s > 0)?,
+                gamma: *gamma,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl<T: Float> Scheduler<T> for StepDecay<T> {
+    fn name(&self) -> &'static str {
+        "step_decay"
+    }
+
+    fn learning_rate(&self, base: T, epoch: usize) -> T {
+        base * self.gamma.powi((epoch / self.step_size) as i32)
+    }
+
+    fn parameters(&self) -> Vec<T> {
+        vec![T::from(self.step_size).unwrap(), self.gamma]
+    }
+}
+
+/// Multiplies the learning rate by `gamma` every epoch.
+///
+/// # Examples
+///
+/// ```
+/// use robit::optimizers::{ExponentialDecay, Scheduler};
+///
+/// let scheduler = ExponentialDecay::new(0.5);
+///
+/// assert_eq!(0.025, scheduler.learning_rate(0.1, 2));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExponentialDecay<T = f64> {
+    gamma: T,
+}
+
+impl<T: Float> ExponentialDecay<T> {
+    pub fn new(gamma: T) -> Self {
+        Self { gamma }
+    }
+
+    pub(crate) fn from_parameters(parameters: &[T]) -> Option<Self> {
+        match parameters {
+            [gamma] => Some(Self { gamma: *gamma }),
+            _ => None,
+        }
+    }
+}
+
+impl<T: Float> Scheduler<T> for ExponentialDecay<T> {
+    fn name(&self) -> &'static str {
+        "exponential_decay"
+    }
+
+    fn learning_rate(&self, base: T, epoch: usize) -> T {
+        base * self.gamma.powi(epoch as i32)
+    }
+
+    fn parameters(&self) -> Vec<T> {
+        vec![self.gamma]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_decay() {
+        let scheduler = StepDecay::new(3, 0.1);
+
+        assert_eq!(1.0, scheduler.learning_rate(1.0, 2));
+        assert!((scheduler.learning_rate(1.0, 6) - 0.01).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_from_parameters() {
+        let scheduler = StepDecay::new(3, 0.1);
+
+        assert_eq!(
+            Some(scheduler.clone()),
+            StepDecay::from_parameters(&scheduler.parameters())
+        );
+        assert_eq!(None, StepDecay::from_parameters(&[0.0, 0.1]));
+        assert_eq!(
+            Some(ExponentialDecay::new(0.9)),
+            ExponentialDecay::from_parameters(&[0.9])
+        );
+    }
+}
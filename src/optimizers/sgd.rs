@@ -1,85 +1,185 @@
-use std::{
-    fmt::Debug,
-    ops::{Add, AddAssign, Div, Mul, SubAssign},
-};
+use num_traits::Float;
 
-use num_traits::{Signed, Zero};
+use super::{indices, zeros, Optimizer, OptimizerState, Rows};
 
-use crate::{layers::Layer, losses::Loss};
+/// Stochastic gradient descent with optional momentum.
+///
+/// Without momentum a parameter `p` with gradient `g` is updated to
+/// `p - learning_rate * g`. With momentum `m` a velocity `v = m * v + g` is
+/// accumulated and the parameter is updated to `p - learning_rate * v`.
+///
+/// # Examples
+///
+/// ```
+/// use robit::optimizers::Sgd;
+///
+/// let optimizer: Sgd = Sgd::new().momentum(0.9);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sgd<T = f64> {
+    momentum: T,
+    steps: u64,
+    velocities: Vec<Vec<T>>,
+}
+
+impl<T: Float> Sgd<T> {
+    pub fn new() -> Self {
+        Self {
+            momentum: T::zero(),
+            steps: 0,
+            velocities: vec![],
+        }
+    }
 
-use super::Optimizer;
+    /// Sets the momentum factor, `0` disables momentum.
+    pub fn momentum(mut self, momentum: T) -> Self {
+        self.momentum = momentum;
+        self
+    }
 
-pub struct SGD<T = f64> {
-    learning_rate: T,
+    /// Returns `None` unless the state has no velocities or one per
+    /// parameter.
+    pub(crate) fn from_state(state: OptimizerState<T>, n_parameters: usize) -> Option<Self> {
+        if !state.buffers.is_empty() && state.buffers.len() != n_parameters {
+            return None;
+        }
+
+        match state.hyperparameters[..] {
+            [momentum] => Some(Self {
+                momentum,
+                steps: state.steps,
+                velocities: state.buffers,
+            }),
+            _ => None,
+        }
+    }
 }
 
-impl<T> Optimizer<T> for SGD<T>
-where
-    T: Copy
-        + Zero
-        + AddAssign
-        + SubAssign
-        + From<i32>
-        + Div<T, Output = T>
-        + Mul<Output = T>
-        + Debug
-        + Signed,
-{
-    fn minimize<const M: usize, const N: usize, L>(
-        &self,
-        weights: &mut Vec<T>,
-        biases: &mut Vec<T>,
-        training_data: &Vec<([T; M], [T; N])>,
-        layer: &dyn Layer<M, N, T>,
-        loss: &L,
-    ) where
-        L: Loss<T>,
-    {
-        let n_samples = training_data.len();
-
-        training_data.chunks(16).into_iter().for_each(|batch| {
-            let mut error = T::zero();
-
-            for (x_train, y_train) in batch {
-                let y_pred = layer.predict(weights, biases, x_train);
-                error += loss.get(y_train, &y_pred).abs();
-            }
+impl<T: Float> Default for Sgd<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-            let avg_error: T = error / T::from(16);
+impl<T: Float> Optimizer<T> for Sgd<T> {
+    fn name(&self) -> &'static str {
+        "sgd"
+    }
 
-            weights
-                .iter_mut()
-                .for_each(|w| *w += avg_error * self.learning_rate);
-            biases
-                .iter_mut()
-                .for_each(|b| *b += avg_error * self.learning_rate);
+    fn step(&mut self, parameters: &mut [&mut [T]], gradients: &[&[T]], learning_rate: T) {
+        self.sparse_step(parameters, gradients, &[], learning_rate);
+    }
 
-            let mut error = T::zero();
+    /// Only updates the given rows and their velocities.
+    fn sparse_step(
+        &mut self,
+        parameters: &mut [&mut [T]],
+        gradients: &[&[T]],
+        sparse: &[Rows<'_>],
+        learning_rate: T,
+    ) {
+        self.steps += 1;
 
-            for (x_train, y_train) in batch {
-                let y_pred = layer.predict(weights, biases, x_train);
-                error += loss.get(y_train, &y_pred).abs();
+        if self.momentum == T::zero() {
+            for (i, (p, g)) in parameters.iter_mut().zip(gradients).enumerate() {
+                for j in indices(sparse, i, p.len()) {
+                    p[j] = p[j] - g[j] * learning_rate;
+                }
             }
 
-            let avg_error_2: T = error / T::from(16);
+            return;
+        }
 
-            println!("improved: {:?}", avg_error - avg_error_2);
-        });
+        if self.velocities.is_empty() {
+            self.velocities = zeros(parameters);
+        }
+
+        for (i, (p, g)) in parameters.iter_mut().zip(gradients).enumerate() {
+            let v = &mut self.velocities[i];
+
+            for j in indices(sparse, i, p.len()) {
+                v[j] = self.momentum * v[j] + g[j];
+                p[j] = p[j] - learning_rate * v[j];
+            }
+        }
     }
-}
 
-impl Default for SGD<f64> {
-    fn default() -> Self {
-        Self {
-            learning_rate: 0.0025,
+    fn state(&self) -> OptimizerState<T> {
+        OptimizerState {
+            hyperparameters: vec![self.momentum],
+            steps: self.steps,
+            buffers: self.velocities.clone(),
         }
     }
 }
 
-impl Default for SGD<f32> {
-    fn default() -> Self {
-        Self {
-            learning_rate: 0.0005,
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step() {
+        let mut optimizer = Sgd::new();
+        let mut p = [1.0, 2.0];
+
+        optimizer.step(&mut [&mut p], &[&[0.5, -1.0]], 0.1);
+
+        assert_eq!([0.95, 2.1], p);
+        assert!(optimizer.state().buffers.is_empty());
+    }
+
+    #[test]
+    fn test_momentum() {
+        let mut optimizer = Sgd::new().momentum(0.5);
+        let mut p = [1.0];
+
+        optimizer.step(&mut [&mut p], &[&[1.0]], 0.1);
+        optimizer.step(&mut [&mut p], &[&[1.0]], 0.1);
+
+        // v = 1, then v = 0.5 * 1 + 1
+        assert!((p[0] - (1.0 - 0.1 - 0.15)).abs() < 1e-12);
+        assert_eq!(vec![vec![1.5]], optimizer.state().buffers);
+        assert_eq!(2, optimizer.state().steps);
+    }
+
+    #[test]
+    fn test_sparse_step() {
+        let mut optimizer = Sgd::new().momentum(0.5);
+        let mut p = [1.0; 4];
+        let rows = Rows {
+            parameter: 0,
+            len: 2,
+            rows: &[1],
+        };
+
+        optimizer.step(&mut [&mut p], &[&[1.0; 4]], 0.1);
+        optimizer.sparse_step(&mut [&mut p], &[&[0.0, 0.0, 1.0, 1.0]], &[rows], 0.1);
+
+        // the first row and its velocity are not updated by the second step
+        assert_eq!([0.9, 0.9], p[..2]);
+        assert!((p[2] - (1.0 - 0.1 - 0.15)).abs() < 1e-12);
+        assert_eq!(vec![vec![1.0, 1.0, 1.5, 1.5]], optimizer.state().buffers);
+    }
+
+    #[test]
+    fn test_from_state() {
+        let mut optimizer = Sgd::new().momentum(0.9);
+        optimizer.step(&mut [&mut [1.0, 2.0]], &[&[0.1, 0.2]], 0.01);
+
+        let restored = Sgd::from_state(optimizer.state(), 1).unwrap();
+
+        assert_eq!(optimizer, restored);
+        assert_eq!(None, Sgd::from_state(optimizer.state(), 2));
+        assert_eq!(
+            None,
+            Sgd::<f64>::from_state(
+                OptimizerState {
+                    hyperparameters: vec![],
+                    steps: 0,
+                    buffers: vec![],
+                },
+                1
+            )
+        );
     }
 }
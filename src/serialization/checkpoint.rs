@@ -0,0 +1,869 @@
+use std::{
+    error,
+    fmt::{self, Display},
+    io::{self, Read, Write},
+};
+
+use ndarray::{Array1, Array2, ArrayD, ScalarOperand};
+use num_traits::{Float, FromPrimitive};
+
+use crate::{
+    activations::Activation,
+    layers::{self, Layer},
+    losses::Loss,
+    model::{Layers, Node},
+    optimizers::{optimizer_from_state, scheduler_from_parameters, OptimizerState},
+    Model,
+};
+
+use super::{Dtype, Element};
+
+const MAGIC: &[u8; 4] = b"RBTC";
+const VERSION: u16 = 1;
+
+/// Error of reading or writing a binary checkpoint.
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+
+    /// The data does not start with the checkpoint magic bytes.
+    InvalidMagic,
+
+    /// The checkpoint was written in a format version this build can not
+    /// read.
+    UnsupportedVersion(u16),
+
+    /// The stored values have a different type than the model.
+    DtypeMismatch {
+        expected: Dtype,
+        found: u8,
+    },
+
+    /// The checksum does not match the data, e.g. because the file was
+    /// truncated or corrupted.
+    ChecksumMismatch {
+        expected: u32,
+        found: u32,
+    },
+
+    /// The data is well-formed but can not be loaded into the model, e.g.
+    /// because of a different activation function.
+    Incompatible(String),
+}
+
+impl Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Io(err) => write!(f, "{}", err),
+            CheckpointError::InvalidMagic => write!(f, "not a robit checkpoint"),
+            CheckpointError::UnsupportedVersion(version) => {
+                write!(f, "unsupported checkpoint version {}", version)
+            }
+            CheckpointError::DtypeMismatch { expected, found } => {
+                write!(f, "expected dtype {}, found dtype id {}", expected, found)
+            }
+            CheckpointError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "checksum mismatch: expected {:08x}, found {:08x}",
+                expected, found
+            ),
+            CheckpointError::Incompatible(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl error::Error for CheckpointError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            CheckpointError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CheckpointError {
+    fn from(err: io::Error) -> Self {
+        CheckpointError::Io(err)
+    }
+}
+
+fn dtype_id(dtype: Dtype) -> u8 {
+    match dtype {
+        Dtype::F32 => 1,
+        Dtype::F64 => 2,
+    }
+}
+
+/// Writes the model as binary checkpoint.
+///
+/// The checkpoint consists of a header followed by the parameters, the
+/// optional training state and a checksum. All numbers are little-endian.
+///
+/// | Field          | Type                                          |
+/// |----------------|-----------------------------------------------|
+/// | magic          | `b"RBTC"`                                     |
+/// | version        | `u16`                                         |
+/// | dtype          | `u8`, `1` = `f32`, `2` = `f64`                |
+/// | activation     | `u8` length followed by UTF-8 bytes           |
+/// | loss           | `u8` length followed by UTF-8 bytes           |
+/// | learning_rate  | dtype                                         |
+/// | batch_size     | `u64`                                         |
+/// | layers         | `u32`                                         |
+/// | shapes         | `u64` inputs and `u64` outputs of every layer |
+/// | data           | weights (row-major) and biases of every layer |
+/// | extra_layers   | `u32`, layers added with [Model::add]         |
+/// | training_state | `u8`, `1` if the training state follows       |
+/// | checksum       | `u32` CRC-32 of all preceding bytes           |
+///
+/// Every layer added with [Model::add] is stored after `extra_layers`, its
+/// parameters and state have the shapes given by its configuration.
+///
+/// | Field      | Type                                                   |
+/// |------------|--------------------------------------------------------|
+/// | position   | `u32`, number of dense layers before the layer         |
+/// | name       | `u8` length followed by UTF-8 bytes                    |
+/// | config     | `u32` count followed by `f64` values                   |
+/// | parameters | dtype values of every parameter (row-major)            |
+/// | state      | dtype values of every state tensor (row-major)         |
+///
+/// Checkpoints written by [write_training_checkpoint] store the training
+/// state before the checksum. Names are empty if the model has no scheduler
+/// or optimizer, their fields are omitted then.
+///
+/// | Field                     | Type                                      |
+/// |---------------------------|-------------------------------------------|
+/// | epoch                     | `u64`                                     |
+/// | rng_state                 | `u64`                                     |
+/// | scheduler                 | `u8` length followed by UTF-8 bytes       |
+/// | scheduler parameters      | `u32` count followed by dtype values      |
+/// | optimizer                 | `u8` length followed by UTF-8 bytes       |
+/// | optimizer hyperparameters | `u32` count followed by dtype values      |
+/// | optimizer steps           | `u64`                                     |
+/// | optimizer buffers         | `u32` count followed by the buffers       |
+/// | buffer                    | `u64` length followed by dtype values     |
+pub fn write_checkpoint<A, L, T, W>(model: &Model<A, L, T>, writer: W) -> io::Result<()>
+where
+    A: Activation<T>,
+    L: Loss<T>,
+    T: Element,
+    W: Write,
+{
+    write(model, writer, false)
+}
+
+/// Writes the model like [write_checkpoint] together with the state needed
+/// to resume its training: the epoch, the state of the random number
+/// generator, the scheduler and the optimizer with its buffers.
+///
+/// Resuming the training of a model read from such a checkpoint gives the
+/// same results as training without interruption.
+pub fn write_training_checkpoint<A, L, T, W>(model: &Model<A, L, T>, writer: W) -> io::Result<()>
+where
+    A: Activation<T>,
+    L: Loss<T>,
+    T: Element,
+    W: Write,
+{
+    write(model, writer, true)
+}
+
+fn write<A, L, T, W>(model: &Model<A, L, T>, mut writer: W, training_state: bool) -> io::Result<()>
+where
+    A: Activation<T>,
+    L: Loss<T>,
+    T: Element,
+    W: Write,
+{
+    let mut buffer = vec![];
+
+    buffer.extend_from_slice(MAGIC);
+    buffer.extend_from_slice(&VERSION.to_le_bytes());
+    buffer.push(dtype_id(T::DTYPE));
+    write_name(&mut buffer, model.activation().name());
+    write_name(&mut buffer, model.loss().name());
+    model.learning_rate().write_le(&mut buffer);
+    buffer.extend_from_slice(&(model.batch_size() as u64).to_le_bytes());
+    buffer.extend_from_slice(&(model.weights().len() as u32).to_le_bytes());
+
+    for w in model.weights() {
+        buffer.extend_from_slice(&(w.nrows() as u64).to_le_bytes());
+        buffer.extend_from_slice(&(w.ncols() as u64).to_le_bytes());
+    }
+
+    for (w, b) in model.weights().iter().zip(model.biases()) {
+        w.iter().for_each(|v| v.write_le(&mut buffer));
+        b.iter().for_each(|v| v.write_le(&mut buffer));
+    }
+
+    let layers: Vec<&dyn Layer<T>> = model.layers().collect();
+    buffer.extend_from_slice(&(layers.len() as u32).to_le_bytes());
+
+    // number of dense layers before the current node
+    let mut position = 0u32;
+    for node in model.nodes() {
+        match node {
+            Node::Dense(_) => position += 1,
+            Node::Layer(k) => {
+                let layer = layers[k];
+
+                buffer.extend_from_slice(&position.to_le_bytes());
+                write_name(&mut buffer, layer.name());
+                write_values(&mut buffer, &layer.config());
+
+                for tensor in layer.parameters().into_iter().chain(layer.state()) {
+                    tensor.iter().for_each(|v| v.write_le(&mut buffer));
+                }
+            }
+        }
+    }
+
+    buffer.push(training_state as u8);
+
+    if training_state {
+        write_training_state(&mut buffer, model);
+    }
+
+    let checksum = crc32(&buffer);
+    buffer.extend_from_slice(&checksum.to_le_bytes());
+
+    writer.write_all(&buffer)?;
+    writer.flush()
+}
+
+fn write_training_state<A, L, T>(buffer: &mut Vec<u8>, model: &Model<A, L, T>)
+where
+    A: Activation<T>,
+    L: Loss<T>,
+    T: Element,
+{
+    buffer.extend_from_slice(&(model.epoch() as u64).to_le_bytes());
+    buffer.extend_from_slice(&model.rng_state().to_le_bytes());
+
+    match model.scheduler() {
+        Some(scheduler) => {
+            write_name(buffer, scheduler.name());
+            write_values(buffer, &scheduler.parameters());
+        }
+        None => write_name(buffer, ""),
+    }
+
+    match model.optimizer() {
+        Some(optimizer) => {
+            let state = optimizer.state();
+
+            write_name(buffer, optimizer.name());
+            write_values(buffer, &state.hyperparameters);
+            buffer.extend_from_slice(&state.steps.to_le_bytes());
+            buffer.extend_from_slice(&(state.buffers.len() as u32).to_le_bytes());
+
+            for values in &state.buffers {
+                buffer.extend_from_slice(&(values.len() as u64).to_le_bytes());
+                values.iter().for_each(|v| v.write_le(buffer));
+            }
+        }
+        None => write_name(buffer, ""),
+    }
+}
+
+fn write_values<T: Element>(buffer: &mut Vec<u8>, values: &[T]) {
+    buffer.extend_from_slice(&(values.len() as u32).to_le_bytes());
+    values.iter().for_each(|v| v.write_le(buffer));
+}
+
+fn write_name(buffer: &mut Vec<u8>, name: &str) {
+    buffer.push(name.len() as u8);
+    buffer.extend_from_slice(name.as_bytes());
+}
+
+/// Reads a checkpoint written by [write_checkpoint] or
+/// [write_training_checkpoint], restoring the training state if it was
+/// stored.
+pub fn read_checkpoint<A, L, T, R>(mut reader: R) -> Result<Model<A, L, T>, CheckpointError>
+where
+    A: Activation<T> + Default,
+    L: Loss<T> + Default,
+    T: Element + Float + FromPrimitive + ScalarOperand,
+    R: Read,
+{
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+
+    if data.len() < MAGIC.len() || &data[..MAGIC.len()] != MAGIC {
+        return Err(CheckpointError::InvalidMagic);
+    }
+
+    let mut bytes = Bytes {
+        data: &data,
+        offset: MAGIC.len(),
+    };
+
+    let version = u16::from_le_bytes(bytes.take(2)?.try_into().unwrap());
+    if version != VERSION {
+        return Err(CheckpointError::UnsupportedVersion(version));
+    }
+
+    if data.len() < 4 {
+        return Err(truncated());
+    }
+    let (content, checksum) = data.split_at(data.len() - 4);
+    let expected = u32::from_le_bytes(checksum.try_into().unwrap());
+    let found = crc32(content);
+    if expected != found {
+        return Err(CheckpointError::ChecksumMismatch { expected, found });
+    }
+
+    let mut bytes = Bytes {
+        data: content,
+        offset: bytes.offset,
+    };
+
+    let dtype = bytes.take(1)?[0];
+    if dtype != dtype_id(T::DTYPE) {
+        return Err(CheckpointError::DtypeMismatch {
+            expected: T::DTYPE,
+            found: dtype,
+        });
+    }
+
+    let activation = bytes.name()?;
+    let loss = bytes.name()?;
+    let learning_rate = T::read_le(bytes.take(T::DTYPE.size())?);
+    let batch_size = bytes.u64()? as usize;
+    let n_layers = bytes.u32()? as usize;
+
+    let mut model: Model<A, L, T> = Model::new(learning_rate);
+    model.set_batch_size(batch_size);
+
+    if activation != model.activation().name() {
+        return Err(CheckpointError::Incompatible(format!(
+            "expected activation {}, found {}",
+            model.activation().name(),
+            activation
+        )));
+    }
+
+    if loss != model.loss().name() {
+        return Err(CheckpointError::Incompatible(format!(
+            "expected loss {}, found {}",
+            model.loss().name(),
+            loss
+        )));
+    }
+
+    // the counts are not trusted for allocations, the vectors grow with the
+    // data that is actually read
+    let mut shapes = vec![];
+    for _ in 0..n_layers {
+        shapes.push((bytes.u64()? as usize, bytes.u64()? as usize));
+    }
+
+    let mut weights = vec![];
+    let mut biases = vec![];
+
+    for (n_inputs, n_outputs) in shapes {
+        let n_weights = n_inputs.checked_mul(n_outputs).ok_or_else(|| {
+            CheckpointError::Incompatible(format!(
+                "dense layer with {} inputs and {} outputs is too large",
+                n_inputs, n_outputs
+            ))
+        })?;
+        let w = bytes.values::<T>(n_weights)?;
+        weights.push(Array2::from_shape_vec((n_inputs, n_outputs), w).unwrap());
+        biases.push(Array1::from_vec(bytes.values::<T>(n_outputs)?));
+    }
+
+    model.set_parameters(weights, biases);
+
+    let n_layers = bytes.u32()? as usize;
+    model.set_layers(read_layers(&mut bytes, n_layers, model.weights().len())?);
+    model
+        .check_shapes()
+        .map_err(|err| CheckpointError::Incompatible(err.to_string()))?;
+
+    if bytes.take(1)?[0] == 1 {
+        read_training_state(&mut bytes, &mut model)?;
+    }
+
+    if bytes.offset != content.len() {
+        return Err(CheckpointError::Incompatible(
+            "unexpected data after the parameters".to_owned(),
+        ));
+    }
+
+    Ok(model)
+}
+
+fn read_layers<T>(
+    bytes: &mut Bytes,
+    n_layers: usize,
+    n_dense: usize,
+) -> Result<Layers<T>, CheckpointError>
+where
+    T: Element + Float + FromPrimitive + ScalarOperand,
+{
+    let mut layers: Layers<T> = vec![];
+
+    for _ in 0..n_layers {
+        let position = bytes.u32()? as usize;
+        if position > n_dense
+            || layers
+                .last()
+                .is_some_and(|(previous, _)| *previous > position)
+        {
+            return Err(CheckpointError::Incompatible(format!(
+                "invalid layer position {}",
+                position
+            )));
+        }
+
+        let name = bytes.name()?;
+        let n_config = bytes.u32()? as usize;
+        let config = bytes.values::<f64>(n_config)?;
+        let max_values = bytes.remaining() / T::DTYPE.size();
+        let mut layer = layers::layer_from_config::<T>(&name, &config, max_values)
+            .ok_or_else(|| CheckpointError::Incompatible(format!("invalid layer {}", name)))?;
+
+        read_tensors(bytes, layer.parameters_mut())?;
+        read_tensors(bytes, layer.state_mut())?;
+
+        layers.push((position, layer));
+    }
+
+    Ok(layers)
+}
+
+fn read_tensors<T: Element>(
+    bytes: &mut Bytes,
+    tensors: Vec<&mut ArrayD<T>>,
+) -> Result<(), CheckpointError> {
+    for tensor in tensors {
+        let values = bytes.values::<T>(tensor.len())?;
+        *tensor = ArrayD::from_shape_vec(tensor.shape(), values).unwrap();
+    }
+
+    Ok(())
+}
+
+fn read_training_state<A, L, T>(
+    bytes: &mut Bytes,
+    model: &mut Model<A, L, T>,
+) -> Result<(), CheckpointError>
+where
+    A: Activation<T>,
+    L: Loss<T>,
+    T: Element + Float,
+{
+    model.set_epoch(bytes.u64()? as usize);
+    model.set_seed(bytes.u64()?);
+
+    let scheduler = bytes.name()?;
+    if !scheduler.is_empty() {
+        let n_parameters = bytes.u32()? as usize;
+        let parameters = bytes.values::<T>(n_parameters)?;
+
+        model.set_boxed_scheduler(Some(
+            scheduler_from_parameters(&scheduler, &parameters).ok_or_else(|| {
+                CheckpointError::Incompatible(format!("invalid scheduler {}", scheduler))
+            })?,
+        ));
+    }
+
+    let optimizer = bytes.name()?;
+    if !optimizer.is_empty() {
+        let n_hyperparameters = bytes.u32()? as usize;
+        let hyperparameters = bytes.values::<T>(n_hyperparameters)?;
+        let steps = bytes.u64()?;
+        let n_buffers = bytes.u32()? as usize;
+
+        // one buffer per parameter, in the order the optimizer receives them
+        let sizes: Vec<usize> = model
+            .weights()
+            .iter()
+            .map(|w| w.len())
+            .chain(model.biases().iter().map(|b| b.len()))
+            .chain(
+                model
+                    .layers()
+                    .flat_map(|layer| layer.parameters())
+                    .map(|p| p.len()),
+            )
+            .collect();
+
+        if (sizes.is_empty() && n_buffers > 0) || !n_buffers.is_multiple_of(sizes.len().max(1)) {
+            return Err(CheckpointError::Incompatible(format!(
+                "expected a multiple of {} optimizer buffers, found {}",
+                sizes.len(),
+                n_buffers
+            )));
+        }
+
+        let mut buffers = vec![];
+        for i in 0..n_buffers {
+            let len = bytes.u64()? as usize;
+            if len != sizes[i % sizes.len()] {
+                return Err(CheckpointError::Incompatible(format!(
+                    "expected {} values in optimizer buffer {}, found {}",
+                    sizes[i % sizes.len()],
+                    i,
+                    len
+                )));
+            }
+
+            buffers.push(bytes.values::<T>(len)?);
+        }
+
+        let state = OptimizerState {
+            hyperparameters,
+            steps,
+            buffers,
+        };
+
+        model.set_boxed_optimizer(Some(
+            optimizer_from_state(&optimizer, state, sizes.len()).ok_or_else(|| {
+                CheckpointError::Incompatible(format!("invalid optimizer {}", optimizer))
+            })?,
+        ));
+    }
+
+    Ok(())
+}
+
+fn truncated() -> CheckpointError {
+    CheckpointError::Io(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "checkpoint is truncated",
+    ))
+}
+
+struct Bytes<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Bytes<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], CheckpointError> {
+        let end = self.offset.checked_add(n).ok_or_else(truncated)?;
+        let bytes = self.data.get(self.offset..end).ok_or_else(truncated)?;
+        self.offset = end;
+
+        Ok(bytes)
+    }
+
+    /// Number of bytes that have not been read yet.
+    fn remaining(&self) -> usize {
+        self.data.len() - self.offset
+    }
+
+    fn u32(&mut self) -> Result<u32, CheckpointError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, CheckpointError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn name(&mut self) -> Result<String, CheckpointError> {
+        let len = self.take(1)?[0] as usize;
+
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|_| CheckpointError::Incompatible("invalid name".to_owned()))
+    }
+
+    fn values<T: Element>(&mut self, n: usize) -> Result<Vec<T>, CheckpointError> {
+        let size = T::DTYPE.size();
+        let len = n.checked_mul(size).ok_or_else(truncated)?;
+
+        Ok(self.take(len)?.chunks_exact(size).map(T::read_le).collect())
+    }
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+}
+
+/// CRC-32 (IEEE 802.3) checksum.
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, byte| {
+        CRC32_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        activations::Relu,
+        initializers::{RandomDistr, Zeros},
+        layers::{AlphaDropout, Embedding},
+        losses::MeanSquaredError,
+        optimizers::{Adam, Optimizer, Sgd, StepDecay},
+    };
+
+    fn model() -> Model<Relu, MeanSquaredError, f32> {
+        let mut model = Model::new(0.015);
+        model.add_layer((3, 4), RandomDistr::normal_with(0.0, 0.5).unwrap());
+        model.add_layer((4, 2), RandomDistr::normal_with(0.0, 0.5).unwrap());
+        model
+    }
+
+    fn write(model: &Model<Relu, MeanSquaredError, f32>) -> Vec<u8> {
+        let mut buffer = vec![];
+        write_checkpoint(model, &mut buffer).unwrap();
+        buffer
+    }
+
+    fn read<T>(data: &[u8]) -> Result<Model<Relu, MeanSquaredError, T>, CheckpointError>
+    where
+        T: Element + Float + FromPrimitive + ScalarOperand,
+        Relu: Activation<T>,
+        MeanSquaredError: Loss<T>,
+    {
+        read_checkpoint(data)
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(0xcbf4_3926, crc32(b"123456789"));
+        assert_eq!(0, crc32(b""));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let model = model();
+        let loaded = read::<f32>(&write(&model)).unwrap();
+
+        assert_eq!(model.weights(), loaded.weights());
+        assert_eq!(model.biases(), loaded.biases());
+        assert_eq!(0.015, loaded.learning_rate());
+    }
+
+    #[test]
+    fn test_invalid_magic() {
+        let mut data = write(&model());
+        data[0] = b'X';
+
+        assert!(matches!(
+            read::<f32>(&data),
+            Err(CheckpointError::InvalidMagic)
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let mut data = write(&model());
+        data[4] = 9;
+
+        assert!(matches!(
+            read::<f32>(&data),
+            Err(CheckpointError::UnsupportedVersion(9))
+        ));
+    }
+
+    #[test]
+    fn test_corrupted() {
+        let mut data = write(&model());
+        let i = data.len() - 10;
+        data[i] ^= 0xff;
+
+        assert!(matches!(
+            read::<f32>(&data),
+            Err(CheckpointError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_truncated() {
+        let data = write(&model());
+
+        assert!(matches!(
+            read::<f32>(&data[..data.len() / 2]),
+            Err(CheckpointError::ChecksumMismatch { .. })
+        ));
+        assert!(matches!(
+            read::<f32>(&data[..5]),
+            Err(CheckpointError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_oversized_shape() {
+        let mut data = write(&model());
+        data.truncate(data.len() - 4);
+        let shape = [3u64.to_le_bytes(), 4u64.to_le_bytes()].concat();
+        let i = data.windows(16).position(|w| w == shape).unwrap();
+        data[i..i + 8].copy_from_slice(&(1u64 << 32).to_le_bytes());
+        data[i + 8..i + 16].copy_from_slice(&(1u64 << 32).to_le_bytes());
+        let checksum = crc32(&data);
+        data.extend_from_slice(&checksum.to_le_bytes());
+
+        assert!(matches!(
+            read::<f32>(&data),
+            Err(CheckpointError::Incompatible(message)) if message.contains("too large")
+        ));
+    }
+
+    #[test]
+    fn test_oversized_layer() {
+        let mut model = model();
+        model.add(Embedding::new(3, 2, Zeros).unwrap());
+        let mut data = write(&model);
+        data.truncate(data.len() - 4);
+        let config = [3f64.to_le_bytes(), 2f64.to_le_bytes()].concat();
+        let i = data.windows(16).position(|w| w == config).unwrap();
+        data[i..i + 8].copy_from_slice(&1e6f64.to_le_bytes());
+        data[i + 8..i + 16].copy_from_slice(&1e6f64.to_le_bytes());
+        let checksum = crc32(&data);
+        data.extend_from_slice(&checksum.to_le_bytes());
+
+        assert!(matches!(
+            read::<f32>(&data),
+            Err(CheckpointError::Incompatible(message)) if message == "invalid layer embedding"
+        ));
+    }
+
+    #[test]
+    fn test_dtype_mismatch() {
+        let data = write(&model());
+
+        assert!(matches!(
+            read::<f64>(&data),
+            Err(CheckpointError::DtypeMismatch {
+                expected: Dtype::F64,
+                found: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_training_state() {
+        let mut model = model();
+        model.set_optimizer(Sgd::new().momentum(0.9));
+        model.set_scheduler(StepDecay::new(5, 0.5));
+        model.set_seed(42);
+        model.set_epoch(7);
+
+        let mut buffer = vec![];
+        write_training_checkpoint(&model, &mut buffer).unwrap();
+        let loaded = read::<f32>(&buffer).unwrap();
+
+        assert_eq!(7, loaded.epoch());
+        assert_eq!(42, loaded.rng_state());
+        assert_eq!("step_decay", loaded.scheduler().unwrap().name());
+        assert_eq!(
+            model.optimizer().unwrap().state(),
+            loaded.optimizer().unwrap().state()
+        );
+    }
+
+    #[test]
+    fn test_without_training_state() {
+        let mut model = model();
+        model.set_optimizer(Adam::new());
+        model.set_epoch(3);
+
+        let loaded = read::<f32>(&write(&model)).unwrap();
+
+        assert_eq!(0, loaded.epoch());
+        assert!(loaded.optimizer().is_none());
+    }
+
+    #[test]
+    fn test_layers() {
+        let mut model = model();
+        model.add(AlphaDropout::new(0.1).unwrap());
+        model.add_layer((2, 2), RandomDistr::normal_with(0.0, 0.5).unwrap());
+
+        let loaded = read::<f32>(&write(&model)).unwrap();
+        let layers: Vec<_> = loaded
+            .layers()
+            .map(|layer| (layer.name(), layer.config()))
+            .collect();
+
+        assert_eq!(vec![("alpha_dropout", vec![0.1])], layers);
+        assert_eq!(model.nodes(), loaded.nodes());
+        assert_eq!(model.weights(), loaded.weights());
+    }
+
+    /// Optimizer that stores the given state, e.g. with too few buffers.
+    struct Stored(&'static str, OptimizerState<f32>);
+
+    impl Optimizer<f32> for Stored {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn step(&mut self, _: &mut [&mut [f32]], _: &[&[f32]], _: f32) {}
+
+        fn state(&self) -> OptimizerState<f32> {
+            self.1.clone()
+        }
+    }
+
+    #[test]
+    fn test_optimizer_buffer_count() {
+        let mut model = model();
+        // one buffer per parameter, Adam needs two and Sgd one
+        let buffers: Vec<Vec<f32>> = [12, 8, 4, 2].iter().map(|&n| vec![0.0; n]).collect();
+        let state = |hyperparameters: Vec<f32>, buffers: Vec<Vec<f32>>| OptimizerState {
+            hyperparameters,
+            steps: 1,
+            buffers,
+        };
+
+        for (name, state) in [
+            ("adam", state(vec![0.9, 0.999, 1e-8], buffers.clone())),
+            (
+                "sgd",
+                state(vec![0.9], [&buffers[..], &buffers[..]].concat()),
+            ),
+        ] {
+            model.set_optimizer(Stored(name, state));
+
+            let mut buffer = vec![];
+            write_training_checkpoint(&model, &mut buffer).unwrap();
+
+            assert!(matches!(
+                read::<f32>(&buffer),
+                Err(CheckpointError::Incompatible(message)) if message.starts_with("invalid optimizer")
+            ));
+        }
+    }
+
+    #[test]
+    fn test_invalid_optimizer_buffers() {
+        let mut model = model();
+        let mut optimizer = Adam::new();
+        optimizer.step(&mut [&mut [0.0]], &[&[1.0]], 0.1);
+        model.set_optimizer(optimizer);
+
+        let mut buffer = vec![];
+        write_training_checkpoint(&model, &mut buffer).unwrap();
+
+        assert!(matches!(
+            read::<f32>(&buffer),
+            Err(CheckpointError::Incompatible(_))
+        ));
+    }
+}
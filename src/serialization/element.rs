@@ -0,0 +1,73 @@
+use std::fmt::{self, Display};
+
+/// Data type of the values of a stored tensor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dtype {
+    F32,
+    F64,
+}
+
+impl Dtype {
+    /// Size of a single value in bytes.
+    pub fn size(&self) -> usize {
+        match self {
+            Dtype::F32 => 4,
+            Dtype::F64 => 8,
+        }
+    }
+}
+
+impl Display for Dtype {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dtype::F32 => write!(f, "f32"),
+            Dtype::F64 => write!(f, "f64"),
+        }
+    }
+}
+
+/// A value type that can be stored in the binary formats.
+pub trait Element: Copy + 'static {
+    const DTYPE: Dtype;
+
+    /// Appends the little-endian bytes of the value.
+    fn write_le(&self, buffer: &mut Vec<u8>);
+
+    /// Reads a value from the first [Dtype::size] little-endian bytes.
+    fn read_le(bytes: &[u8]) -> Self;
+
+    /// Converts a value read from a file with a different dtype.
+    fn from_f64(value: f64) -> Self;
+}
+
+impl Element for f32 {
+    const DTYPE: Dtype = Dtype::F32;
+
+    fn write_le(&self, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        f32::from_le_bytes(bytes[..4].try_into().unwrap())
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+}
+
+impl Element for f64 {
+    const DTYPE: Dtype = Dtype::F64;
+
+    fn write_le(&self, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        f64::from_le_bytes(bytes[..8].try_into().unwrap())
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+}
@@ -0,0 +1,359 @@
+//! Minimal JSON support for the headers of the binary formats.
+
+use std::fmt::Write;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+
+    /// Number in its textual representation, so integers keep their full
+    /// precision.
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+
+    /// Object with the members in the order of the source.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Number(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_object(&self) -> Option<&[(String, Value)]> {
+        match self {
+            Value::Object(members) => Some(members),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        self.as_object()?
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+}
+
+/// Appends `s` as quoted and escaped JSON string.
+pub(crate) fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+}
+
+/// Maximum nesting of arrays and objects, which bounds the recursion of the
+/// parser.
+const MAX_DEPTH: usize = 128;
+
+/// Parses a JSON document, returning a description of the first error.
+pub(crate) fn parse(input: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        chars: input.char_indices().peekable(),
+        input,
+        depth: 0,
+    };
+
+    let value = parser.value()?;
+    parser.whitespace();
+
+    match parser.chars.next() {
+        None => Ok(value),
+        Some((i, _)) => Err(format!("unexpected data at {}", i)),
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    input: &'a str,
+
+    /// Number of arrays and objects that enclose the current value.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn whitespace(&mut self) {
+        while let Some((_, ' ' | '\n' | '\r' | '\t')) = self.chars.peek() {
+            self.chars.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((i, c)) => Err(format!("expected '{}' at {}, found '{}'", expected, i, c)),
+            None => Err(format!("expected '{}', found end of input", expected)),
+        }
+    }
+
+    fn literal(&mut self, literal: &str, value: Value) -> Result<Value, String> {
+        for expected in literal.chars() {
+            self.expect(expected)?;
+        }
+
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.whitespace();
+
+        match self.chars.peek().copied() {
+            Some((_, '{')) => self.nested(Self::object),
+            Some((_, '[')) => self.nested(Self::array),
+            Some((_, '"')) => Ok(Value::String(self.string()?)),
+            Some((_, 't')) => self.literal("true", Value::Bool(true)),
+            Some((_, 'f')) => self.literal("false", Value::Bool(false)),
+            Some((_, 'n')) => self.literal("null", Value::Null),
+            Some((_, '-' | '0'..='9')) => self.number(),
+            Some((i, c)) => Err(format!("unexpected '{}' at {}", c, i)),
+            None => Err("unexpected end of input".to_owned()),
+        }
+    }
+
+    /// Parses an array or object with `parse`, failing if it is nested
+    /// deeper than [MAX_DEPTH].
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Value, String>) -> Result<Value, String> {
+        if self.depth == MAX_DEPTH {
+            let i = self.chars.peek().map(|(i, _)| *i).unwrap_or(0);
+
+            return Err(format!("nesting deeper than {} at {}", MAX_DEPTH, i));
+        }
+
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+
+        value
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        self.whitespace();
+
+        let mut members = vec![];
+
+        if let Some((_, '}')) = self.chars.peek() {
+            self.chars.next();
+            return Ok(Value::Object(members));
+        }
+
+        loop {
+            self.whitespace();
+            let key = self.string()?;
+            self.whitespace();
+            self.expect(':')?;
+            members.push((key, self.value()?));
+            self.whitespace();
+
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, '}')) => return Ok(Value::Object(members)),
+                Some((i, c)) => return Err(format!("unexpected '{}' at {}", c, i)),
+                None => return Err("unexpected end of input".to_owned()),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        self.whitespace();
+
+        let mut values = vec![];
+
+        if let Some((_, ']')) = self.chars.peek() {
+            self.chars.next();
+            return Ok(Value::Array(values));
+        }
+
+        loop {
+            values.push(self.value()?);
+            self.whitespace();
+
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, ']')) => return Ok(Value::Array(values)),
+                Some((i, c)) => return Err(format!("unexpected '{}' at {}", c, i)),
+                None => return Err("unexpected end of input".to_owned()),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.chars.peek().map(|(i, _)| *i).unwrap_or(0);
+        let mut end = start;
+
+        while let Some((i, c)) = self.chars.peek().copied() {
+            if !matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9') {
+                break;
+            }
+
+            end = i + c.len_utf8();
+            self.chars.next();
+        }
+
+        let number = &self.input[start..end];
+
+        match number.parse::<f64>() {
+            Ok(_) => Ok(Value::Number(number.to_owned())),
+            Err(_) => Err(format!("invalid number at {}", start)),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+
+        let mut s = String::new();
+
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(s),
+                Some((i, '\\')) => match self.chars.next() {
+                    Some((_, '"')) => s.push('"'),
+                    Some((_, '\\')) => s.push('\\'),
+                    Some((_, '/')) => s.push('/'),
+                    Some((_, 'b')) => s.push('\u{8}'),
+                    Some((_, 'f')) => s.push('\u{c}'),
+                    Some((_, 'n')) => s.push('\n'),
+                    Some((_, 'r')) => s.push('\r'),
+                    Some((_, 't')) => s.push('\t'),
+                    Some((_, 'u')) => s.push(self.unicode_escape(i)?),
+                    _ => return Err(format!("invalid escape at {}", i)),
+                },
+                Some((_, c)) => s.push(c),
+                None => return Err("unterminated string".to_owned()),
+            }
+        }
+    }
+
+    fn hex4(&mut self, position: usize) -> Result<u32, String> {
+        let mut code = 0;
+
+        for _ in 0..4 {
+            let digit = self
+                .chars
+                .next()
+                .and_then(|(_, c)| c.to_digit(16))
+                .ok_or_else(|| format!("invalid unicode escape at {}", position))?;
+            code = code * 16 + digit;
+        }
+
+        Ok(code)
+    }
+
+    fn unicode_escape(&mut self, position: usize) -> Result<char, String> {
+        let high = self.hex4(position)?;
+
+        let code = if (0xd800..0xdc00).contains(&high) {
+            self.expect('\\')?;
+            self.expect('u')?;
+            let low = self.hex4(position)?;
+
+            0x10000 + ((high - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff)
+        } else {
+            high
+        };
+
+        char::from_u32(code).ok_or_else(|| format!("invalid unicode escape at {}", position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let value =
+            parse(r#" {"a": [1, 2.5, -3e2], "b": {"c": "d\"é"}, "e": null, "f": true} "#).unwrap();
+
+        assert_eq!(
+            Some(
+                &[
+                    Value::Number("1".to_owned()),
+                    Value::Number("2.5".to_owned()),
+                    Value::Number("-3e2".to_owned())
+                ][..]
+            ),
+            value.get("a").unwrap().as_array()
+        );
+        assert_eq!(
+            Some("d\"é"),
+            value.get("b").unwrap().get("c").unwrap().as_str()
+        );
+        assert_eq!(Some(&Value::Null), value.get("e"));
+        assert_eq!(Some(&Value::Bool(true)), value.get("f"));
+    }
+
+    #[test]
+    fn test_large_integer() {
+        let value = parse("[18446744073709551615]").unwrap();
+
+        assert_eq!(Some(u64::MAX), value.as_array().unwrap()[0].as_u64());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(parse("{\"a\": }").is_err());
+        assert!(parse("[1, 2").is_err());
+        assert!(parse("{} x").is_err());
+        assert!(parse("\"abc").is_err());
+    }
+
+    #[test]
+    fn test_depth() {
+        let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(
+            Err(format!(
+                "nesting deeper than {} at {}",
+                MAX_DEPTH, MAX_DEPTH
+            )),
+            parse(&nested(MAX_DEPTH + 1))
+        );
+        assert!(parse(&"{\"a\": ".repeat(100_000)).is_err());
+    }
+
+    #[test]
+    fn test_write_string() {
+        let mut out = String::new();
+        write_string(&mut out, "a\"b\\c\n\u{1}");
+
+        assert_eq!(r#""a\"b\\c\n\u0001""#, out);
+        assert_eq!(
+            Value::String("a\"b\\c\n\u{1}".to_owned()),
+            parse(&out).unwrap()
+        );
+    }
+}
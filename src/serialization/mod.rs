@@ -0,0 +1,77 @@
+//! Reading and writing models.
+//!
+//! # Text format
+//!
+//! Human-readable format used by [Model::save] and [Model::load].
+//!
+//! The format is line based. A model with a `784 → 10` and a `10 → 2` dense
+//! layer and a dropout layer between them is stored as:
+//!
+//! ```text
+//! robit-model 1
+//! activation relu
+//! loss mean_squared_error
+//! learning_rate 0.00015
+//! batch_size 32
+//! layers 3
+//! dense 784 10
+//! weights
+//! <784 lines with 10 values each>
+//! biases
+//! <1 line with 10 values>
+//! layer dropout 0.2
+//! dense 10 2
+//! weights
+//! <10 lines with 2 values each>
+//! biases
+//! <1 line with 2 values>
+//! ```
+//!
+//! Values are separated by a single space and written with [Display], which
+//! prints the shortest representation that parses back to the same float.
+//!
+//! Layers added with [Model::add] are stored with their name and
+//! configuration, followed by a `parameters` and a `state` line with one line
+//! per tensor each if the layer has parameters or state.
+//!
+//! # Binary checkpoints
+//!
+//! [Model::save_checkpoint] stores the model in a compact binary format with
+//! a versioned header and a checksum, see [write_checkpoint] for the layout.
+//! [Model::save_training_checkpoint] additionally stores the optimizer,
+//! scheduler, epoch and random number generator state to resume training.
+//!
+//! # Safetensors
+//!
+//! [SafeTensors] reads and writes the [safetensors] format to exchange
+//! parameters with other frameworks.
+//!
+//! # ONNX
+//!
+//! [Model::export_onnx] writes the model as ONNX graph for inference in other
+//! runtimes, see [write_onnx].
+//!
+//! [safetensors]: https://github.com/huggingface/safetensors
+//! [Model::export_onnx]: crate::Model::export_onnx
+//! [Model::add]: crate::Model::add
+//! [Model::save]: crate::Model::save
+//! [Model::load]: crate::Model::load
+//! [Model::save_checkpoint]: crate::Model::save_checkpoint
+//! [Model::save_training_checkpoint]: crate::Model::save_training_checkpoint
+//! [Display]: std::fmt::Display
+
+mod checkpoint;
+mod element;
+mod json;
+mod onnx;
+mod protobuf;
+mod safetensors;
+mod text;
+
+pub use checkpoint::{
+    read_checkpoint, write_checkpoint, write_training_checkpoint, CheckpointError,
+};
+pub use element::{Dtype, Element};
+pub use onnx::write_onnx;
+pub use safetensors::{SafeTensors, SafetensorsError};
+pub use text::{read_model, write_model, TextFormatError};
@@ -0,0 +1,323 @@
+use std::io::{self, Write};
+
+use crate::{activations::Activation, losses::Loss, Model};
+
+use super::{protobuf::Encoder, Dtype, Element};
+
+const IR_VERSION: i64 = 7;
+const OPSET_VERSION: i64 = 13;
+
+const INPUT: &str = "input";
+const OUTPUT: &str = "output";
+
+/// `TensorProto.DataType` of the element type.
+fn data_type(dtype: Dtype) -> i64 {
+    match dtype {
+        Dtype::F32 => 1,
+        Dtype::F64 => 11,
+    }
+}
+
+/// ONNX operator that implements the activation function.
+fn activation_op(name: &str) -> Option<&'static str> {
+    match name {
+        "relu" => Some("Relu"),
+        "sigmoid" => Some("Sigmoid"),
+        "tanh" => Some("Tanh"),
+        _ => None,
+    }
+}
+
+/// Returns whether the layer passes its input unchanged during inference and
+/// is left out of the graph, e.g. dropout.
+fn is_identity(name: &str) -> bool {
+    matches!(name, "dropout" | "alpha_dropout")
+}
+
+/// Writes the model as [ONNX] graph.
+///
+/// Every dense layer is exported as `Gemm` node, computing `x · W + b`,
+/// followed by the node of the activation function. Dropout layers are left
+/// out because they do not change the input during inference. The graph has
+/// a single input named `input` with the shape `[batch, inputs]` and a single
+/// output named `output` with the shape `[batch, outputs]`, where `batch` is
+/// a symbolic dimension.
+///
+/// The parameters are stored as initializers named `layers.{i}.weight` and
+/// `layers.{i}.bias`.
+///
+/// Fails with [io::ErrorKind::InvalidInput] if the model has no dense layers,
+/// the activation function has no ONNX equivalent or the model has other
+/// layers added with [Model::add].
+///
+/// [ONNX]: https://onnx.ai
+pub fn write_onnx<A, L, T, W>(model: &Model<A, L, T>, mut writer: W) -> io::Result<()>
+where
+    A: Activation<T>,
+    L: Loss<T>,
+    T: Element,
+    W: Write,
+{
+    let op = activation_op(model.activation().name()).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "activation {} can not be exported to ONNX",
+                model.activation().name()
+            ),
+        )
+    })?;
+
+    if let Some(layer) = model.layers().find(|layer| !is_identity(layer.name())) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("layer {} can not be exported to ONNX", layer.name()),
+        ));
+    }
+
+    let (n_inputs, n_outputs) = match (model.weights().first(), model.weights().last()) {
+        (Some(first), Some(last)) => (first.nrows(), last.ncols()),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "model has no layers",
+            ))
+        }
+    };
+
+    let mut model_proto = Encoder::new();
+
+    model_proto.int(1, IR_VERSION);
+    model_proto.string(2, "robit");
+    model_proto.string(3, env!("CARGO_PKG_VERSION"));
+    model_proto.message(7, |graph| {
+        let n_layers = model.weights().len();
+        let mut input = INPUT.to_owned();
+
+        for i in 0..n_layers {
+            let weight = format!("layers.{}.weight", i);
+            let bias = format!("layers.{}.bias", i);
+            let gemm = format!("layers.{}.gemm", i);
+            let output = if i + 1 == n_layers {
+                OUTPUT.to_owned()
+            } else {
+                format!("layers.{}.output", i)
+            };
+
+            node(graph, "Gemm", &gemm, &[&input, &weight, &bias], &gemm);
+            node(
+                graph,
+                op,
+                &format!("layers.{}.activation", i),
+                &[&gemm],
+                &output,
+            );
+
+            input = output;
+        }
+
+        graph.string(2, "robit");
+
+        for (i, (w, b)) in model.weights().iter().zip(model.biases()).enumerate() {
+            tensor(
+                graph,
+                &format!("layers.{}.weight", i),
+                &[w.nrows(), w.ncols()],
+                w.iter(),
+            );
+            tensor(graph, &format!("layers.{}.bias", i), &[b.len()], b.iter());
+        }
+
+        value_info::<T>(graph, 11, INPUT, n_inputs);
+        value_info::<T>(graph, 12, OUTPUT, n_outputs);
+    });
+    model_proto.message(8, |opset| {
+        opset.string(1, "");
+        opset.int(2, OPSET_VERSION);
+    });
+
+    writer.write_all(&model_proto.into_bytes())?;
+    writer.flush()
+}
+
+/// Adds a `NodeProto` to the graph.
+fn node(graph: &mut Encoder, op_type: &str, name: &str, inputs: &[&str], output: &str) {
+    graph.message(1, |node| {
+        for input in inputs {
+            node.string(1, input);
+        }
+        node.string(2, output);
+        node.string(3, name);
+        node.string(4, op_type);
+    });
+}
+
+/// Adds a `TensorProto` initializer to the graph.
+fn tensor<'a, T, I>(graph: &mut Encoder, name: &str, dims: &[usize], values: I)
+where
+    T: Element,
+    I: Iterator<Item = &'a T>,
+{
+    let mut data = vec![];
+    values.for_each(|v| v.write_le(&mut data));
+
+    graph.message(5, |tensor| {
+        for dim in dims {
+            tensor.int(1, *dim as i64);
+        }
+        tensor.int(2, data_type(T::DTYPE));
+        tensor.string(8, name);
+        tensor.bytes(9, &data);
+    });
+}
+
+/// Adds a `ValueInfoProto` for a `[batch, features]` tensor to the graph.
+fn value_info<T: Element>(graph: &mut Encoder, field: u32, name: &str, features: usize) {
+    graph.message(field, |info| {
+        info.string(1, name);
+        info.message(2, |ty| {
+            ty.message(1, |tensor_type| {
+                tensor_type.int(1, data_type(T::DTYPE));
+                tensor_type.message(2, |shape| {
+                    shape.message(1, |dim| dim.string(2, "batch"));
+                    shape.message(1, |dim| dim.int(1, features as i64));
+                });
+            });
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        initializers::RandomDistr,
+        layers::Dropout,
+        serialization::protobuf::{decode, Field},
+    };
+
+    fn model() -> Model<crate::activations::Relu, crate::losses::MeanSquaredError, f32> {
+        let mut model = Model::new(0.01);
+        model.add_layer((3, 4), RandomDistr::normal_with(0.0, 0.5).unwrap());
+        model.add_layer((4, 2), RandomDistr::normal_with(0.0, 0.5).unwrap());
+        model
+    }
+
+    fn fields(fields: &[(u32, Field)], number: u32) -> Vec<&Field> {
+        fields
+            .iter()
+            .filter(|(n, _)| *n == number)
+            .map(|(_, f)| f)
+            .collect()
+    }
+
+    fn strings(message: &[u8], number: u32) -> Vec<String> {
+        fields(&decode(message), number)
+            .into_iter()
+            .map(|f| f.as_str().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_structure() {
+        let model = model();
+        let mut buffer = vec![];
+        write_onnx(&model, &mut buffer).unwrap();
+
+        let model_proto = decode(&buffer);
+        assert_eq!(IR_VERSION, fields(&model_proto, 1)[0].as_int());
+        assert_eq!("robit", fields(&model_proto, 2)[0].as_str());
+
+        let opset = decode(fields(&model_proto, 8)[0].as_bytes());
+        assert_eq!(OPSET_VERSION, fields(&opset, 2)[0].as_int());
+
+        let graph = decode(fields(&model_proto, 7)[0].as_bytes());
+        let nodes: Vec<&[u8]> = fields(&graph, 1).into_iter().map(Field::as_bytes).collect();
+
+        assert_eq!(
+            vec!["Gemm", "Relu", "Gemm", "Relu"],
+            nodes
+                .iter()
+                .map(|n| strings(n, 4)[0].clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["input", "layers.0.weight", "layers.0.bias"],
+            strings(nodes[0], 1)
+        );
+        assert_eq!(vec!["layers.0.output".to_owned()], strings(nodes[1], 2));
+        assert_eq!(
+            vec!["layers.0.output".to_owned()],
+            strings(nodes[2], 1)[..1]
+        );
+        assert_eq!(vec!["output".to_owned()], strings(nodes[3], 2));
+    }
+
+    #[test]
+    fn test_initializers() {
+        let model = model();
+        let mut buffer = vec![];
+        write_onnx(&model, &mut buffer).unwrap();
+
+        let model_proto = decode(&buffer);
+        let graph = decode(fields(&model_proto, 7)[0].as_bytes());
+        let initializers = fields(&graph, 5);
+
+        assert_eq!(4, initializers.len());
+
+        let weight = decode(initializers[0].as_bytes());
+        let dims: Vec<i64> = fields(&weight, 1).iter().map(|f| f.as_int()).collect();
+        let raw_data = fields(&weight, 9)[0].as_bytes();
+
+        assert_eq!(vec![3, 4], dims);
+        assert_eq!(1, fields(&weight, 2)[0].as_int());
+        assert_eq!("layers.0.weight", fields(&weight, 8)[0].as_str());
+        assert_eq!(
+            model.weights()[0][(0, 1)],
+            f32::from_le_bytes(raw_data[4..8].try_into().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_inputs_and_outputs() {
+        let mut buffer = vec![];
+        write_onnx(&model(), &mut buffer).unwrap();
+
+        let model_proto = decode(&buffer);
+        let graph = decode(fields(&model_proto, 7)[0].as_bytes());
+
+        let output = decode(fields(&graph, 12)[0].as_bytes());
+        assert_eq!("output", fields(&output, 1)[0].as_str());
+
+        let ty = decode(fields(&output, 2)[0].as_bytes());
+        let tensor_type = decode(fields(&ty, 1)[0].as_bytes());
+        let shape = decode(fields(&tensor_type, 2)[0].as_bytes());
+        let dims = fields(&shape, 1);
+
+        assert_eq!("batch", fields(&decode(dims[0].as_bytes()), 2)[0].as_str());
+        assert_eq!(2, fields(&decode(dims[1].as_bytes()), 1)[0].as_int());
+    }
+
+    #[test]
+    fn test_empty_model() {
+        let model: Model<crate::activations::Relu, crate::losses::MeanSquaredError, f32> =
+            Model::new(0.01);
+
+        let err = write_onnx(&model, vec![]).err().unwrap();
+
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    }
+
+    #[test]
+    fn test_dropout() {
+        let mut model = model();
+        let mut expected = vec![];
+        write_onnx(&model, &mut expected).unwrap();
+
+        model.add(Dropout::new(0.5).unwrap());
+        let mut buffer = vec![];
+        write_onnx(&model, &mut buffer).unwrap();
+
+        assert_eq!(expected, buffer);
+    }
+}
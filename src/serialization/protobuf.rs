@@ -0,0 +1,176 @@
+//! Minimal protocol buffers encoding for the ONNX exporter.
+
+const VARINT: u64 = 0;
+const LENGTH_DELIMITED: u64 = 2;
+
+/// Encodes the fields of a message in the order they are added.
+#[derive(Debug, Default)]
+pub(crate) struct Encoder {
+    buffer: Vec<u8>,
+}
+
+impl Encoder {
+    pub(crate) fn new() -> Self {
+        Self { buffer: vec![] }
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    fn raw_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buffer.push((value as u8 & 0x7f) | 0x80);
+            value >>= 7;
+        }
+
+        self.buffer.push(value as u8);
+    }
+
+    fn key(&mut self, field: u32, wire_type: u64) {
+        self.raw_varint(((field as u64) << 3) | wire_type);
+    }
+
+    /// Encodes an `int32`, `int64` or enum field.
+    pub(crate) fn int(&mut self, field: u32, value: i64) {
+        self.key(field, VARINT);
+        self.raw_varint(value as u64);
+    }
+
+    pub(crate) fn bytes(&mut self, field: u32, value: &[u8]) {
+        self.key(field, LENGTH_DELIMITED);
+        self.raw_varint(value.len() as u64);
+        self.buffer.extend_from_slice(value);
+    }
+
+    pub(crate) fn string(&mut self, field: u32, value: &str) {
+        self.bytes(field, value.as_bytes());
+    }
+
+    /// Encodes an embedded message whose fields are added by `encode`.
+    pub(crate) fn message<F: FnOnce(&mut Encoder)>(&mut self, field: u32, encode: F) {
+        let mut message = Encoder::new();
+        encode(&mut message);
+
+        self.bytes(field, &message.buffer);
+    }
+}
+
+/// Value of a decoded field.
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Field {
+    Varint(u64),
+    Bytes(Vec<u8>),
+}
+
+#[cfg(test)]
+impl Field {
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        match self {
+            Field::Bytes(bytes) => bytes,
+            Field::Varint(_) => panic!("expected bytes, found varint"),
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        std::str::from_utf8(self.as_bytes()).unwrap()
+    }
+
+    pub(crate) fn as_int(&self) -> i64 {
+        match self {
+            Field::Varint(value) => *value as i64,
+            Field::Bytes(_) => panic!("expected varint, found bytes"),
+        }
+    }
+}
+
+/// Decodes the fields of a message that only uses varint and
+/// length-delimited fields.
+#[cfg(test)]
+pub(crate) fn decode(mut data: &[u8]) -> Vec<(u32, Field)> {
+    fn varint(data: &mut &[u8]) -> u64 {
+        let mut value = 0;
+        let mut shift = 0;
+
+        loop {
+            let byte = data[0];
+            *data = &data[1..];
+            value |= ((byte & 0x7f) as u64) << shift;
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                return value;
+            }
+        }
+    }
+
+    let mut fields = vec![];
+
+    while !data.is_empty() {
+        let key = varint(&mut data);
+        let field = (key >> 3) as u32;
+
+        match key & 7 {
+            VARINT => fields.push((field, Field::Varint(varint(&mut data)))),
+            LENGTH_DELIMITED => {
+                let len = varint(&mut data) as usize;
+                fields.push((field, Field::Bytes(data[..len].to_vec())));
+                data = &data[len..];
+            }
+            wire_type => panic!("unsupported wire type {}", wire_type),
+        }
+    }
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint() {
+        let mut encoder = Encoder::new();
+        encoder.int(1, 150);
+
+        assert_eq!(vec![0x08, 0x96, 0x01], encoder.into_bytes());
+    }
+
+    #[test]
+    fn test_string() {
+        let mut encoder = Encoder::new();
+        encoder.string(2, "testing");
+
+        assert_eq!(
+            vec![0x12, 0x07, b't', b'e', b's', b't', b'i', b'n', b'g'],
+            encoder.into_bytes()
+        );
+    }
+
+    #[test]
+    fn test_negative_int() {
+        let mut encoder = Encoder::new();
+        encoder.int(1, -1);
+        let bytes = encoder.into_bytes();
+
+        assert_eq!(11, bytes.len());
+        assert_eq!(-1, decode(&bytes)[0].1.as_int());
+    }
+
+    #[test]
+    fn test_message() {
+        let mut encoder = Encoder::new();
+        encoder.message(3, |m| {
+            m.int(1, 1);
+            m.string(2, "a");
+        });
+
+        let fields = decode(&encoder.into_bytes());
+        let inner = decode(fields[0].1.as_bytes());
+
+        assert_eq!(3, fields[0].0);
+        assert_eq!((1, Field::Varint(1)), inner[0]);
+        assert_eq!("a", inner[1].1.as_str());
+    }
+}
@@ -0,0 +1,665 @@
+use std::{
+    collections::BTreeMap,
+    error,
+    fmt::{self, Display},
+    io::{self, Read, Write},
+};
+
+use ndarray::{Array2, ArrayD, Ix1, Ix2, IxDyn};
+
+use crate::{activations::Activation, losses::Loss, Model};
+
+use super::{
+    json::{self, Value},
+    Dtype, Element,
+};
+
+/// Headers larger than this are rejected, as recommended by the format.
+const MAX_HEADER_SIZE: u64 = 100_000_000;
+
+const METADATA_KEY: &str = "__metadata__";
+
+/// Error of reading a safetensors file or loading its tensors into a model.
+#[derive(Debug)]
+pub enum SafetensorsError {
+    Io(io::Error),
+
+    /// The JSON header or the tensor offsets are malformed.
+    InvalidHeader(String),
+
+    /// A tensor has a dtype that can not be converted to floats.
+    UnsupportedDtype {
+        name: String,
+        dtype: String,
+    },
+
+    /// A tensor that the model requires is not in the file.
+    MissingTensor(String),
+
+    /// A tensor has a different shape than the parameter of the model.
+    ShapeMismatch {
+        name: String,
+        expected: Vec<usize>,
+        found: Vec<usize>,
+    },
+}
+
+impl Display for SafetensorsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SafetensorsError::Io(err) => write!(f, "{}", err),
+            SafetensorsError::InvalidHeader(message) => {
+                write!(f, "invalid safetensors header: {}", message)
+            }
+            SafetensorsError::UnsupportedDtype { name, dtype } => {
+                write!(f, "tensor {} has unsupported dtype {}", name, dtype)
+            }
+            SafetensorsError::MissingTensor(name) => write!(f, "missing tensor {}", name),
+            SafetensorsError::ShapeMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "tensor {} has shape {:?}, expected {:?}",
+                name, found, expected
+            ),
+        }
+    }
+}
+
+impl error::Error for SafetensorsError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SafetensorsError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SafetensorsError {
+    fn from(err: io::Error) -> Self {
+        SafetensorsError::Io(err)
+    }
+}
+
+/// Dtypes that can be read from a file and converted to floats.
+#[derive(Debug, Clone, Copy)]
+enum StoredDtype {
+    F16,
+    BF16,
+    F32,
+    F64,
+}
+
+impl StoredDtype {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "F16" => Some(StoredDtype::F16),
+            "BF16" => Some(StoredDtype::BF16),
+            "F32" => Some(StoredDtype::F32),
+            "F64" => Some(StoredDtype::F64),
+            _ => None,
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            StoredDtype::F16 | StoredDtype::BF16 => 2,
+            StoredDtype::F32 => 4,
+            StoredDtype::F64 => 8,
+        }
+    }
+
+    fn read<T: Element>(&self, bytes: &[u8]) -> T {
+        match self {
+            StoredDtype::F16 => {
+                T::from_f64(f16_to_f32(u16::from_le_bytes([bytes[0], bytes[1]])) as f64)
+            }
+            StoredDtype::BF16 => {
+                let bits = u16::from_le_bytes([bytes[0], bytes[1]]) as u32;
+
+                T::from_f64(f32::from_bits(bits << 16) as f64)
+            }
+            StoredDtype::F32 => T::from_f64(f32::read_le(bytes) as f64),
+            StoredDtype::F64 => T::from_f64(f64::read_le(bytes)),
+        }
+    }
+}
+
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits >> 15 == 1 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as u32;
+    let fraction = (bits & 0x3ff) as u32;
+
+    let value = match exponent {
+        0 => fraction as f32 * 2f32.powi(-24),
+        31 if fraction == 0 => f32::INFINITY,
+        31 => f32::NAN,
+        _ => f32::from_bits(((exponent + 112) << 23) | (fraction << 13)),
+    };
+
+    sign * value
+}
+
+fn dtype_name(dtype: Dtype) -> &'static str {
+    match dtype {
+        Dtype::F32 => "F32",
+        Dtype::F64 => "F64",
+    }
+}
+
+/// Named tensors in the [safetensors] format.
+///
+/// Files with `F16`, `BF16`, `F32` and `F64` tensors can be read, the values
+/// are converted to `T`. Tensors are written with the dtype of `T`.
+///
+/// # Examples
+///
+/// ```
+/// use ndarray::{arr2, ArrayD};
+/// use robit::serialization::SafeTensors;
+///
+/// let mut tensors = SafeTensors::new();
+/// tensors.insert("weight", arr2(&[[1.0f32, 2.0]]).into_dyn());
+///
+/// let mut buffer = vec![];
+/// tensors.write(&mut buffer).unwrap();
+///
+/// let read: SafeTensors<f32> = SafeTensors::read(buffer.as_slice()).unwrap();
+/// assert_eq!(tensors.get("weight"), read.get("weight"));
+/// ```
+///
+/// [safetensors]: https://github.com/huggingface/safetensors
+#[derive(Debug, Clone, PartialEq)]
+pub struct SafeTensors<T = f64> {
+    tensors: BTreeMap<String, ArrayD<T>>,
+    metadata: BTreeMap<String, String>,
+}
+
+impl<T> SafeTensors<T> {
+    pub fn new() -> Self {
+        Self {
+            tensors: BTreeMap::new(),
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, name: &str, tensor: ArrayD<T>) {
+        self.tensors.insert(name.to_owned(), tensor);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ArrayD<T>> {
+        self.tensors.get(name)
+    }
+
+    /// Names of the tensors in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.tensors.keys().map(String::as_str)
+    }
+
+    /// Free-form string metadata stored in the header.
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+
+    pub fn set_metadata(&mut self, key: &str, value: &str) {
+        self.metadata.insert(key.to_owned(), value.to_owned());
+    }
+}
+
+impl<T> Default for SafeTensors<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Element> SafeTensors<T> {
+    /// Returns the parameters of the model. The weight and bias of dense
+    /// layer `i` are named `layers.{i}.weight` and `layers.{i}.bias`.
+    ///
+    /// Weights are stored transposed with the shape `[outputs, inputs]`, the
+    /// layout of `torch.nn.Linear`.
+    ///
+    /// The parameters and state of the other layers are named after the
+    /// layer in the [summary](Model::summary), e.g. `batch_norm_1d_0.parameters.1`
+    /// or `batch_norm_1d_0.state.0`, in the order of
+    /// [Layer::parameters](crate::layers::Layer::parameters) and
+    /// [Layer::state](crate::layers::Layer::state).
+    pub fn from_model<A, L>(model: &Model<A, L, T>) -> Self
+    where
+        A: Activation<T>,
+        L: Loss<T>,
+    {
+        let mut tensors = Self::new();
+
+        tensors.set_metadata("format", "robit");
+        tensors.set_metadata("activation", model.activation().name());
+        tensors.set_metadata("loss", model.loss().name());
+
+        for (i, (w, b)) in model.weights().iter().zip(model.biases()).enumerate() {
+            let name = layer_name(i);
+
+            tensors.insert(
+                &format!("{}.weight", name),
+                w.t().as_standard_layout().into_owned().into_dyn(),
+            );
+            tensors.insert(&format!("{}.bias", name), b.clone().into_dyn());
+        }
+
+        for (name, layer) in model.layer_names().iter().zip(model.layers()) {
+            for (j, p) in layer.parameters().into_iter().enumerate() {
+                tensors.insert(&format!("{}.parameters.{}", name, j), p.clone());
+            }
+
+            for (j, s) in layer.state().into_iter().enumerate() {
+                tensors.insert(&format!("{}.state.{}", name, j), s.clone());
+            }
+        }
+
+        tensors
+    }
+
+    /// Replaces the parameters of the model with the tensors named like
+    /// [SafeTensors::from_model] does.
+    pub fn load_parameters<A, L>(&self, model: &mut Model<A, L, T>) -> Result<(), SafetensorsError>
+    where
+        A: Activation<T>,
+        L: Loss<T>,
+    {
+        self.load_parameters_with(model, layer_name)
+    }
+
+    /// Replaces the parameters of the model with the tensors named
+    /// `{prefix}.weight` and `{prefix}.bias`, where `prefix` returns the
+    /// prefix of a dense layer index, e.g. `0`, `2` and `4` for the linear
+    /// layers of a `torch.nn.Sequential` with interleaved activations. The
+    /// tensors of the other layers are named like [SafeTensors::from_model]
+    /// does.
+    ///
+    /// The model is left unchanged if a tensor is missing or has a different
+    /// shape than the parameter it replaces.
+    pub fn load_parameters_with<A, L, F>(
+        &self,
+        model: &mut Model<A, L, T>,
+        prefix: F,
+    ) -> Result<(), SafetensorsError>
+    where
+        A: Activation<T>,
+        L: Loss<T>,
+        F: Fn(usize) -> String,
+    {
+        let mut weights = Vec::with_capacity(model.weights().len());
+        let mut biases = Vec::with_capacity(model.biases().len());
+
+        for (i, w) in model.weights().iter().enumerate() {
+            let prefix = prefix(i);
+            let (n_inputs, n_outputs) = w.dim();
+
+            let weight = self.parameter(&format!("{}.weight", prefix), &[n_outputs, n_inputs])?;
+            let bias = self.parameter(&format!("{}.bias", prefix), &[n_outputs])?;
+
+            let weight: Array2<T> = weight.into_dimensionality::<Ix2>().unwrap();
+            weights.push(weight.t().as_standard_layout().into_owned());
+            biases.push(bias.into_dimensionality::<Ix1>().unwrap());
+        }
+
+        let mut layers = vec![];
+
+        for (name, layer) in model.layer_names().iter().zip(model.layers()) {
+            for (j, p) in layer.parameters().into_iter().enumerate() {
+                layers.push(self.parameter(&format!("{}.parameters.{}", name, j), p.shape())?);
+            }
+
+            for (j, s) in layer.state().into_iter().enumerate() {
+                layers.push(self.parameter(&format!("{}.state.{}", name, j), s.shape())?);
+            }
+        }
+
+        model.set_parameters(weights, biases);
+        model.set_layer_tensors(layers);
+
+        Ok(())
+    }
+
+    fn parameter(&self, name: &str, shape: &[usize]) -> Result<ArrayD<T>, SafetensorsError> {
+        let tensor = self
+            .get(name)
+            .ok_or_else(|| SafetensorsError::MissingTensor(name.to_owned()))?;
+
+        if tensor.shape() != shape {
+            return Err(SafetensorsError::ShapeMismatch {
+                name: name.to_owned(),
+                expected: shape.to_vec(),
+                found: tensor.shape().to_vec(),
+            });
+        }
+
+        Ok(tensor.clone())
+    }
+
+    /// Writes the tensors in alphabetical order.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut header = String::from("{");
+        let mut data = vec![];
+
+        if !self.metadata.is_empty() {
+            json::write_string(&mut header, METADATA_KEY);
+            header.push_str(":{");
+
+            for (i, (key, value)) in self.metadata.iter().enumerate() {
+                if i > 0 {
+                    header.push(',');
+                }
+                json::write_string(&mut header, key);
+                header.push(':');
+                json::write_string(&mut header, value);
+            }
+
+            header.push('}');
+        }
+
+        for (name, tensor) in self.tensors.iter() {
+            let begin = data.len();
+            tensor.iter().for_each(|v| v.write_le(&mut data));
+
+            if header.len() > 1 {
+                header.push(',');
+            }
+
+            json::write_string(&mut header, name);
+            header.push_str(&format!(
+                ":{{\"dtype\":\"{}\",\"shape\":{:?},\"data_offsets\":[{},{}]}}",
+                dtype_name(T::DTYPE),
+                tensor.shape(),
+                begin,
+                data.len()
+            ));
+        }
+
+        header.push('}');
+
+        // Pads the header so that the data is aligned to 8 bytes.
+        while header.len() % 8 != 0 {
+            header.push(' ');
+        }
+
+        writer.write_all(&(header.len() as u64).to_le_bytes())?;
+        writer.write_all(header.as_bytes())?;
+        writer.write_all(&data)?;
+        writer.flush()
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, SafetensorsError> {
+        let mut size = [0; 8];
+        reader.read_exact(&mut size)?;
+        let size = u64::from_le_bytes(size);
+
+        if size > MAX_HEADER_SIZE {
+            return Err(invalid(format!("header size {} is too large", size)));
+        }
+
+        let mut header = vec![0; size as usize];
+        reader.read_exact(&mut header)?;
+
+        let header = String::from_utf8(header).map_err(|_| invalid("not UTF-8".to_owned()))?;
+        let header = json::parse(&header).map_err(invalid)?;
+        let members = header
+            .as_object()
+            .ok_or_else(|| invalid("not an object".to_owned()))?;
+
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+
+        let mut tensors = Self::new();
+
+        for (name, info) in members {
+            if name == METADATA_KEY {
+                for (key, value) in info.as_object().unwrap_or_default() {
+                    let value = value
+                        .as_str()
+                        .ok_or_else(|| invalid(format!("metadata {} is not a string", key)))?;
+                    tensors.set_metadata(key, value);
+                }
+
+                continue;
+            }
+
+            tensors.insert(name, read_tensor(name, info, &data)?);
+        }
+
+        Ok(tensors)
+    }
+}
+
+fn layer_name(i: usize) -> String {
+    format!("layers.{}", i)
+}
+
+fn invalid(message: String) -> SafetensorsError {
+    SafetensorsError::InvalidHeader(message)
+}
+
+fn read_tensor<T: Element>(
+    name: &str,
+    info: &Value,
+    data: &[u8],
+) -> Result<ArrayD<T>, SafetensorsError> {
+    let field = |key: &str| {
+        info.get(key)
+            .ok_or_else(|| invalid(format!("tensor {} has no {}", name, key)))
+    };
+    let integers = |value: &Value| {
+        value
+            .as_array()
+            .and_then(|values| {
+                values
+                    .iter()
+                    .map(|v| v.as_u64().map(|v| v as usize))
+                    .collect::<Option<Vec<usize>>>()
+            })
+            .ok_or_else(|| invalid(format!("tensor {} has invalid integers", name)))
+    };
+
+    let dtype_name = field("dtype")?
+        .as_str()
+        .ok_or_else(|| invalid(format!("tensor {} has invalid dtype", name)))?;
+    let dtype =
+        StoredDtype::parse(dtype_name).ok_or_else(|| SafetensorsError::UnsupportedDtype {
+            name: name.to_owned(),
+            dtype: dtype_name.to_owned(),
+        })?;
+    let shape = integers(field("shape")?)?;
+    let offsets = integers(field("data_offsets")?)?;
+
+    let (begin, end) = match offsets[..] {
+        [begin, end] if begin <= end && end <= data.len() => (begin, end),
+        _ => return Err(invalid(format!("tensor {} has invalid data offsets", name))),
+    };
+
+    let n_values = shape
+        .iter()
+        .try_fold(1usize, |n, d| n.checked_mul(*d))
+        .ok_or_else(|| invalid(format!("tensor {} is too large", name)))?;
+
+    if n_values.checked_mul(dtype.size()) != Some(end - begin) {
+        return Err(invalid(format!(
+            "tensor {} has {} bytes for {} values",
+            name,
+            end - begin,
+            n_values
+        )));
+    }
+
+    let values = data[begin..end]
+        .chunks_exact(dtype.size())
+        .map(|bytes| dtype.read(bytes))
+        .collect();
+
+    Ok(ArrayD::from_shape_vec(IxDyn(&shape), values).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::arr2;
+
+    use super::*;
+    use crate::{
+        initializers::RandomDistr,
+        layers::{BatchNorm1d, Layer},
+    };
+
+    fn model() -> Model {
+        let mut model = Model::new(0.01);
+        model.add_layer((3, 4), RandomDistr::normal());
+        model.add_layer((4, 2), RandomDistr::normal());
+        model
+    }
+
+    /// Builds a file by hand with the given header and data.
+    fn file(header: &str, data: &[u8]) -> Vec<u8> {
+        let mut bytes = (header.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tensors = SafeTensors::from_model(&model());
+
+        let mut buffer = vec![];
+        tensors.write(&mut buffer).unwrap();
+
+        assert_eq!(0, (u64::from_le_bytes(buffer[..8].try_into().unwrap())) % 8);
+        assert_eq!(tensors, SafeTensors::read(buffer.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn test_load_parameters() {
+        let source = model();
+        let mut target = model();
+
+        SafeTensors::from_model(&source)
+            .load_parameters(&mut target)
+            .unwrap();
+
+        assert_eq!(source.weights(), target.weights());
+        assert_eq!(source.biases(), target.biases());
+    }
+
+    #[test]
+    fn test_layers() {
+        let mut source = model();
+        let mut norm = BatchNorm1d::new(2);
+        norm.state_mut()[0].fill(0.5);
+        source.add(norm);
+
+        let mut target = model();
+        target.add(BatchNorm1d::new(2));
+
+        let tensors = SafeTensors::from_model(&source);
+        assert_eq!(
+            &[2],
+            tensors.get("batch_norm_1d_0.parameters.1").unwrap().shape()
+        );
+
+        tensors.load_parameters(&mut target).unwrap();
+        assert_eq!(source.layer_tensors(), target.layer_tensors());
+
+        let err = SafeTensors::from_model(&model())
+            .load_parameters(&mut target)
+            .err()
+            .unwrap();
+        assert!(matches!(err, SafetensorsError::MissingTensor(_)));
+    }
+
+    #[test]
+    fn test_torch_layout() {
+        let tensors = SafeTensors::from_model(&model());
+
+        assert_eq!(&[4, 3], tensors.get("layers.0.weight").unwrap().shape());
+        assert_eq!(&[2], tensors.get("layers.1.bias").unwrap().shape());
+        assert_eq!(
+            Some(&"relu".to_owned()),
+            tensors.metadata().get("activation")
+        );
+    }
+
+    #[test]
+    fn test_shape_mismatch() {
+        let mut target: Model = Model::new(0.01);
+        target.add_layer((4, 4), RandomDistr::normal());
+        target.add_layer((4, 2), RandomDistr::normal());
+        let weights = target.weights().to_vec();
+
+        let err = SafeTensors::from_model(&model())
+            .load_parameters(&mut target)
+            .err()
+            .unwrap();
+
+        assert!(matches!(err, SafetensorsError::ShapeMismatch { .. }));
+        assert_eq!(weights, target.weights());
+    }
+
+    #[test]
+    fn test_load_parameters_with() {
+        let mut tensors = SafeTensors::new();
+        tensors.insert("0.weight", arr2(&[[1.0, 2.0]]).into_dyn());
+        tensors.insert("0.bias", ndarray::arr1(&[0.5]).into_dyn());
+
+        let mut target: Model = Model::new(0.01);
+        target.add_layer((2, 1), RandomDistr::normal());
+        tensors
+            .load_parameters_with(&mut target, |i| (2 * i).to_string())
+            .unwrap();
+
+        assert_eq!(arr2(&[[1.0], [2.0]]), target.weights()[0]);
+    }
+
+    #[test]
+    fn test_read_f16_and_bf16() {
+        let header = r#"{"a":{"dtype":"F16","shape":[2],"data_offsets":[0,4]},"b":{"dtype":"BF16","shape":[1],"data_offsets":[4,6]}}"#;
+        // 1.0 and -2.5 as f16, 1.5 as bf16
+        let data = [0x00, 0x3c, 0x00, 0xc1, 0xc0, 0x3f];
+
+        let tensors: SafeTensors<f32> = SafeTensors::read(file(header, &data).as_slice()).unwrap();
+
+        assert_eq!(&[1.0, -2.5], tensors.get("a").unwrap().as_slice().unwrap());
+        assert_eq!(&[1.5], tensors.get("b").unwrap().as_slice().unwrap());
+    }
+
+    #[test]
+    fn test_read_converts_dtype() {
+        let mut tensors = SafeTensors::new();
+        tensors.insert("x", arr2(&[[0.25f32]]).into_dyn());
+
+        let mut buffer = vec![];
+        tensors.write(&mut buffer).unwrap();
+
+        let read: SafeTensors<f64> = SafeTensors::read(buffer.as_slice()).unwrap();
+        assert_eq!(0.25, read.get("x").unwrap()[[0, 0]]);
+    }
+
+    #[test]
+    fn test_invalid_offsets() {
+        let header = r#"{"a":{"dtype":"F32","shape":[2],"data_offsets":[0,4]}}"#;
+
+        let err = SafeTensors::<f32>::read(file(header, &[0; 4]).as_slice())
+            .err()
+            .unwrap();
+
+        assert!(matches!(err, SafetensorsError::InvalidHeader(_)));
+    }
+
+    #[test]
+    fn test_unsupported_dtype() {
+        let header = r#"{"a":{"dtype":"I64","shape":[1],"data_offsets":[0,8]}}"#;
+
+        let err = SafeTensors::<f32>::read(file(header, &[0; 8]).as_slice())
+            .err()
+            .unwrap();
+
+        assert!(matches!(err, SafetensorsError::UnsupportedDtype { .. }));
+    }
+}
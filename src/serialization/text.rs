@@ -0,0 +1,556 @@
+use std::{
+    error,
+    fmt::{self, Display},
+    io::{self, BufRead, Write},
+    str::FromStr,
+};
+
+use ndarray::{Array1, Array2, ArrayD, ScalarOperand};
+use num_traits::{Float, FromPrimitive};
+
+use crate::{
+    activations::Activation,
+    layers::{self, Layer},
+    losses::Loss,
+    model::Node,
+    Model,
+};
+
+const MAGIC: &str = "robit-model";
+
+const VERSION: u32 = 1;
+
+/// Error of reading a model in the text format.
+#[derive(Debug)]
+pub enum TextFormatError {
+    Io(io::Error),
+
+    /// The file was written in a format version this build can not read.
+    UnsupportedVersion(u32),
+
+    /// The data ends before the model is complete.
+    UnexpectedEof {
+        line: usize,
+    },
+
+    /// A line is malformed, e.g. it lacks the expected keyword or has a
+    /// value that can not be parsed.
+    InvalidLine {
+        line: usize,
+        message: String,
+    },
+
+    /// The data is well-formed but can not be loaded into the model, e.g.
+    /// because of a different activation function.
+    Incompatible(String),
+}
+
+impl Display for TextFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextFormatError::Io(err) => write!(f, "{}", err),
+            TextFormatError::UnsupportedVersion(version) => {
+                write!(f, "unsupported version {}", version)
+            }
+            TextFormatError::UnexpectedEof { line } => {
+                write!(f, "line {}: unexpected end of file", line)
+            }
+            TextFormatError::InvalidLine { line, message } => {
+                write!(f, "line {}: {}", line, message)
+            }
+            TextFormatError::Incompatible(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl error::Error for TextFormatError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            TextFormatError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TextFormatError {
+    fn from(err: io::Error) -> Self {
+        TextFormatError::Io(err)
+    }
+}
+
+/// Writes the model in the text format.
+pub fn write_model<A, L, T, W>(model: &Model<A, L, T>, mut writer: W) -> io::Result<()>
+where
+    A: Activation<T>,
+    L: Loss<T>,
+    T: Display + Copy,
+    W: Write,
+{
+    writeln!(writer, "{} {}", MAGIC, VERSION)?;
+    writeln!(writer, "activation {}", model.activation().name())?;
+    writeln!(writer, "loss {}", model.loss().name())?;
+    writeln!(writer, "learning_rate {}", model.learning_rate())?;
+    writeln!(writer, "batch_size {}", model.batch_size())?;
+    let nodes = model.nodes();
+    let layers: Vec<&dyn Layer<T>> = model.layers().collect();
+    writeln!(writer, "layers {}", nodes.len())?;
+
+    for node in nodes {
+        match node {
+            Node::Dense(i) => {
+                let (w, b) = (&model.weights()[i], &model.biases()[i]);
+                writeln!(writer, "dense {} {}", w.nrows(), w.ncols())?;
+
+                writeln!(writer, "weights")?;
+                for row in w.rows() {
+                    write_values(&mut writer, row.iter())?;
+                }
+
+                writeln!(writer, "biases")?;
+                write_values(&mut writer, b.iter())?;
+            }
+            Node::Layer(k) => {
+                let layer = layers[k];
+                let mut line = vec![layer.name().to_owned()];
+                line.extend(layer.config().iter().map(f64::to_string));
+                writeln!(writer, "layer {}", line.join(" "))?;
+
+                write_tensors(&mut writer, "parameters", &layer.parameters())?;
+                write_tensors(&mut writer, "state", &layer.state())?;
+            }
+        }
+    }
+
+    writer.flush()
+}
+
+/// Writes the keyword followed by one line per tensor, nothing if there are
+/// no tensors.
+fn write_tensors<T: Display, W: Write>(
+    writer: &mut W,
+    keyword: &str,
+    tensors: &[&ArrayD<T>],
+) -> io::Result<()> {
+    if tensors.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(writer, "{}", keyword)?;
+    for tensor in tensors {
+        write_values(writer, tensor.iter())?;
+    }
+
+    Ok(())
+}
+
+fn write_values<'a, T, W, I>(writer: &mut W, values: I) -> io::Result<()>
+where
+    T: Display + 'a,
+    W: Write,
+    I: Iterator<Item = &'a T>,
+{
+    let line = values.map(|v| v.to_string()).collect::<Vec<_>>().join(" ");
+
+    writeln!(writer, "{}", line)
+}
+
+/// Reads a model in the text format.
+///
+/// Fails with a [TextFormatError] if the data can not be read or is
+/// malformed, or if the stored activation or loss function does not match `A`
+/// and `L`.
+pub fn read_model<A, L, T, R>(mut reader: R) -> Result<Model<A, L, T>, TextFormatError>
+where
+    A: Activation<T> + Default,
+    L: Loss<T> + Default,
+    T: FromStr + Float + FromPrimitive + ScalarOperand,
+    R: BufRead,
+{
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+
+    let mut lines = Lines {
+        lines: data.lines(),
+        number: 0,
+        remaining: data.len(),
+    };
+
+    let version: u32 = lines.field(MAGIC)?;
+    if version != VERSION {
+        return Err(TextFormatError::UnsupportedVersion(version));
+    }
+
+    let activation: String = lines.field("activation")?;
+    let loss: String = lines.field("loss")?;
+    let learning_rate: T = lines.field("learning_rate")?;
+    let batch_size: usize = lines.field("batch_size")?;
+    let n_layers: usize = lines.field("layers")?;
+
+    let mut model: Model<A, L, T> = Model::new(learning_rate);
+    model.set_batch_size(batch_size);
+
+    if activation != model.activation().name() {
+        return Err(TextFormatError::Incompatible(format!(
+            "expected activation {}, found {}",
+            model.activation().name(),
+            activation
+        )));
+    }
+
+    if loss != model.loss().name() {
+        return Err(TextFormatError::Incompatible(format!(
+            "expected loss {}, found {}",
+            model.loss().name(),
+            loss
+        )));
+    }
+
+    // the counts are not trusted for allocations, the vectors grow with the
+    // data that is actually read
+    let mut weights = vec![];
+    let mut biases = vec![];
+    let mut layers = vec![];
+    // number of outputs of the previous layer if it is a dense layer
+    let mut previous = None;
+
+    for _ in 0..n_layers {
+        let (n_inputs, n_outputs) = match lines.entry()? {
+            Entry::Dense(n_inputs, n_outputs) => (n_inputs, n_outputs),
+            Entry::Layer(name, config) => {
+                // every value takes at least one byte
+                let mut layer = layers::layer_from_config::<T>(&name, &config, lines.remaining)
+                    .ok_or_else(|| lines.error(format!("invalid layer {}", name)))?;
+
+                lines.tensors("parameters", layer.parameters_mut())?;
+                lines.tensors("state", layer.state_mut())?;
+
+                layers.push((weights.len(), layer));
+                previous = None;
+                continue;
+            }
+        };
+
+        if let Some(previous) = previous {
+            if previous != n_inputs {
+                return Err(lines.error(format!(
+                    "layer expects {} inputs, previous layer has {} outputs",
+                    n_inputs, previous
+                )));
+            }
+        }
+        previous = Some(n_outputs);
+
+        if n_inputs.checked_mul(n_outputs).is_none() {
+            return Err(lines.error(format!(
+                "dense layer with {} inputs and {} outputs is too large",
+                n_inputs, n_outputs
+            )));
+        }
+
+        lines.keyword("weights")?;
+        let mut values = vec![];
+        for _ in 0..n_inputs {
+            values.extend(lines.values::<T>(n_outputs)?);
+        }
+        weights.push(Array2::from_shape_vec((n_inputs, n_outputs), values).unwrap());
+
+        lines.keyword("biases")?;
+        biases.push(Array1::from_vec(lines.values(n_outputs)?));
+    }
+
+    model.set_parameters(weights, biases);
+    model.set_layers(layers);
+    model
+        .check_shapes()
+        .map_err(|err| TextFormatError::Incompatible(err.to_string()))?;
+
+    Ok(model)
+}
+
+/// Header line of a layer.
+enum Entry {
+    /// Number of inputs and outputs of a dense layer.
+    Dense(usize, usize),
+
+    /// Name and configuration of a layer added with [Model::add].
+    Layer(String, Vec<f64>),
+}
+
+struct Lines<R> {
+    lines: io::Lines<R>,
+    number: usize,
+
+    /// Number of bytes that have not been read yet.
+    remaining: usize,
+}
+
+impl<R: BufRead> Lines<R> {
+    fn next(&mut self) -> Result<String, TextFormatError> {
+        self.number += 1;
+
+        match self.lines.next() {
+            Some(line) => {
+                let line = line?;
+                self.remaining = self.remaining.saturating_sub(line.len() + 1);
+                Ok(line)
+            }
+            None => Err(TextFormatError::UnexpectedEof { line: self.number }),
+        }
+    }
+
+    fn error(&self, message: String) -> TextFormatError {
+        TextFormatError::InvalidLine {
+            line: self.number,
+            message,
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), TextFormatError> {
+        let line = self.next()?;
+
+        if line.trim() != keyword {
+            return Err(self.error(format!("expected {}", keyword)));
+        }
+
+        Ok(())
+    }
+
+    /// Reads a line that consists of the keyword followed by values.
+    fn tokens(&mut self, keyword: &str, n: usize) -> Result<Vec<String>, TextFormatError> {
+        let line = self.next()?;
+        let mut tokens = line.split_whitespace();
+
+        if tokens.next() != Some(keyword) {
+            return Err(self.error(format!("expected {}", keyword)));
+        }
+
+        let values: Vec<String> = tokens.map(str::to_owned).collect();
+
+        if values.len() != n {
+            return Err(self.error(format!("expected {} values after {}", n, keyword)));
+        }
+
+        Ok(values)
+    }
+
+    fn parse<F: FromStr>(&self, keyword: &str, value: &str) -> Result<F, TextFormatError> {
+        value
+            .parse()
+            .map_err(|_| self.error(format!("invalid value of {}", keyword)))
+    }
+
+    fn field<F: FromStr>(&mut self, keyword: &str) -> Result<F, TextFormatError> {
+        let tokens = self.tokens(keyword, 1)?;
+
+        self.parse(keyword, &tokens[0])
+    }
+
+    /// Reads the header line of a layer.
+    fn entry(&mut self) -> Result<Entry, TextFormatError> {
+        let line = self.next()?;
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        match tokens.as_slice() {
+            ["dense", n_inputs, n_outputs] => Ok(Entry::Dense(
+                self.parse("dense", n_inputs)?,
+                self.parse("dense", n_outputs)?,
+            )),
+            ["layer", name, config @ ..] => Ok(Entry::Layer(
+                name.to_string(),
+                config
+                    .iter()
+                    .map(|value| self.parse("layer", value))
+                    .collect::<Result<_, _>>()?,
+            )),
+            _ => Err(self.error("expected dense or layer".to_owned())),
+        }
+    }
+
+    /// Reads the values of the tensors after the keyword, which is omitted if
+    /// there are no tensors.
+    fn tensors<T: FromStr>(
+        &mut self,
+        keyword: &str,
+        tensors: Vec<&mut ArrayD<T>>,
+    ) -> Result<(), TextFormatError> {
+        if tensors.is_empty() {
+            return Ok(());
+        }
+
+        self.keyword(keyword)?;
+        for tensor in tensors {
+            let values = self.values(tensor.len())?;
+            *tensor = ArrayD::from_shape_vec(tensor.shape(), values).unwrap();
+        }
+
+        Ok(())
+    }
+
+    fn values<T: FromStr>(&mut self, n: usize) -> Result<Vec<T>, TextFormatError> {
+        let line = self.next()?;
+        let values = line
+            .split_whitespace()
+            .map(|v| v.parse().ok())
+            .collect::<Option<Vec<T>>>()
+            .ok_or_else(|| self.error("invalid value".to_owned()))?;
+
+        if values.len() != n {
+            return Err(self.error(format!("expected {} values, found {}", n, values.len())));
+        }
+
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{initializers::RandomDistr, layers::Dropout};
+
+    fn model() -> Model {
+        let mut model = Model::new(0.015);
+        model.add_layer((3, 4), RandomDistr::normal());
+        model.add_layer((4, 2), RandomDistr::normal());
+        model.set_batch_size(8);
+        model
+    }
+
+    fn write(model: &Model) -> String {
+        let mut buffer = vec![];
+        write_model(model, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    fn read(text: &str) -> Result<Model, TextFormatError> {
+        read_model(text.as_bytes())
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let model = model();
+        let loaded = read(&write(&model)).unwrap();
+
+        assert_eq!(model.weights(), loaded.weights());
+        assert_eq!(model.biases(), loaded.biases());
+        assert_eq!(0.015, loaded.learning_rate());
+        assert_eq!(8, loaded.batch_size());
+    }
+
+    #[test]
+    fn test_header() {
+        let text = write(&model());
+        let lines: Vec<&str> = text.lines().take(7).collect();
+
+        assert_eq!(
+            vec![
+                "robit-model 1",
+                "activation relu",
+                "loss mean_squared_error",
+                "learning_rate 0.015",
+                "batch_size 8",
+                "layers 2",
+                "dense 3 4",
+            ],
+            lines
+        );
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let text = write(&model()).replacen("robit-model 1", "robit-model 2", 1);
+
+        assert!(matches!(
+            read(&text),
+            Err(TextFormatError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[test]
+    fn test_activation_mismatch() {
+        let text = write(&model()).replacen("activation relu", "activation sigmoid", 1);
+        let err = read(&text).err().unwrap();
+
+        assert!(matches!(err, TextFormatError::Incompatible(_)));
+        assert_eq!("expected activation relu, found sigmoid", err.to_string());
+    }
+
+    #[test]
+    fn test_shape_mismatch() {
+        let text = write(&model()).replacen("dense 4 2", "dense 5 2", 1);
+
+        assert!(matches!(
+            read(&text),
+            Err(TextFormatError::InvalidLine { line: 14, .. })
+        ));
+    }
+
+    #[test]
+    fn test_layers() {
+        let mut model = model();
+        model.add(Dropout::new(0.25).unwrap());
+
+        let text = write(&model);
+        assert!(text.contains("layers 3\n"));
+        assert!(text.ends_with("layer dropout 0.25\n"));
+
+        let loaded = read(&text).unwrap();
+        let layers: Vec<_> = loaded
+            .layers()
+            .map(|layer| (layer.name(), layer.config()))
+            .collect();
+
+        assert_eq!(vec![("dropout", vec![0.25])], layers);
+        assert_eq!(model.weights(), loaded.weights());
+    }
+
+    #[test]
+    fn test_unknown_layer() {
+        let mut model = model();
+        model.add(Dropout::new(0.25).unwrap());
+
+        let text = write(&model).replacen("layer dropout", "layer unknown", 1);
+
+        assert!(matches!(
+            read(&text),
+            Err(TextFormatError::InvalidLine { message, .. }) if message == "invalid layer unknown"
+        ));
+    }
+
+    #[test]
+    fn test_oversized_counts() {
+        let text = write(&model()).replacen("layers 2", "layers 18446744073709551615", 1);
+        assert!(matches!(
+            read(&text),
+            Err(TextFormatError::UnexpectedEof { .. })
+        ));
+
+        let text = write(&model()).replacen("dense 3 4", "dense 4294967296 4294967296", 1);
+        let err = read(&text).err().unwrap();
+        assert!(matches!(err, TextFormatError::InvalidLine { line: 7, .. }));
+        assert!(err.to_string().contains("too large"));
+
+        for config in ["100000000000 100000000000", "1000000 1000000"] {
+            let text = write(&model()).replacen(
+                "layers 2\n",
+                &format!("layers 3\nlayer embedding {} -1 0\n", config),
+                1,
+            );
+            assert!(matches!(
+                read(&text),
+                Err(TextFormatError::InvalidLine { message, .. })
+                    if message == "invalid layer embedding"
+            ));
+        }
+    }
+
+    #[test]
+    fn test_truncated() {
+        let text = write(&model());
+        let err = read(&text[..text.len() / 2]).err().unwrap();
+
+        assert!(matches!(
+            err,
+            TextFormatError::UnexpectedEof { .. } | TextFormatError::InvalidLine { .. }
+        ));
+    }
+}
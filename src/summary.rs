@@ -0,0 +1,187 @@
+use std::fmt::{self, Display};
+
+/// Description of a single layer in a [Summary].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerSummary {
+    /// Name of the layer, unique within the model, e.g. `dense_0`.
+    pub name: String,
+
+    /// Type of the layer, e.g. `Dense`.
+    pub kind: String,
+
+    /// Shape of a single input sample, without the batch dimension. Empty if
+    /// the shape is not known because the model does not start with a dense
+    /// layer.
+    pub input_shape: Vec<usize>,
+
+    /// Shape of a single output sample, without the batch dimension. Empty if
+    /// the shape is not known, like [LayerSummary::input_shape].
+    pub output_shape: Vec<usize>,
+
+    /// Name of the activation function applied to the output, if any.
+    pub activation: Option<String>,
+
+    /// Number of parameters updated by the optimizer.
+    pub trainable_params: usize,
+
+    /// Number of parameters that are not updated by the optimizer, e.g.
+    /// running statistics.
+    pub non_trainable_params: usize,
+}
+
+impl LayerSummary {
+    pub fn params(&self) -> usize {
+        self.trainable_params + self.non_trainable_params
+    }
+}
+
+/// Overview of the layers and parameters of a model, returned by
+/// [Model::summary].
+///
+/// [Model::summary]: crate::Model::summary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Summary {
+    pub layers: Vec<LayerSummary>,
+
+    /// Size of a single parameter value in bytes.
+    pub value_size: usize,
+}
+
+impl Summary {
+    pub fn trainable_params(&self) -> usize {
+        self.layers.iter().map(|l| l.trainable_params).sum()
+    }
+
+    pub fn non_trainable_params(&self) -> usize {
+        self.layers.iter().map(|l| l.non_trainable_params).sum()
+    }
+
+    pub fn total_params(&self) -> usize {
+        self.trainable_params() + self.non_trainable_params()
+    }
+
+    /// Memory occupied by the parameter values in bytes.
+    pub fn memory(&self) -> usize {
+        self.total_params() * self.value_size
+    }
+}
+
+/// Formats a shape with the batch dimension as `None`, e.g. `(None, 784)`,
+/// or `?` if the shape is not known.
+fn shape(shape: &[usize]) -> String {
+    if shape.is_empty() {
+        return "?".to_owned();
+    }
+
+    let dims: Vec<String> = shape.iter().map(|d| d.to_string()).collect();
+
+    format!("(None, {})", dims.join(", "))
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header = ["Layer (type)", "Input shape", "Output shape", "Activation", "Params"];
+        let rows: Vec<[String; 5]> = self
+            .layers
+            .iter()
+            .map(|l| {
+                [
+                    format!("{} ({})", l.name, l.kind),
+                    shape(&l.input_shape),
+                    shape(&l.output_shape),
+                    l.activation.clone().unwrap_or_else(|| "-".to_owned()),
+                    l.params().to_string(),
+                ]
+            })
+            .collect();
+
+        let mut widths = header.map(str::len);
+        for row in rows.iter() {
+            for (width, cell) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(cell.len());
+            }
+        }
+
+        let line_width = widths.iter().sum::<usize>() + 2 * (widths.len() - 1);
+        let write_row = |f: &mut fmt::Formatter<'_>, row: &[&str]| {
+            for (i, cell) in row.iter().enumerate() {
+                if i == row.len() - 1 {
+                    write!(f, "{:>width$}", cell, width = widths[i])?;
+                } else {
+                    write!(f, "{:<width$}  ", cell, width = widths[i])?;
+                }
+            }
+            writeln!(f)
+        };
+
+        write_row(f, &header)?;
+        writeln!(f, "{}", "=".repeat(line_width))?;
+
+        for row in rows.iter() {
+            write_row(f, &row.each_ref().map(String::as_str))?;
+        }
+
+        writeln!(f, "{}", "=".repeat(line_width))?;
+        writeln!(f, "Total params: {}", self.total_params())?;
+        writeln!(f, "Trainable params: {}", self.trainable_params())?;
+        writeln!(f, "Non-trainable params: {}", self.non_trainable_params())?;
+        write!(f, "Parameter memory: {} bytes", self.memory())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary() -> Summary {
+        Summary {
+            layers: vec![
+                LayerSummary {
+                    name: "dense_0".to_owned(),
+                    kind: "Dense".to_owned(),
+                    input_shape: vec![784],
+                    output_shape: vec![32],
+                    activation: Some("relu".to_owned()),
+                    trainable_params: 25120,
+                    non_trainable_params: 0,
+                },
+                LayerSummary {
+                    name: "norm".to_owned(),
+                    kind: "BatchNorm1d".to_owned(),
+                    input_shape: vec![32],
+                    output_shape: vec![32],
+                    activation: None,
+                    trainable_params: 64,
+                    non_trainable_params: 64,
+                },
+            ],
+            value_size: 4,
+        }
+    }
+
+    #[test]
+    fn test_totals() {
+        let summary = summary();
+
+        assert_eq!(25184, summary.trainable_params());
+        assert_eq!(64, summary.non_trainable_params());
+        assert_eq!(25248, summary.total_params());
+        assert_eq!(100992, summary.memory());
+    }
+
+    #[test]
+    fn test_display() {
+        let expected = "\
+Layer (type)        Input shape  Output shape  Activation  Params
+=================================================================
+dense_0 (Dense)     (None, 784)  (None, 32)    relu         25120
+norm (BatchNorm1d)  (None, 32)   (None, 32)    -              128
+=================================================================
+Total params: 25248
+Trainable params: 25184
+Non-trainable params: 64
+Parameter memory: 100992 bytes";
+
+        assert_eq!(expected, summary().to_string());
+    }
+}
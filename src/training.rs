@@ -0,0 +1,308 @@
+use std::collections::BTreeMap;
+
+use ndarray::{Array, Array2, Axis, Ix2, RemoveAxis, Slice};
+
+use crate::{metrics::Metric, Error, Result};
+
+type Dataset<T, D> = (Array<T, D>, Array2<T>);
+
+/// Training data followed by the optional validation data.
+type Split<T, D> = (Array<T, D>, Array2<T>, Option<Dataset<T, D>>);
+
+/// Configuration of a training run started with [Model::fit_epochs], for
+/// inputs with the dimension `D`.
+///
+/// # Examples
+///
+/// ```
+/// use robit::FitConfig;
+///
+/// let config: FitConfig<f32> = FitConfig {
+///     epochs: 20,
+///     validation_split: Some(0.1),
+///     ..Default::default()
+/// };
+/// ```
+///
+/// [Model::fit_epochs]: crate::Model::fit_epochs
+pub struct FitConfig<T = f64, D = Ix2> {
+    /// Number of passes over the training data.
+    pub epochs: usize,
+
+    /// Data the model is evaluated on at the end of every epoch. Takes
+    /// precedence over `validation_split`.
+    pub validation_data: Option<Dataset<T, D>>,
+
+    /// Fraction of the training data that is held back for validation. The
+    /// samples are taken from the end of the data, before any training. Both
+    /// parts must keep at least one sample.
+    pub validation_split: Option<f64>,
+
+    /// Metrics that are computed at the end of every epoch, in addition to
+    /// the loss.
+    pub metrics: Vec<Box<dyn Metric<T>>>,
+
+    /// Whether the training data is shuffled before every epoch, using the
+    /// random number generator of the model.
+    pub shuffle: bool,
+}
+
+impl<T, D> Default for FitConfig<T, D> {
+    fn default() -> Self {
+        Self {
+            epochs: 1,
+            validation_data: None,
+            validation_split: None,
+            metrics: vec![],
+            shuffle: false,
+        }
+    }
+}
+
+impl<T: Clone, D: RemoveAxis> FitConfig<T, D> {
+    /// Splits `x` and `y` into training and validation data according to the
+    /// configuration.
+    pub(crate) fn split(&self, x: &Array<T, D>, y: &Array2<T>) -> Result<Split<T, D>> {
+        if let Some((x_val, y_val)) = &self.validation_data {
+            return Ok((x.clone(), y.clone(), Some((x_val.clone(), y_val.clone()))));
+        }
+
+        match self.validation_split {
+            Some(split) if !(0.0..1.0).contains(&split) => Err(Error::invalid_hyperparameter(
+                "validation_split",
+                format!("{} is not in [0, 1)", split),
+            )),
+            Some(split) if split > 0.0 => {
+                let n_samples = x.shape()[0];
+                let n_train = n_samples - (n_samples as f64 * split).ceil() as usize;
+                if n_train == 0 || n_train == n_samples {
+                    return Err(Error::invalid_hyperparameter(
+                        "validation_split",
+                        format!(
+                            "{} of {} samples leaves no training or validation samples",
+                            split, n_samples
+                        ),
+                    ));
+                }
+                let train = Slice::from(..n_train);
+                let validation = Slice::from(n_train..);
+
+                Ok((
+                    x.slice_axis(Axis(0), train).to_owned(),
+                    y.slice_axis(Axis(0), train).to_owned(),
+                    Some((
+                        x.slice_axis(Axis(0), validation).to_owned(),
+                        y.slice_axis(Axis(0), validation).to_owned(),
+                    )),
+                ))
+            }
+            _ => Ok((x.clone(), y.clone(), None)),
+        }
+    }
+}
+
+/// Record of a training run returned by [Model::fit_epochs].
+///
+/// [Model::fit_epochs]: crate::Model::fit_epochs
+#[derive(Debug, Clone, PartialEq)]
+pub struct History<T = f64> {
+    /// Mean training loss of every epoch.
+    pub loss: Vec<T>,
+
+    /// Validation loss of every epoch, empty when training without
+    /// validation data.
+    pub val_loss: Vec<T>,
+
+    /// Values of the tracked metrics of every epoch, keyed by their name.
+    /// Validation values are prefixed with `val_`.
+    pub metrics: BTreeMap<String, Vec<T>>,
+}
+
+impl<T> History<T> {
+    pub fn new() -> Self {
+        Self {
+            loss: vec![],
+            val_loss: vec![],
+            metrics: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the values of the tracked metric with the given name.
+    pub fn metric(&self, name: &str) -> Option<&[T]> {
+        self.metrics.get(name).map(Vec::as_slice)
+    }
+
+    pub(crate) fn push_metric(&mut self, name: &str, value: T) {
+        self.metrics.entry(name.to_owned()).or_default().push(value);
+    }
+
+    /// Number of epochs recorded.
+    pub fn epochs(&self) -> usize {
+        self.loss.len()
+    }
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// SplitMix64 random number generator whose state is a single number, so it
+/// can be stored in checkpoints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub(crate) fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a uniformly distributed number in `[0, 1)`.
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a random permutation of `0..n`.
+    pub(crate) fn permutation(&mut self, n: usize) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..n).collect();
+
+        for i in (1..n).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            indices.swap(i, j);
+        }
+
+        indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::arr2;
+
+    #[test]
+    fn test_split_none() {
+        let x = arr2(&[[1.0], [2.0], [3.0], [4.0]]);
+        let y = arr2(&[[0.0], [1.0], [0.0], [1.0]]);
+
+        let (x_train, y_train, val) = FitConfig::default().split(&x, &y).unwrap();
+
+        assert_eq!(x, x_train);
+        assert_eq!(y, y_train);
+        assert!(val.is_none());
+    }
+
+    #[test]
+    fn test_split_fraction() {
+        let x = arr2(&[[1.0], [2.0], [3.0], [4.0]]);
+        let y = arr2(&[[0.0], [1.0], [0.0], [1.0]]);
+
+        let config = FitConfig {
+            validation_split: Some(0.25),
+            ..Default::default()
+        };
+        let (x_train, _, val) = config.split(&x, &y).unwrap();
+        let (x_val, y_val) = val.unwrap();
+
+        assert_eq!(arr2(&[[1.0], [2.0], [3.0]]), x_train);
+        assert_eq!(arr2(&[[4.0]]), x_val);
+        assert_eq!(arr2(&[[1.0]]), y_val);
+    }
+
+    #[test]
+    fn test_validation_data_takes_precedence() {
+        let x = arr2(&[[1.0], [2.0]]);
+        let y = arr2(&[[0.0], [1.0]]);
+
+        let config = FitConfig {
+            validation_data: Some((arr2(&[[5.0]]), arr2(&[[1.0]]))),
+            validation_split: Some(0.5),
+            ..Default::default()
+        };
+        let (x_train, _, val) = config.split(&x, &y).unwrap();
+
+        assert_eq!(x, x_train);
+        assert_eq!(arr2(&[[5.0]]), val.unwrap().0);
+    }
+
+    #[test]
+    fn test_split_invalid() {
+        let x = arr2(&[[1.0], [2.0]]);
+
+        let config = FitConfig {
+            validation_split: Some(1.0),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            config.split(&x, &x),
+            Err(Error::InvalidHyperparameter {
+                name: "validation_split",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_split_empty() {
+        let config = FitConfig {
+            validation_split: Some(0.5),
+            ..Default::default()
+        };
+
+        for x in [arr2(&[[1.0]]), Array2::zeros((0, 1))] {
+            assert!(matches!(
+                config.split(&x, &x),
+                Err(Error::InvalidHyperparameter {
+                    name: "validation_split",
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_rng() {
+        let mut rng = Rng::new(1234567);
+
+        assert_eq!(6457827717110365317, rng.next_u64());
+        assert_eq!(3203168211198807973, rng.next_u64());
+
+        let mut restored = Rng::new(rng.state());
+        assert_eq!(rng.next_u64(), restored.next_u64());
+    }
+
+    #[test]
+    fn test_next_f64() {
+        let mut rng = Rng::new(42);
+
+        assert!((0..1000)
+            .map(|_| rng.next_f64())
+            .all(|x| (0.0..1.0).contains(&x)));
+    }
+
+    #[test]
+    fn test_permutation() {
+        let mut indices = Rng::new(0).permutation(10);
+        indices.sort();
+
+        assert_eq!((0..10).collect::<Vec<_>>(), indices);
+        assert_ne!(Rng::new(0).permutation(10), Rng::new(1).permutation(10));
+    }
+}